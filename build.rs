@@ -0,0 +1,150 @@
+//! Generates the AnimationID enum and its Aseprite tag mappings from the atlas
+//! JSON exported by Aseprite. The enum is derived from the atlas so that an
+//! artist adding, renaming, or removing a tag surfaces as a compile error
+//! wherever the code references the ID instead of as a startup failure.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+static ATLAS_PATH: &str = "src/atlas/atlas.json";
+
+/// Tags whose AnimationID names deviate from the PascalCase convention. Most
+/// tags map by convention, "file-stateOrVariant" to FileStateOrVariant, so
+/// only irregular names and tags that aren't valid identifiers are listed.
+static IRREGULAR_IDS: &[(&str, &str)] = &[
+  ("arrow-right", "ArrowHorizontal"),
+  ("arrow-up", "ArrowVertical"),
+  ("arrow-upRight", "ArrowDiagonal"),
+  ("backpackerIcon-idle", "PlayerStatusIdle"),
+  ("backpackerIcon-walk", "PlayerStatusWalk"),
+  ("path-/", "PathNe"),
+  ("path->", "PathCornerE"),
+  ("path-^", "PathCornerN"),
+  ("uiReticle", "UiCursorReticle"),
+];
+
+fn main() {
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed={}", ATLAS_PATH);
+
+  let json = fs::read_to_string(ATLAS_PATH)
+    .unwrap_or_else(|error| panic!("{} unreadable: {}.", ATLAS_PATH, error));
+  let atlas: Value = serde_json::from_str(&json)
+    .unwrap_or_else(|error| panic!("{} malformed: {}.", ATLAS_PATH, error));
+  let ids = parse_ids(&atlas);
+
+  let out_dir = env::var("OUT_DIR").expect("OUT_DIR unset.");
+  fs::write(Path::new(&out_dir).join("animation_id.rs"), generate(&ids))
+    .expect("animation_id.rs not written.");
+}
+
+/// Returns (tag, ID) pairs ordered by ID.
+fn parse_ids(atlas: &Value) -> Vec<(String, String)> {
+  let irregular: HashMap<_, _> = IRREGULAR_IDS.iter().cloned().collect();
+  let tags = atlas["meta"]["frameTags"]
+    .as_array()
+    .unwrap_or_else(|| panic!("{} frameTags missing.", ATLAS_PATH));
+
+  let mut tag_by_id: HashMap<String, String> = HashMap::new();
+  for tag in tags {
+    let tag = tag["name"]
+      .as_str()
+      .unwrap_or_else(|| panic!("{} frameTag name missing.", ATLAS_PATH))
+      .to_string();
+    let id = irregular
+      .get(tag.as_str())
+      .map_or_else(|| to_pascal_case(&tag), |id| id.to_string());
+    if !is_identifier(&id) {
+      panic!(
+        "Tag \"{}\" converts to invalid AnimationID \"{}\"; add it to \
+         IRREGULAR_IDS.",
+        tag, id
+      );
+    }
+    if let Some(duplicate) = tag_by_id.insert(id.clone(), tag.clone()) {
+      panic!(
+        "Tags \"{}\" and \"{}\" both convert to AnimationID {}.",
+        duplicate, tag, id
+      );
+    }
+  }
+
+  for (tag, _) in IRREGULAR_IDS {
+    if !tag_by_id.values().any(|val| val == tag) {
+      panic!("Irregular tag \"{}\" missing from {}.", tag, ATLAS_PATH);
+    }
+  }
+
+  let mut ids: Vec<_> =
+    tag_by_id.into_iter().map(|(id, tag)| (tag, id)).collect();
+  ids.sort_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs));
+  ids
+}
+
+/// Converts a tag like "cloud-largeShadow" to "CloudLargeShadow".
+fn to_pascal_case(tag: &str) -> String {
+  tag
+    .split(|letter: char| !letter.is_ascii_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(|word| {
+      let mut chars = word.chars();
+      let first = chars.next().unwrap().to_ascii_uppercase();
+      first.to_string() + chars.as_str()
+    })
+    .collect()
+}
+
+fn is_identifier(id: &str) -> bool {
+  id.chars().next().map_or(false, |letter| letter.is_ascii_uppercase())
+    && id.chars().all(|letter| letter.is_ascii_alphanumeric())
+}
+
+fn generate(ids: &[(String, String)]) -> String {
+  let mut variants = String::new();
+  let mut from_tag = String::new();
+  let mut to_tag = String::new();
+  for (tag, id) in ids {
+    variants += &format!("  {},\n", id);
+    from_tag += &format!("      {:?} => Some(Self::{}),\n", tag, id);
+    to_tag += &format!("      Self::{} => {:?},\n", id, tag);
+  }
+
+  format!(
+    "\
+/// The Atlas Animation identifier for all Animations in the Atlas. IDs are used
+/// to reference immutable image source properties, such as dimensions and
+/// animation duration, from the Atlas. The tag convention used here is
+/// <file stem>-<state or variant or ancillary image>.
+///
+/// Generated by build.rs from {path}.
+#[derive(
+  Debug, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize, Clone, Copy,
+)]
+pub enum AnimationID {{
+{variants}}}
+
+impl AnimationID {{
+  /// Returns the ID for an Aseprite tag or None if the tag is not in the
+  /// atlas.
+  pub fn from_tag(tag: &str) -> Option<Self> {{
+    match tag {{
+{from_tag}      _ => None,
+    }}
+  }}
+
+  /// Returns the Aseprite tag for the ID.
+  pub fn tag(&self) -> &'static str {{
+    match self {{
+{to_tag}    }}
+  }}
+}}
+",
+    path = ATLAS_PATH,
+    variants = variants,
+    from_tag = from_tag,
+    to_tag = to_tag
+  )
+}
@@ -3,289 +3,8 @@ use std::fmt;
 use std::fmt::{Display, Formatter};
 use strum_macros::EnumIter;
 
-/// The Atlas Animation identifier for all Animations in the Atlas. IDs are used
-/// to reference immutable image source properties, such as dimensions and
-/// animation duration, from the Atlas. The tag convention used here is
-/// <file stem>-<state or variant or ancillary image>.
-#[derive(
-  Debug, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize, Clone, Copy,
-)]
-pub enum AnimationID {
-  AppleTree,
-  AppleTreeShadow,
-  ArrowDiagonal,
-  ArrowHorizontal,
-  ArrowVertical,
-  BackpackerIdleDown,
-  BackpackerIdleLeft,
-  BackpackerIdleRight,
-  BackpackerIdleUp,
-  BackpackerMeleeRight,
-  BackpackerWalkDown,
-  BackpackerWalkHorizontalShadow,
-  BackpackerWalkLeft,
-  BackpackerWalkRight,
-  BackpackerWalkUp,
-  BackpackerWalkVerticalShadow,
-  Bee,
-  BeeBlood,
-  BeeDead,
-  BeeShadow,
-  BirdFly,
-  BirdRest,
-  BirdRise,
-  BunnyBlood,
-  Bunny,
-  BunnyDead,
-  BunnyShadow,
-  Bush,
-  BushShadow,
-  Cattails,
-  CloudLarge,
-  CloudLargeShadow,
-  CloudMedium,
-  CloudMediumShadow,
-  CloudRain,
-  CloudRainPuddle,
-  CloudRainSplash,
-  CloudRainSprinkle,
-  Clover0x0,
-  Clover0x1,
-  Clover0x2,
-  Clover0x3,
-  Clover0x4,
-  Clover1x0,
-  Clover1x1,
-  Clover1x2,
-  Clover1x3,
-  Clover1x4,
-  Conifer,
-  ConiferShadow,
-  EggCompartmentDrawer,
-  EggCompartmentUnit,
-  EggCompartmentUnitPressed,
-  Flag,
-  FlagShadow,
-  Flower,
-  FlowerShadow,
-  FrogEat,
-  FrogIdle,
-  FrogIdleShadow,
-  FrogLeap,
-  Grass00,
-  Grass01,
-  Grass02,
-  Grass03,
-  Grass04,
-  Grass05,
-  Grass06,
-  Grass07,
-  Grass08,
-  Grass09,
-  Grass10,
-  Grass11,
-  Grass12,
-  Grass13,
-  Grass14,
-  Grass15,
-  GrassShadow,
-  HealthBauble,
-  ItemApple,
-  ItemAppleShadow,
-  Lattice,
-  LifeCounter,
-  MeleeButtonDisabled,
-  MeleeButtonEnabled,
-  MemFont000,
-  MemFont001,
-  MemFont002,
-  MemFont003,
-  MemFont004,
-  MemFont005,
-  MemFont006,
-  MemFont007,
-  MemFont008,
-  MemFont009,
-  MemFont010,
-  MemFont011,
-  MemFont012,
-  MemFont013,
-  MemFont014,
-  MemFont015,
-  MemFont016,
-  MemFont017,
-  MemFont018,
-  MemFont019,
-  MemFont020,
-  MemFont021,
-  MemFont022,
-  MemFont023,
-  MemFont024,
-  MemFont025,
-  MemFont026,
-  MemFont027,
-  MemFont028,
-  MemFont029,
-  MemFont030,
-  MemFont031,
-  MemFont032,
-  MemFont033,
-  MemFont034,
-  MemFont035,
-  MemFont036,
-  MemFont037,
-  MemFont038,
-  MemFont039,
-  MemFont040,
-  MemFont041,
-  MemFont042,
-  MemFont043,
-  MemFont044,
-  MemFont045,
-  MemFont046,
-  MemFont047,
-  MemFont048,
-  MemFont049,
-  MemFont050,
-  MemFont051,
-  MemFont052,
-  MemFont053,
-  MemFont054,
-  MemFont055,
-  MemFont056,
-  MemFont057,
-  MemFont058,
-  MemFont059,
-  MemFont060,
-  MemFont061,
-  MemFont062,
-  MemFont063,
-  MemFont064,
-  MemFont065,
-  MemFont066,
-  MemFont067,
-  MemFont068,
-  MemFont069,
-  MemFont070,
-  MemFont071,
-  MemFont072,
-  MemFont073,
-  MemFont074,
-  MemFont075,
-  MemFont076,
-  MemFont077,
-  MemFont078,
-  MemFont079,
-  MemFont080,
-  MemFont081,
-  MemFont082,
-  MemFont083,
-  MemFont084,
-  MemFont085,
-  MemFont086,
-  MemFont087,
-  MemFont088,
-  MemFont089,
-  MemFont090,
-  MemFont091,
-  MemFont092,
-  MemFont093,
-  MemFont094,
-  MemFont095,
-  MemFont096,
-  MemFont097,
-  MemFont098,
-  MemFont099,
-  MemFont100,
-  MemFont101,
-  MemFont102,
-  MemFont103,
-  MemFont104,
-  MemFont105,
-  MemFont106,
-  MemFont107,
-  MemFont108,
-  MemFont109,
-  MemFont110,
-  MemFont111,
-  MemFont112,
-  MemFont113,
-  MemFont114,
-  MemFont115,
-  MemFont116,
-  MemFont117,
-  MemFont118,
-  MemFont119,
-  MemFont120,
-  MemFont121,
-  MemFont122,
-  MemFont123,
-  MemFont124,
-  MemFont125,
-  MemFont126,
-  MemFont127,
-  MonumentMedium,
-  MonumentMediumShadow,
-  MonumentSmall,
-  MonumentSmallShadow,
-  Moon,
-  Mountain,
-  MountainShadow,
-  Oddoid,
-  PaletteBlack,
-  PaletteBlue,
-  PaletteDarkGreen,
-  PaletteDarkRed,
-  PaletteGreen,
-  PaletteGrey,
-  PaletteLightBlue,
-  PaletteLightGreen,
-  PaletteLightGrey,
-  PaletteOrange,
-  PalettePaleGreen,
-  PaletteRed,
-  PaletteTransparent,
-  PaletteWhite,
-  PathCornerE,
-  PathCornerN,
-  PathNe,
-  Pig,
-  PigShadow,
-  PlayerStatusIdle,
-  PlayerStatusWalk,
-  Pond,
-  RoseBauble,
-  SnakeShadow,
-  Snake,
-  SubshrubShadow,
-  Subshrub,
-  TreeLargeBare,
-  TreeLargeShadow,
-  TreeLarge,
-  TreeSmallShadow,
-  TreeSmall,
-  UiButtonBase,
-  UiButtonCreate,
-  UiButtonDecrement,
-  UiButtonDestroy,
-  UiButtonIncrement,
-  UiButtonMenu,
-  UiButtonPressed,
-  UiButtonToggleGrid,
-  UiCheckerboardBlackTransparent,
-  UiCheckerboardBlackWhite,
-  UiCheckerboardBlueGrey,
-  UiCursorHandPick,
-  UiCursorHandPoint,
-  UiCursorReticle,
-  UiDestinationMarker,
-  UiGrid,
-  UiSwitch,
-  UiWindowModeChart,
-  UiZoomMultiplierChart,
-  Water,
-  Wave,
-}
+// The AnimationID enum and its tag mappings are generated from atlas.json.
+include!(concat!(env!("OUT_DIR"), "/animation_id.rs"));
 
 impl Display for AnimationID {
   fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
@@ -295,10 +14,25 @@ impl Display for AnimationID {
 
 #[cfg(test)]
 mod test {
+  use super::*;
   use crate::atlas;
+  use strum::IntoEnumIterator;
 
   #[test]
   fn ids_are_coherent() {
     atlas::parse(&include_json!("atlas.json").unwrap()).unwrap();
   }
+
+  #[test]
+  fn tags_roundtrip() {
+    for id in AnimationID::iter() {
+      assert_eq!(AnimationID::from_tag(id.tag()), Some(id));
+    }
+  }
+
+  #[test]
+  fn irregular_tags() {
+    assert_eq!(AnimationID::from_tag("path->"), Some(AnimationID::PathCornerE));
+    assert_eq!(AnimationID::PlayerStatusIdle.tag(), "backpackerIcon-idle");
+  }
 }
@@ -10,7 +10,7 @@ use std::convert::TryInto;
 pub type AnimatorPeriod = i32;
 
 /// Record and update an Animation's state.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Animator {
   /// The current cycle offset used to track oscillation state and calculate
   /// index.
@@ -21,23 +21,30 @@ pub struct Animator {
   /// direction. This value should be carried over from each call with the
   /// current time step added, and zeroed on manual cel change.
   exposure: Millis,
+
+  /// The number of cels whose exposure elapsed since the last reset or cel
+  /// change. Only tracked for animations with a finite repeat.
+  played: u32,
 }
 
 impl Animator {
   pub fn new(period: AnimatorPeriod, exposure: Millis) -> Self {
-    Self { period, exposure }
+    Self { period, exposure, played: 0 }
   }
 
   /// Reset the animation and exposure.
   pub fn reset(&mut self) {
     self.period = 0;
     self.exposure = 0.;
+    self.played = 0;
   }
 
-  /// Change the animation cel and reset the exposure.
+  /// Change the animation cel and reset the exposure. A finite animation
+  /// replays its repeats from the new cel.
   pub fn set(&mut self, period: AnimatorPeriod) {
     self.period = period;
     self.exposure = 0.;
+    self.played = 0;
   }
 
   /// Returns the current animation cel for the Animator's period.
@@ -47,31 +54,93 @@ impl Animator {
 
   /// Returns the current animation cel index for the Animator's period.
   pub fn index(&self, animation: &Animation) -> Option<usize> {
-    if animation.cels.is_empty() {
-      return None;
-    }
-    let len: AnimatorPeriod = animation.cels.len().try_into().ok()?;
-    (self.period % len).abs().try_into().ok()
+    period_index(animation, self.period)
   }
 
   /// Apply the time since last frame was shown, possibly advancing the
-  /// animation period.
-  pub fn animate(&mut self, animation: &Animation, exposure: Millis) {
-    if animation.cels.len() < 2 || animation.duration == 0. {
-      return;
+  /// animation period. Returns the events that occurred in order except that
+  /// skipped complete cycles are reported first, as one event, and without
+  /// their Cels.
+  pub fn animate(
+    &mut self,
+    animation: &Animation,
+    exposure: Millis,
+  ) -> Vec<AnimatorEvent> {
+    let mut events = Vec::new();
+    let len = animation.cels.len();
+    // The total number of cels played by a finite animation.
+    let finite =
+      animation.repeat.map(|repeat| animation.direction.plays(len, repeat));
+    let ended = finite.map_or(len < 2, |plays| self.played >= plays);
+    if ended || animation.duration == 0. {
+      return events;
     }
 
-    // Avoid unnecessary iterations by skipping complete animation cycles.
-    // Modulo by infinity is the number.
-    self.exposure = (self.exposure + exposure) % animation.duration;
+    let mut exposure = self.exposure + exposure;
+    if finite.is_none() {
+      // Avoid unnecessary iterations by skipping complete animation cycles.
+      // Division and modulo by infinity are zero and the number respectively.
+      let cycles = (exposure / animation.duration).floor() as usize;
+      if cycles > 0 {
+        events.push(AnimatorEvent::CycleCompleted(cycles));
+      }
+      exposure %= animation.duration;
+    }
+    self.exposure = exposure;
+    let start = period_index(animation, 0).unwrap();
     while self.exposure >= self.cel(animation).unwrap().duration {
       self.exposure -= self.cel(animation).unwrap().duration;
-      self.period =
-        animation.direction.advance(self.period, animation.cels.len());
+      if let Some(plays) = finite {
+        self.played += 1;
+        if self.played == plays {
+          events.push(AnimatorEvent::Ended);
+          break;
+        }
+      }
+      self.period = animation.direction.advance(self.period, len);
+
+      let index = self.index(animation).unwrap();
+      events.push(AnimatorEvent::CelEntered(index));
+      if index == start {
+        events.push(AnimatorEvent::CycleCompleted(1));
+      }
+      if animation.cels[index].duration.is_infinite() {
+        events.push(AnimatorEvent::Ended);
+      }
     }
+    events
   }
 }
 
+/// A notable change in Animator state reported by animate().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimatorEvent {
+  /// The Cel at the index became current.
+  CelEntered(usize),
+  /// The starting Cel was returned to this many times. Skipped cycles are
+  /// counted in one event so that a large exposure reports a single event.
+  CycleCompleted(usize),
+  /// An infinite duration final Cel was entered or the final Cel of a finite
+  /// animation elapsed. The animation will not advance further.
+  Ended,
+}
+
+/// Returns the animation cel index for the period.
+fn period_index(
+  animation: &Animation,
+  period: AnimatorPeriod,
+) -> Option<usize> {
+  if animation.cels.is_empty() {
+    return None;
+  }
+  let len: AnimatorPeriod = animation.cels.len().try_into().ok()?;
+  let index = (period % len).abs();
+  if animation.direction == Playback::PingPongReverse {
+    return (len - 1 - index).try_into().ok();
+  }
+  index.try_into().ok()
+}
+
 impl Playback {
   /// Returns the next period.
   fn advance(&self, period: AnimatorPeriod, len: usize) -> AnimatorPeriod {
@@ -81,8 +150,21 @@ impl Playback {
       Self::Forward => (period % AnimatorPeriod::max_value()) + 1,
       // An integer in the domain (-∞, len - 1].
       Self::Reverse => (period % AnimatorPeriod::min_value()) - 1 + len,
-      // An integer in the domain [2 - len, len - 1].
-      Self::PingPong => wrap(period - 1, 2 - len, len),
+      // An integer in the domain [2 - len, len - 1]. The reverse is mirrored
+      // by index().
+      Self::PingPong | Self::PingPongReverse => wrap(period - 1, 2 - len, len),
+    }
+  }
+
+  /// Returns the number of cels played when repeating the animation. Each
+  /// ping-pong traversal after the first shares the cel it turns on.
+  fn plays(&self, len: usize, repeat: u16) -> u32 {
+    let len: u32 = len.try_into().unwrap();
+    match self {
+      Self::Forward | Self::Reverse => len * u32::from(repeat),
+      Self::PingPong | Self::PingPongReverse => {
+        (len - 1) * u32::from(repeat) + 1
+      }
     }
   }
 }
@@ -94,13 +176,19 @@ mod test {
 
   #[test]
   fn reset() {
-    let cel =
-      Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![cel.clone(), cel.clone()],
       duration: 2.,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 1.5);
@@ -113,8 +201,13 @@ mod test {
 
   #[test]
   fn set() {
-    let cel =
-      Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![
@@ -126,6 +219,7 @@ mod test {
       ],
       duration: 2.,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 1.5);
@@ -138,13 +232,19 @@ mod test {
 
   #[test]
   fn animate_exposure_lt_duration() {
-    let cel =
-      Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![cel.clone(), cel.clone()],
       duration: 2.,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 0.5);
@@ -154,13 +254,19 @@ mod test {
 
   #[test]
   fn animate_exposure_eq_duration() {
-    let cel =
-      Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![cel.clone(), cel.clone()],
       duration: 2.,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 1.);
@@ -170,13 +276,19 @@ mod test {
 
   #[test]
   fn animate_exposure_gt_duration() {
-    let cel =
-      Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![cel.clone(), cel.clone()],
       duration: 2.,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 1.5);
@@ -188,14 +300,17 @@ mod test {
   fn animate_infinite_duration() {
     let cel = Cel {
       bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
       duration: f64::INFINITY,
       slices: vec![],
+      bodies: vec![],
     };
     let animation = Animation {
       size: XY::new(0, 0),
       cels: vec![cel.clone(), cel.clone()],
       duration: f64::INFINITY,
       direction: Playback::Forward,
+      repeat: None,
     };
     let mut animator = Animator::new(0, 0.);
     animator.animate(&animation, 1.5);
@@ -209,13 +324,19 @@ mod test {
       .iter()
       .enumerate()
       .for_each(|(i, &direction)| {
-        let cel =
-          Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+        let cel = Cel {
+          bounds: R16::new(0, 0, 0, 0),
+          trim: R16::new(0, 0, 0, 0),
+          duration: 1.,
+          slices: vec![],
+          bodies: vec![],
+        };
         let animation = Animation {
           size: XY::new(0, 0),
           cels: vec![cel.clone(), cel.clone()],
           duration: 2.,
           direction,
+          repeat: None,
         };
         let mut animator = Animator::new(0, 0.);
         animator.animate(&animation, 1.);
@@ -235,13 +356,19 @@ mod test {
       .iter()
       .enumerate()
       .for_each(|(i, &direction)| {
-        let cel =
-          Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+        let cel = Cel {
+          bounds: R16::new(0, 0, 0, 0),
+          trim: R16::new(0, 0, 0, 0),
+          duration: 1.,
+          slices: vec![],
+          bodies: vec![],
+        };
         let animation = Animation {
           size: XY::new(0, 0),
           cels: vec![cel.clone(), cel.clone()],
           duration: 2.,
           direction,
+          repeat: None,
         };
         let mut animator = Animator::new(0, 0.);
         animator.period = 1;
@@ -298,13 +425,19 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, period, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![cel.clone(), cel.clone(), cel.clone(), cel.clone()],
         duration: 4.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       animator.period = period;
@@ -333,8 +466,13 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![
@@ -346,6 +484,7 @@ mod test {
         ],
         duration: 5.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       let mut recording = Vec::new();
@@ -373,8 +512,13 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![
@@ -386,6 +530,7 @@ mod test {
         ],
         duration: 5.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       let mut recording = Vec::new();
@@ -431,8 +576,13 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![
@@ -444,6 +594,7 @@ mod test {
         ],
         duration: 5.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       let mut recording = Vec::new();
@@ -489,8 +640,13 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![
@@ -502,6 +658,7 @@ mod test {
         ],
         duration: 5.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       let mut recording = Vec::new();
@@ -547,8 +704,13 @@ mod test {
     .iter()
     .enumerate()
     .for_each(|(i, &(direction, expected))| {
-      let cel =
-        Cel { bounds: R16::new(0, 0, 0, 0), duration: 1., slices: vec![] };
+      let cel = Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      };
       let animation = Animation {
         size: XY::new(0, 0),
         cels: vec![
@@ -560,6 +722,7 @@ mod test {
         ],
         duration: 5.,
         direction,
+        repeat: None,
       };
       let mut animator = Animator::new(0, 0.);
       let mut recording = Vec::new();
@@ -576,4 +739,218 @@ mod test {
       );
     });
   }
+
+  #[test]
+  fn animate_events() {
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
+    let infinite = Cel { duration: f64::INFINITY, ..cel.clone() };
+    [
+      (
+        Playback::Forward,
+        vec![cel.clone(), cel.clone(), cel.clone()],
+        3.,
+        vec![
+          (1., vec![AnimatorEvent::CelEntered(1)]),
+          (1.5, vec![AnimatorEvent::CelEntered(2)]),
+          (
+            0.5,
+            vec![
+              AnimatorEvent::CelEntered(0),
+              AnimatorEvent::CycleCompleted(1),
+            ],
+          ),
+          (3., vec![AnimatorEvent::CycleCompleted(1)]),
+          (0.5, vec![]),
+        ],
+      ),
+      (
+        Playback::PingPong,
+        vec![cel.clone(), cel.clone(), cel.clone()],
+        4.,
+        vec![
+          (
+            3.,
+            vec![
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(2),
+              AnimatorEvent::CelEntered(1),
+            ],
+          ),
+          (
+            1.,
+            vec![
+              AnimatorEvent::CelEntered(0),
+              AnimatorEvent::CycleCompleted(1),
+            ],
+          ),
+        ],
+      ),
+      (
+        Playback::Forward,
+        vec![cel.clone(), cel.clone(), infinite],
+        f64::INFINITY,
+        vec![
+          (
+            2.,
+            vec![
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(2),
+              AnimatorEvent::Ended,
+            ],
+          ),
+          (100., vec![]),
+        ],
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (direction, cels, duration, steps))| {
+      let animation = Animation {
+        size: XY::new(0, 0),
+        cels: cels.clone(),
+        duration: *duration,
+        direction: *direction,
+        repeat: None,
+      };
+      let mut animator = Animator::new(0, 0.);
+      for (step, (exposure, expected)) in steps.iter().enumerate() {
+        assert_eq!(
+          &animator.animate(&animation, *exposure),
+          expected,
+          "Case {} step {} failed.",
+          i,
+          step
+        );
+      }
+    });
+  }
+
+  #[test]
+  fn animate_repeat() {
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
+    [
+      (
+        Playback::Forward,
+        6.,
+        vec![
+          (
+            5.,
+            vec![
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(2),
+              AnimatorEvent::CelEntered(0),
+              AnimatorEvent::CycleCompleted(1),
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(2),
+            ],
+            2,
+          ),
+          (1., vec![AnimatorEvent::Ended], 2),
+          (10., vec![], 2),
+        ],
+      ),
+      (
+        Playback::PingPong,
+        5.,
+        vec![(
+          10.,
+          vec![
+            AnimatorEvent::CelEntered(1),
+            AnimatorEvent::CelEntered(2),
+            AnimatorEvent::CelEntered(1),
+            AnimatorEvent::CelEntered(0),
+            AnimatorEvent::CycleCompleted(1),
+            AnimatorEvent::Ended,
+          ],
+          0,
+        )],
+      ),
+      (
+        Playback::PingPongReverse,
+        5.,
+        vec![
+          (
+            4.5,
+            vec![
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(0),
+              AnimatorEvent::CelEntered(1),
+              AnimatorEvent::CelEntered(2),
+              AnimatorEvent::CycleCompleted(1),
+            ],
+            2,
+          ),
+          (0.5, vec![AnimatorEvent::Ended], 2),
+        ],
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (direction, duration, steps))| {
+      let animation = Animation {
+        size: XY::new(0, 0),
+        cels: vec![cel.clone(), cel.clone(), cel.clone()],
+        duration: *duration,
+        direction: *direction,
+        repeat: Some(2),
+      };
+      let mut animator = Animator::new(0, 0.);
+      for (step, (exposure, expected, index)) in steps.iter().enumerate() {
+        assert_eq!(
+          &animator.animate(&animation, *exposure),
+          expected,
+          "Case {} step {} failed.",
+          i,
+          step
+        );
+        assert_eq!(
+          animator.index(&animation).unwrap(),
+          *index,
+          "Case {} step {} index failed.",
+          i,
+          step
+        );
+      }
+    });
+  }
+
+  #[test]
+  fn reset_repeat() {
+    let cel = Cel {
+      bounds: R16::new(0, 0, 0, 0),
+      trim: R16::new(0, 0, 0, 0),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![],
+    };
+    let animation = Animation {
+      size: XY::new(0, 0),
+      cels: vec![cel.clone(), cel.clone()],
+      duration: 2.,
+      direction: Playback::Forward,
+      repeat: Some(1),
+    };
+    let mut animator = Animator::new(0, 0.);
+    assert_eq!(
+      animator.animate(&animation, 2.),
+      vec![AnimatorEvent::CelEntered(1), AnimatorEvent::Ended]
+    );
+    animator.reset();
+    assert_eq!(
+      animator.animate(&animation, 1.),
+      vec![AnimatorEvent::CelEntered(1)]
+    );
+  }
 }
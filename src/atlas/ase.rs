@@ -0,0 +1,894 @@
+//! A reader for the Aseprite [binary format] and a converter to the JSON model
+//! of aseprite.rs. Only the subset used by the art in assets/atlas is
+//! interpreted: layers, frames, cels, tags, slices, and palettes. Tilemaps,
+//! blend modes other than normal, and user data other than slice colors are
+//! rejected or ignored.
+//!
+//! [binary format]: https://github.com/aseprite/aseprite/blob/master/docs/ase-file-specs.md
+
+use super::aseprite;
+use crate::math::{R16, XY, XY16};
+use crate::utils::zlib;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::num::TryFromIntError;
+
+const FILE_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+
+const OLD_PALETTE_CHUNK: u16 = 0x0004;
+const LAYER_CHUNK: u16 = 0x2004;
+const CEL_CHUNK: u16 = 0x2005;
+const TAGS_CHUNK: u16 = 0x2018;
+const PALETTE_CHUNK: u16 = 0x2019;
+const USER_DATA_CHUNK: u16 = 0x2020;
+const SLICE_CHUNK: u16 = 0x2022;
+
+/// The slice color Aseprite assigns when none is specified.
+const DEFAULT_SLICE_COLOR: Rgba = [0, 0, 0xff, 0xff];
+
+pub type Rgba = [u8; 4];
+
+/// A parsed .aseprite file.
+pub struct Ase {
+  /// The canvas dimensions shared by every Frame.
+  pub size: XY<u16>,
+  pub depth: ColorDepth,
+  /// The palette index rendered as transparent for Indexed files except on
+  /// the background layer.
+  pub transparent_index: u8,
+  pub palette: Vec<Rgba>,
+  /// Layers in file order, bottommost first.
+  pub layers: Vec<Layer>,
+  pub frames: Vec<Frame>,
+  pub tags: Vec<Tag>,
+  pub slices: Vec<Slice>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorDepth {
+  Rgba,
+  Grayscale,
+  Indexed,
+}
+
+pub struct Layer {
+  pub name: String,
+  pub visible: bool,
+  pub background: bool,
+  pub group: bool,
+  /// The group nesting depth. Layers belong to the nearest preceding group
+  /// with a lesser child level.
+  pub child_level: u16,
+  pub opacity: u8,
+}
+
+pub struct Frame {
+  /// Duration in milliseconds.
+  pub duration: aseprite::Duration,
+  pub cels: Vec<Cel>,
+}
+
+pub struct Cel {
+  /// The Layer index.
+  pub layer: usize,
+  /// The image offset within the canvas.
+  pub position: XY16,
+  pub opacity: u8,
+  pub image: CelImage,
+}
+
+pub enum CelImage {
+  /// Pixels in the file's ColorDepth, row-major.
+  Pixels { size: XY<u16>, pixels: Vec<u8> },
+  /// The same layer's Cel in another Frame.
+  Linked(usize),
+}
+
+pub struct Tag {
+  pub name: aseprite::Tag,
+  /// The inclusive starting Frame index.
+  pub from: u16,
+  /// The inclusive ending Frame index.
+  pub to: u16,
+  pub direction: Direction,
+  /// The number of plays or zero for unlimited.
+  pub repeat: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+  Forward,
+  Reverse,
+  PingPong,
+  PingPongReverse,
+}
+
+pub struct Slice {
+  pub name: aseprite::Tag,
+  pub color: Rgba,
+  pub keys: Vec<SliceKey>,
+}
+
+pub struct SliceKey {
+  /// The starting Frame index.
+  pub frame: u32,
+  pub bounds: R16,
+}
+
+/// The placement of every file's Frames within the packed image.
+pub struct Layout {
+  /// The packed image basename. E.g., "atlas.png".
+  pub image: String,
+  pub size: XY<u16>,
+  /// The border added to each side of every Frame.
+  pub padding: u16,
+  /// The top-left of each padded Frame by file and Frame index.
+  pub positions: HashMap<(usize, usize), XY<u16>>,
+  /// The region of each trimmed Frame kept in local pixels by file and Frame
+  /// index. Frames absent are untrimmed.
+  pub trims: HashMap<(usize, usize), R16>,
+}
+
+pub fn read(bytes: &[u8]) -> Result<Ase, AseParseError> {
+  let mut reader = Reader::new(bytes);
+  reader.u32()?; // File size.
+  if reader.u16()? != FILE_MAGIC {
+    return Err("Aseprite file magic number invalid.".into());
+  }
+  let frame_count = reader.u16()?;
+  let size = XY::new(reader.u16()?, reader.u16()?);
+  let depth = ColorDepth::parse(reader.u16()?)?;
+  let flags = reader.u32()?;
+  reader.skip(2 + 4 + 4)?; // Deprecated speed and reserved.
+  let transparent_index = reader.u8()?;
+  reader.skip(3 + 2 + 1 + 1 + 2 + 2 + 2 + 2 + 84)?;
+
+  let mut ase = Ase {
+    size,
+    depth,
+    transparent_index,
+    palette: Vec::new(),
+    layers: Vec::new(),
+    frames: Vec::new(),
+    tags: Vec::new(),
+    slices: Vec::new(),
+  };
+  let mut old_palette = Vec::new();
+  // Layer opacity is only valid when the header flag is set.
+  let layer_opacity = flags & 1 == 1;
+
+  for _ in 0..frame_count {
+    let start = reader.offset;
+    let end = start + reader.u32()? as usize;
+    if reader.u16()? != FRAME_MAGIC {
+      return Err("Aseprite frame magic number invalid.".into());
+    }
+    reader.skip(2)?; // Old chunk count.
+    let duration = reader.u16()?;
+    reader.skip(2 + 4)?; // Reserved and new chunk count.
+
+    let mut cels = Vec::new();
+    let mut last_chunk = 0;
+    while reader.offset < end {
+      let chunk_start = reader.offset;
+      let chunk_end = chunk_start + reader.u32()? as usize;
+      let chunk = reader.u16()?;
+      let mut chunk_reader = Reader::new(
+        bytes
+          .get(reader.offset..chunk_end)
+          .ok_or("Aseprite chunk truncated.")?,
+      );
+      match chunk {
+        OLD_PALETTE_CHUNK => old_palette = read_old_palette(&mut chunk_reader)?,
+        LAYER_CHUNK => {
+          ase.layers.push(read_layer(&mut chunk_reader, layer_opacity)?)
+        }
+        CEL_CHUNK => cels.push(read_cel(&mut chunk_reader, depth)?),
+        TAGS_CHUNK => ase.tags = read_tags(&mut chunk_reader)?,
+        PALETTE_CHUNK => read_palette(&mut chunk_reader, &mut ase.palette)?,
+        SLICE_CHUNK => ase.slices.push(read_slice(&mut chunk_reader)?),
+        USER_DATA_CHUNK if last_chunk == SLICE_CHUNK => {
+          if let Some(color) = read_user_data_color(&mut chunk_reader)? {
+            ase.slices.last_mut().unwrap().color = color;
+          }
+        }
+        _ => (),
+      }
+      last_chunk = chunk;
+      reader.offset = chunk_end;
+    }
+    reader.offset = end;
+    ase.frames.push(Frame { duration, cels });
+  }
+
+  if ase.palette.is_empty() {
+    ase.palette = old_palette;
+  }
+  ase.validate()?;
+  Ok(ase)
+}
+
+impl ColorDepth {
+  fn parse(bits: u16) -> Result<Self, AseParseError> {
+    match bits {
+      32 => Ok(Self::Rgba),
+      16 => Ok(Self::Grayscale),
+      8 => Ok(Self::Indexed),
+      _ => Err(format!("Color depth invalid: {}.", bits).into()),
+    }
+  }
+
+  fn bytes(self) -> usize {
+    match self {
+      Self::Rgba => 4,
+      Self::Grayscale => 2,
+      Self::Indexed => 1,
+    }
+  }
+}
+
+impl Direction {
+  fn parse(direction: u8) -> Result<Self, AseParseError> {
+    match direction {
+      0 => Ok(Self::Forward),
+      1 => Ok(Self::Reverse),
+      2 => Ok(Self::PingPong),
+      3 => Ok(Self::PingPongReverse),
+      _ => Err(format!("Tag direction invalid: {}.", direction).into()),
+    }
+  }
+
+  /// Returns the direction as exported in JSON.
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Forward => "forward",
+      Self::Reverse => "reverse",
+      Self::PingPong => "pingpong",
+      Self::PingPongReverse => "pingpong_reverse",
+    }
+  }
+}
+
+fn read_layer(
+  reader: &mut Reader,
+  opacity: bool,
+) -> Result<Layer, AseParseError> {
+  let flags = reader.u16()?;
+  let kind = reader.u16()?;
+  let child_level = reader.u16()?;
+  reader.skip(2 + 2 + 2)?; // Default width and height, and blend mode.
+  let layer_opacity = reader.u8()?;
+  reader.skip(3)?;
+  let name = reader.string()?;
+  if kind > 1 {
+    return Err(
+      format!("Layer \"{}\" type {} unsupported.", name, kind).into(),
+    );
+  }
+  Ok(Layer {
+    name,
+    visible: flags & 1 == 1,
+    background: flags & 8 == 8,
+    group: kind == 1,
+    child_level,
+    opacity: if opacity { layer_opacity } else { 0xff },
+  })
+}
+
+fn read_cel(
+  reader: &mut Reader,
+  depth: ColorDepth,
+) -> Result<Cel, AseParseError> {
+  let layer = usize::from(reader.u16()?);
+  let position = XY::new(reader.i16()?, reader.i16()?);
+  let opacity = reader.u8()?;
+  let kind = reader.u16()?;
+  reader.skip(2 + 5)?; // Z-index and reserved.
+  let image = match kind {
+    0 | 2 => {
+      let size = XY::new(reader.u16()?, reader.u16()?);
+      let data = reader.rest();
+      let pixels = if kind == 0 { data.to_vec() } else { zlib::inflate(data)? };
+      let len = usize::from(size.x) * usize::from(size.y) * depth.bytes();
+      if pixels.len() < len {
+        return Err("Cel pixels truncated.".into());
+      }
+      CelImage::Pixels { size, pixels: pixels[..len].to_vec() }
+    }
+    1 => CelImage::Linked(usize::from(reader.u16()?)),
+    _ => return Err(format!("Cel type {} unsupported.", kind).into()),
+  };
+  Ok(Cel { layer, position, opacity, image })
+}
+
+fn read_tags(reader: &mut Reader) -> Result<Vec<Tag>, AseParseError> {
+  let len = reader.u16()?;
+  reader.skip(8)?;
+  let mut tags = Vec::new();
+  for _ in 0..len {
+    let from = reader.u16()?;
+    let to = reader.u16()?;
+    let direction = Direction::parse(reader.u8()?)?;
+    let repeat = reader.u16()?;
+    reader.skip(6 + 3 + 1)?; // Reserved and deprecated color.
+    tags.push(Tag { name: reader.string()?, from, to, direction, repeat });
+  }
+  Ok(tags)
+}
+
+fn read_palette(
+  reader: &mut Reader,
+  palette: &mut Vec<Rgba>,
+) -> Result<(), AseParseError> {
+  let len = reader.u32()? as usize;
+  let first = reader.u32()? as usize;
+  let last = reader.u32()? as usize;
+  reader.skip(8)?;
+  if palette.len() < len {
+    palette.resize(len, [0; 4]);
+  }
+  for i in first..=last {
+    let flags = reader.u16()?;
+    let color = [reader.u8()?, reader.u8()?, reader.u8()?, reader.u8()?];
+    *palette.get_mut(i).ok_or("Palette index out of range.")? = color;
+    if flags & 1 == 1 {
+      reader.string()?;
+    }
+  }
+  Ok(())
+}
+
+fn read_old_palette(reader: &mut Reader) -> Result<Vec<Rgba>, AseParseError> {
+  let mut palette = Vec::new();
+  let packets = reader.u16()?;
+  for _ in 0..packets {
+    let skip = usize::from(reader.u8()?);
+    palette.resize(palette.len() + skip, [0; 4]);
+    let len = match reader.u8()? {
+      0 => 256,
+      len => usize::from(len),
+    };
+    for _ in 0..len {
+      palette.push([reader.u8()?, reader.u8()?, reader.u8()?, 0xff]);
+    }
+  }
+  Ok(palette)
+}
+
+fn read_slice(reader: &mut Reader) -> Result<Slice, AseParseError> {
+  let len = reader.u32()?;
+  let flags = reader.u32()?;
+  reader.skip(4)?;
+  let name = reader.string()?;
+  let mut keys = Vec::new();
+  for _ in 0..len {
+    let frame = reader.u32()?;
+    let x: i16 = reader.i32()?.try_into()?;
+    let y: i16 = reader.i32()?.try_into()?;
+    let w: i16 = reader.u32()?.try_into()?;
+    let h: i16 = reader.u32()?.try_into()?;
+    if flags & 1 == 1 {
+      reader.skip(4 * 4)?; // 9-patch center.
+    }
+    if flags & 2 == 2 {
+      reader.skip(4 * 2)?; // Pivot.
+    }
+    keys.push(SliceKey { frame, bounds: R16::new_size(x, y, w, h) });
+  }
+  Ok(Slice { name, color: DEFAULT_SLICE_COLOR, keys })
+}
+
+fn read_user_data_color(
+  reader: &mut Reader,
+) -> Result<Option<Rgba>, AseParseError> {
+  let flags = reader.u32()?;
+  if flags & 1 == 1 {
+    reader.string()?;
+  }
+  if flags & 2 == 0 {
+    return Ok(None);
+  }
+  Ok(Some([reader.u8()?, reader.u8()?, reader.u8()?, reader.u8()?]))
+}
+
+impl Ase {
+  fn validate(&self) -> Result<(), AseParseError> {
+    for (i, frame) in self.frames.iter().enumerate() {
+      for cel in &frame.cels {
+        if cel.layer >= self.layers.len() {
+          return Err(format!("Cel layer of frame {} invalid.", i).into());
+        }
+        if let CelImage::Linked(linked) = cel.image {
+          if self.cel(linked, cel.layer).is_none() {
+            return Err(format!("Cel link of frame {} invalid.", i).into());
+          }
+        }
+      }
+    }
+    for tag in &self.tags {
+      if tag.from > tag.to || usize::from(tag.to) >= self.frames.len() {
+        return Err(format!("Tag \"{}\" frames invalid.", tag.name).into());
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the Cel pixels of the Layer in the Frame, following links.
+  fn cel(&self, frame: usize, layer: usize) -> Option<(&Cel, XY<u16>, &[u8])> {
+    let cel =
+      self.frames.get(frame)?.cels.iter().find(|cel| cel.layer == layer)?;
+    match &cel.image {
+      CelImage::Pixels { size, pixels } => Some((cel, size.clone(), pixels)),
+      CelImage::Linked(linked) => {
+        // Links never chain but guard against malformed files.
+        if *linked == frame {
+          return None;
+        }
+        let (_, size, pixels) = self.cel(*linked, layer)?;
+        Some((cel, size, pixels))
+      }
+    }
+  }
+
+  /// Returns whether the Layer and all its groups are visible.
+  fn is_visible(&self, layer: usize) -> bool {
+    let mut level = self.layers[layer].child_level;
+    if !self.layers[layer].visible {
+      return false;
+    }
+    for group in self.layers[..layer].iter().rev() {
+      if group.child_level < level {
+        if !group.visible {
+          return false;
+        }
+        level = group.child_level;
+      }
+    }
+    true
+  }
+
+  /// Returns the Frame's visible Layers flattened with normal blending as
+  /// row-major RGBA pixels.
+  pub fn render(&self, frame: usize) -> Vec<u8> {
+    let w = usize::from(self.size.x);
+    let h = usize::from(self.size.y);
+    let mut canvas = vec![0; w * h * 4];
+    for (i, layer) in self.layers.iter().enumerate() {
+      if layer.group || !self.is_visible(i) {
+        continue;
+      }
+      let (cel, size, pixels) = match self.cel(frame, i) {
+        Some(cel) => cel,
+        None => continue,
+      };
+      let opacity = u32::from(cel.opacity) * u32::from(layer.opacity) / 0xff;
+      for y in 0..i32::from(size.y) {
+        let canvas_y = i32::from(cel.position.y) + y;
+        if canvas_y < 0 || canvas_y >= h as i32 {
+          continue;
+        }
+        for x in 0..i32::from(size.x) {
+          let canvas_x = i32::from(cel.position.x) + x;
+          if canvas_x < 0 || canvas_x >= w as i32 {
+            continue;
+          }
+          let src = (y * i32::from(size.x) + x) as usize * self.depth.bytes();
+          let mut color = self.color(&pixels[src..], layer.background);
+          color[3] = (u32::from(color[3]) * opacity / 0xff) as u8;
+          let dst = (canvas_y as usize * w + canvas_x as usize) * 4;
+          blend(&mut canvas[dst..dst + 4], color);
+        }
+      }
+    }
+    canvas
+  }
+
+  fn color(&self, pixel: &[u8], background: bool) -> Rgba {
+    match self.depth {
+      ColorDepth::Rgba => [pixel[0], pixel[1], pixel[2], pixel[3]],
+      ColorDepth::Grayscale => [pixel[0], pixel[0], pixel[0], pixel[1]],
+      ColorDepth::Indexed => {
+        if pixel[0] == self.transparent_index && !background {
+          return [0; 4];
+        }
+        self.palette.get(usize::from(pixel[0])).cloned().unwrap_or([0; 4])
+      }
+    }
+  }
+}
+
+/// Composites the source color over the destination with straight alpha.
+fn blend(dst: &mut [u8], src: Rgba) {
+  let src_a = u32::from(src[3]);
+  if src_a == 0 {
+    return;
+  }
+  let dst_a = u32::from(dst[3]) * (0xff - src_a) / 0xff;
+  let a = src_a + dst_a;
+  for i in 0..3 {
+    dst[i] =
+      ((u32::from(src[i]) * src_a + u32::from(dst[i]) * dst_a) / a) as u8;
+  }
+  dst[3] = a as u8;
+}
+
+/// Returns the file index and Tag of every Tag in the files. Tags are expected
+/// to be unique across files; as with the Aseprite CLI, only the first file's
+/// Tag is listed for a duplicate.
+pub fn tags(ases: &[Ase]) -> Vec<(usize, &Tag)> {
+  let mut names = HashSet::new();
+  let mut tags = Vec::new();
+  for (i, ase) in ases.iter().enumerate() {
+    for tag in &ase.tags {
+      if names.insert(&tag.name) {
+        tags.push((i, tag));
+      }
+    }
+  }
+  tags
+}
+
+/// Returns the JSON model for the files packed as described by the Layout.
+/// Only the Frames of the Tags listed by tags() are included.
+pub fn to_file(
+  ases: &[Ase],
+  layout: &Layout,
+) -> Result<aseprite::File, AseParseError> {
+  let mut frame_tags = Vec::new();
+  let mut frames = aseprite::FrameMap::new();
+  for (file, tag) in tags(ases) {
+    frame_tags.push(aseprite::FrameTag {
+      name: tag.name.clone(),
+      from: tag.from,
+      to: tag.to,
+      direction: tag.direction.name().to_string(),
+      // Zero is infinite.
+      repeat: if tag.repeat == 0 { None } else { Some(tag.repeat.to_string()) },
+    });
+    for i in usize::from(tag.from)..=usize::from(tag.to) {
+      let position = layout.positions.get(&(file, i)).ok_or_else(|| {
+        format!("Layout missing frame {} of \"{}\".", i, tag.name)
+      })?;
+      let trim = layout.trims.get(&(file, i));
+      let frame =
+        to_frame(&ases[file], &ases[file].frames[i], position, trim, layout)?;
+      frames.insert(format!("{} {}", tag.name, i), frame);
+    }
+  }
+  let mut slices = Vec::new();
+  for slice in ases.iter().flat_map(|ase| &ase.slices) {
+    slices.push(to_slice(slice)?);
+  }
+
+  Ok(aseprite::File {
+    meta: aseprite::Meta {
+      app: env!("CARGO_PKG_NAME").to_string(),
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      image: layout.image.clone(),
+      format: "RGBA8888".to_string(),
+      size: aseprite::Size { w: layout.size.x, h: layout.size.y },
+      scale: "1".to_string(),
+      frame_tags,
+      slices,
+    },
+    frames,
+  })
+}
+
+fn to_frame(
+  ase: &Ase,
+  frame: &Frame,
+  position: &XY<u16>,
+  trim: Option<&R16>,
+  layout: &Layout,
+) -> Result<aseprite::Frame, AseParseError> {
+  let XY { x: w, y: h } = ase.size;
+  let sprite_source_size = match trim {
+    Some(trim) => aseprite::Rect {
+      x: trim.from.x,
+      y: trim.from.y,
+      w: (trim.to.x - trim.from.x).try_into()?,
+      h: (trim.to.y - trim.from.y).try_into()?,
+    },
+    None => aseprite::Rect { x: 0, y: 0, w, h },
+  };
+  Ok(aseprite::Frame {
+    frame: aseprite::Rect {
+      x: position.x.try_into()?,
+      y: position.y.try_into()?,
+      w: sprite_source_size.w + layout.padding * 2,
+      h: sprite_source_size.h + layout.padding * 2,
+    },
+    rotated: false,
+    trimmed: trim.is_some(),
+    sprite_source_size,
+    source_size: aseprite::Size { w, h },
+    duration: frame.duration,
+  })
+}
+
+fn to_slice(slice: &Slice) -> Result<aseprite::Slice, AseParseError> {
+  let mut keys = Vec::new();
+  for SliceKey { frame, bounds } in &slice.keys {
+    keys.push(aseprite::Key {
+      frame: *frame,
+      bounds: aseprite::Rect {
+        x: bounds.from.x,
+        y: bounds.from.y,
+        w: (bounds.to.x - bounds.from.x).try_into()?,
+        h: (bounds.to.y - bounds.from.y).try_into()?,
+      },
+    });
+  }
+  let [r, g, b, a] = slice.color;
+  Ok(aseprite::Slice {
+    name: slice.name.clone(),
+    color: format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+    keys,
+  })
+}
+
+/// Little-endian cursor over file bytes.
+struct Reader<'a> {
+  bytes: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes, offset: 0 }
+  }
+
+  fn take(&mut self, len: usize) -> Result<&'a [u8], AseParseError> {
+    let bytes = self
+      .bytes
+      .get(self.offset..self.offset + len)
+      .ok_or("Aseprite file truncated.")?;
+    self.offset += len;
+    Ok(bytes)
+  }
+
+  fn skip(&mut self, len: usize) -> Result<(), AseParseError> {
+    self.take(len).map(|_| ())
+  }
+
+  fn rest(&mut self) -> &'a [u8] {
+    let bytes = &self.bytes[self.offset.min(self.bytes.len())..];
+    self.offset = self.bytes.len();
+    bytes
+  }
+
+  fn u8(&mut self) -> Result<u8, AseParseError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, AseParseError> {
+    let bytes = self.take(2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+  }
+
+  fn i16(&mut self) -> Result<i16, AseParseError> {
+    Ok(self.u16()? as i16)
+  }
+
+  fn u32(&mut self) -> Result<u32, AseParseError> {
+    let bytes = self.take(4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+  }
+
+  fn i32(&mut self) -> Result<i32, AseParseError> {
+    Ok(self.u32()? as i32)
+  }
+
+  fn string(&mut self) -> Result<String, AseParseError> {
+    let len = usize::from(self.u16()?);
+    let bytes = self.take(len)?;
+    String::from_utf8(bytes.to_vec())
+      .or_else(|_| Err("Aseprite string is not UTF-8.".into()))
+  }
+}
+
+#[derive(Debug)]
+pub struct AseParseError(pub String);
+
+impl From<&str> for AseParseError {
+  fn from(error: &str) -> Self {
+    Self(error.to_string())
+  }
+}
+
+impl From<String> for AseParseError {
+  fn from(error: String) -> Self {
+    Self(error)
+  }
+}
+
+impl From<TryFromIntError> for AseParseError {
+  fn from(error: TryFromIntError) -> Self {
+    Self(error.to_string())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn read_bee() {
+    let ase = read(include_bytes!("../../assets/atlas/bee.aseprite")).unwrap();
+    assert_eq!(ase.size, XY::new(5, 5));
+    assert_eq!(ase.depth, ColorDepth::Indexed);
+    assert_eq!(ase.frames.len(), 5);
+    assert_eq!(
+      ase
+        .tags
+        .iter()
+        .map(|tag| (tag.name.as_str(), tag.from, tag.to, tag.direction))
+        .collect::<Vec<_>>(),
+      vec![
+        ("bee", 0, 1, Direction::Forward),
+        ("bee-shadow", 2, 2, Direction::Forward),
+        ("bee-dead", 3, 3, Direction::Forward),
+        ("bee-blood", 4, 4, Direction::Forward)
+      ]
+    );
+    assert_eq!(
+      ase.frames.iter().map(|frame| frame.duration).collect::<Vec<_>>(),
+      vec![48, 48, aseprite::INFINITE, aseprite::INFINITE, aseprite::INFINITE]
+    );
+    assert!(!ase.palette.is_empty());
+  }
+
+  #[test]
+  fn read_pingpong() {
+    let ase =
+      read(include_bytes!("../../assets/atlas/healthBauble.aseprite")).unwrap();
+    assert_eq!(ase.tags[0].direction, Direction::PingPong);
+  }
+
+  #[test]
+  fn read_slices() {
+    let ase =
+      read(include_bytes!("../../assets/atlas/tree-large.aseprite")).unwrap();
+    let slices = ase
+      .slices
+      .iter()
+      .map(|slice| (slice.name.as_str(), slice.keys[0].bounds.clone()))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      slices,
+      vec![
+        ("tree-large", R16::new_size(6, 16, 7, 3)),
+        ("tree-large", R16::new_size(8, 13, 4, 3))
+      ]
+    );
+  }
+
+  #[test]
+  fn read_invalid() {
+    [&b""[..], &[0; 128][..], &include_bytes!("../atlas/atlas.png")[..]]
+      .iter()
+      .enumerate()
+      .for_each(|(i, bytes)| {
+        assert!(read(bytes).is_err(), "Case {} failed.", i)
+      });
+  }
+
+  #[test]
+  fn render_bee() {
+    let ase = read(include_bytes!("../../assets/atlas/bee.aseprite")).unwrap();
+    let pixels = ase.render(0);
+    assert_eq!(pixels.len(), 5 * 5 * 4);
+    // Some pixels are opaque and some transparent.
+    let alphas: Vec<_> = pixels.chunks(4).map(|pixel| pixel[3]).collect();
+    assert!(alphas.contains(&0xff));
+    assert!(alphas.contains(&0));
+  }
+
+  #[test]
+  fn to_file_bee() {
+    let ase = read(include_bytes!("../../assets/atlas/bee.aseprite")).unwrap();
+    let layout = Layout {
+      image: "atlas.png".to_string(),
+      size: XY::new(32, 8),
+      padding: 1,
+      positions: (0..5).map(|i| ((0, i), XY::new(i as u16 * 7, 0))).collect(),
+      trims: HashMap::new(),
+    };
+    let file = to_file(&[ase], &layout).unwrap();
+    assert_eq!(file.meta.frame_tags.len(), 4);
+    assert_eq!(file.frames.len(), 5);
+    let frame = &file.frames["bee 1"];
+    assert_eq!(
+      (frame.frame.x, frame.frame.y, frame.frame.w, frame.frame.h),
+      (7, 0, 7, 7)
+    );
+    assert_eq!((frame.source_size.w, frame.source_size.h), (5, 5));
+    assert_eq!(frame.duration, 48);
+    assert_eq!(file.frames["bee-dead 3"].duration, aseprite::INFINITE);
+  }
+
+  #[test]
+  fn to_file_missing_frame() {
+    let ase = read(include_bytes!("../../assets/atlas/bee.aseprite")).unwrap();
+    let layout = Layout {
+      image: "atlas.png".to_string(),
+      size: XY::new(32, 8),
+      padding: 0,
+      positions: HashMap::new(),
+      trims: HashMap::new(),
+    };
+    assert!(to_file(&[ase], &layout).is_err());
+  }
+
+  #[test]
+  fn to_file_trimmed() {
+    let ase = read(include_bytes!("../../assets/atlas/bee.aseprite")).unwrap();
+    let mut trims = HashMap::new();
+    trims.insert((0, 1), R16::new_size(1, 2, 3, 2));
+    let layout = Layout {
+      image: "atlas.png".to_string(),
+      size: XY::new(32, 8),
+      padding: 1,
+      positions: (0..5).map(|i| ((0, i), XY::new(i as u16 * 7, 0))).collect(),
+      trims,
+    };
+    let file = to_file(&[ase], &layout).unwrap();
+    let frame = &file.frames["bee 1"];
+    assert!(frame.trimmed);
+    assert_eq!(
+      (frame.frame.x, frame.frame.y, frame.frame.w, frame.frame.h),
+      (7, 0, 5, 4)
+    );
+    let trim = &frame.sprite_source_size;
+    assert_eq!((trim.x, trim.y, trim.w, trim.h), (1, 2, 3, 2));
+    assert_eq!((frame.source_size.w, frame.source_size.h), (5, 5));
+    assert!(!file.frames["bee 0"].trimmed);
+  }
+
+  /// Every file in assets/atlas is read and agrees with the atlas exported by
+  /// the Aseprite CLI.
+  #[test]
+  fn read_matches_atlas_json() {
+    let json: serde_json::Value = include_json!("atlas.json").unwrap();
+    let mut tags = HashSet::new();
+    let mut paths: Vec<_> = std::fs::read_dir("assets/atlas")
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .collect();
+    // Files are packed in name order so the first duplicate Tag wins.
+    paths.sort();
+    for path in paths {
+      let ase = read(&std::fs::read(&path).unwrap())
+        .unwrap_or_else(|error| panic!("{:?} failed: {}.", path, error.0));
+      for tag in &ase.tags {
+        if !tags.insert(tag.name.clone()) {
+          continue;
+        }
+        let expected = json["meta"]["frameTags"]
+          .as_array()
+          .unwrap()
+          .iter()
+          .find(|expected| expected["name"] == tag.name.as_str())
+          .unwrap_or_else(|| panic!("Tag \"{}\" missing.", tag.name));
+        assert_eq!(expected["from"], tag.from, "{}", tag.name);
+        assert_eq!(expected["to"], tag.to, "{}", tag.name);
+        assert_eq!(expected["direction"], tag.direction.name(), "{}", tag.name);
+        for i in tag.from..=tag.to {
+          let frame = &json["frames"][format!("{} {}", tag.name, i)];
+          assert_eq!(
+            frame["duration"],
+            ase.frames[usize::from(i)].duration,
+            "{} {}",
+            tag.name,
+            i
+          );
+          assert_eq!(frame["sourceSize"]["w"], ase.size.x, "{}", tag.name);
+          assert_eq!(frame["sourceSize"]["h"], ase.size.y, "{}", tag.name);
+        }
+      }
+    }
+    assert_eq!(tags.len(), json["meta"]["frameTags"].as_array().unwrap().len());
+  }
+}
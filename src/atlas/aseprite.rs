@@ -1,6 +1,6 @@
 //! This typing assumes the options specified in aseprite-atlas-pack and
-//! annotated herein with **via CLI**. The JSON export format appears to be
-//! undocumented but the related [binary format] is. Types marked
+//! pack-atlas and annotated herein with **via CLI**. The JSON export format
+//! appears to be undocumented but the related [binary format] is. Types marked
 //! "**by convention**" are supplemental to and unenforced by the JSON format.
 //! Any data of these types should be validated as soon as possible. All numbers
 //! are integers. All indices are zero-based. All geometry are described from
@@ -8,24 +8,33 @@
 //!
 //! [binary format]: https://github.com/aseprite/aseprite/blob/master/docs/ase-file-specs.md
 
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 /// The topmost data type for JSON exported from Aseprite. This format contains
 /// all the image and animation information for every file packed in the atlas.
 /// **By convention**, every file has one or more animations. Every animation
 /// has a Frame sequence, a Tag, and zero or more Slices.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct File {
   pub meta: Meta,
   /// All Frames for all files packed.
+  #[serde(serialize_with = "serialize_frame_map")]
   pub frames: FrameMap,
 }
 
 pub type FrameMap = HashMap<TagFrameNumber, Frame>;
 
-#[derive(Deserialize)]
+/// Serializes Frames ordered by TagFrameNumber so that output is reproducible.
+fn serialize_frame_map<S: Serializer>(
+  frames: &FrameMap,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  frames.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct Meta {
   /// E.g., "http://www.aseprite.org/".
   pub app: String,
@@ -54,7 +63,7 @@ pub type Tag = String;
 
 /// A single animation frame and most primitive unit. Each file packed always a
 /// has at least one Frame.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Frame {
   /// The Frame's bounds within the atlas, including a any border padding
   /// **via CLI** `--inner-padding n`. The padding dimensions may also be
@@ -74,7 +83,7 @@ pub struct Frame {
 
 /// A label and animation behavior for one or more Frames. When combined with
 /// the referenced Frames, an animation is represented.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct FrameTag {
   /// **By convention**, the associated Frame's Tag.
   pub name: Tag,
@@ -84,6 +93,10 @@ pub struct FrameTag {
   /// index.
   pub to: u16,
   pub direction: String,
+  /// The number of times to play the animation as a decimal string. Absent
+  /// when the animation loops forever, including in older exports.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub repeat: Option<String>,
 }
 
 /// Positive animation length in milliseconds. **By convention**, animations
@@ -94,7 +107,7 @@ pub type Duration = u16;
 /// termination.
 pub const INFINITE: Duration = !0;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Slice {
   pub name: Tag,
   /// Color in #rrggbbaa format. E.g., blue is "#0000ffff".
@@ -102,7 +115,7 @@ pub struct Slice {
   pub keys: Vec<Key>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Key {
   /// The inclusive associated Frame's start offset, the exclusive previous
   /// Frame's end offset. **By convention,** the exclusive end offset is the
@@ -113,7 +126,7 @@ pub struct Key {
   pub bounds: Rect,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Rect {
   /// Distance from the top in pixels.
   pub x: i16,
@@ -125,7 +138,7 @@ pub struct Rect {
   pub h: u16,
 }
 
-#[derive(Deserialize, PartialEq)]
+#[derive(Deserialize, PartialEq, Serialize)]
 pub struct Size {
   /// Width in pixels.
   pub w: u16,
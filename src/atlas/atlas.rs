@@ -1,8 +1,10 @@
 use super::AnimationID;
 use crate::math::Millis;
 use crate::math::{R16, XY};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[derive(Deserialize, Serialize)]
 pub struct Atlas {
   /// The Aseprite version of the parsed file. E.g., '1.2.8.1'.
   pub version: String,
@@ -18,22 +20,26 @@ pub struct Atlas {
 pub type AnimationMap = HashMap<AnimationID, Animation>;
 
 /// A sequence of cels.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
 pub struct Animation {
   /// Width and height within the source atlas image in integral pixels.
   /// Dimensions are identical for every cel.
   pub size: XY<u16>,
   pub cels: Vec<Cel>,
-  /// Positive animation length in milliseconds for a full cycle or infinite.
-  /// For a ping-pong animation, this is a full traversal forward plus the
-  /// traversal backward excluding the first and last frame. E.g., in a five cel
-  /// animation, the total duration would be the sum of the individual durations
-  /// for the initial five frames and the middle three frames.
+  /// Positive animation length in milliseconds for a full cycle, the total
+  /// length when repeat is finite, or infinite. For a ping-pong animation, a
+  /// cycle is a full traversal forward plus the traversal backward excluding
+  /// the first and last frame. E.g., in a five cel animation, the cycle
+  /// duration would be the sum of the individual durations for the initial five
+  /// frames and the middle three frames.
   pub duration: Millis,
   pub direction: Playback,
+  /// The number of times to play the animation before holding the final Cel
+  /// or None to loop forever. Each ping-pong traversal counts as one play.
+  pub repeat: Option<u16>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Playback {
   /// Animate from start to end; when looping, return to start.
   Forward,
@@ -44,17 +50,56 @@ pub enum Playback {
   /// is lesser. A traversal from start to end - 1 then end to start + 1 is
   /// considered a complete loop.
   PingPong,
+  /// Identical to PingPong but starting from end.
+  PingPongReverse,
 }
 
 /// A single frame of an animation sequence.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Cel {
   /// Location within the source atlas image in integral pixels from the
   /// top-left. The width and height are provided for convenience, only XY vary
-  /// from Cel to Cel.
+  /// from Cel to Cel. For trimmed cels, only the trim region of these bounds is
+  /// in the atlas.
   pub bounds: R16,
+  /// The region within the cel, in local pixels, that was kept when the frame
+  /// was trimmed. Pixels outside it are transparent. Untrimmed cels span their
+  /// entire bounds.
+  pub trim: R16,
   /// Positive cel exposure, possibly infinite.
   pub duration: Millis,
   /// Slices within the cel in local pixels.
   pub slices: Vec<R16>,
+  /// Typed collision bodies within the cel in local pixels.
+  pub bodies: Vec<Body>,
+}
+
+/// A collision region of a cel parsed from a slice named "{tag} {kind}". E.g.,
+/// "backpacker-melee hitbox".
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Body {
+  pub kind: BodyKind,
+  /// Local pixels.
+  pub bounds: R16,
+}
+
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Deserialize,
+  Eq,
+  Hash,
+  Ord,
+  PartialEq,
+  PartialOrd,
+  Serialize,
+)]
+pub enum BodyKind {
+  /// Inflicts damage on overlapping hurtboxes. E.g., a melee swing.
+  Hitbox,
+  /// Receives damage from overlapping hitboxes.
+  Hurtbox,
+  /// Where the owner touches the ground. Used for obstacle and terrain tests.
+  Feet,
 }
@@ -1,5 +1,7 @@
 use super::aseprite;
-use super::{Animation, AnimationID, AnimationMap, Atlas, Cel, Playback};
+use super::{
+  Animation, AnimationID, AnimationMap, Atlas, Body, BodyKind, Cel, Playback,
+};
 use crate::math::{Millis, R16, XY, XY16};
 use std::convert::TryFrom;
 use std::num::TryFromIntError;
@@ -47,287 +49,8 @@ pub fn parse_animation_map(
 
 impl AnimationID {
   fn parse(id: &str) -> Result<Self, AtlasParseError> {
-    match id {
-      "appleTree" => Ok(Self::AppleTree),
-      "appleTree-shadow" => Ok(Self::AppleTreeShadow),
-      "arrow-upRight" => Ok(Self::ArrowDiagonal),
-      "arrow-right" => Ok(Self::ArrowHorizontal),
-      "arrow-up" => Ok(Self::ArrowVertical),
-      "backpacker-idleDown" => Ok(Self::BackpackerIdleDown),
-      "backpacker-idleLeft" => Ok(Self::BackpackerIdleLeft),
-      "backpacker-idleRight" => Ok(Self::BackpackerIdleRight),
-      "backpacker-idleUp" => Ok(Self::BackpackerIdleUp),
-      "backpacker-meleeRight" => Ok(Self::BackpackerMeleeRight),
-      "backpacker-walkDown" => Ok(Self::BackpackerWalkDown),
-      "backpacker-walkHorizontalShadow" => {
-        Ok(Self::BackpackerWalkHorizontalShadow)
-      }
-      "backpacker-walkLeft" => Ok(Self::BackpackerWalkLeft),
-      "backpacker-walkRight" => Ok(Self::BackpackerWalkRight),
-      "backpacker-walkUp" => Ok(Self::BackpackerWalkUp),
-      "backpacker-walkVerticalShadow" => Ok(Self::BackpackerWalkVerticalShadow),
-      "bee" => Ok(Self::Bee),
-      "bee-blood" => Ok(Self::BeeBlood),
-      "bee-dead" => Ok(Self::BeeDead),
-      "bee-shadow" => Ok(Self::BeeShadow),
-      "bird-fly" => Ok(Self::BirdFly),
-      "bird-rest" => Ok(Self::BirdRest),
-      "bird-rise" => Ok(Self::BirdRise),
-      "bunny-blood" => Ok(Self::BunnyBlood),
-      "bunny" => Ok(Self::Bunny),
-      "bunny-dead" => Ok(Self::BunnyDead),
-      "bunny-shadow" => Ok(Self::BunnyShadow),
-      "bush" => Ok(Self::Bush),
-      "bush-shadow" => Ok(Self::BushShadow),
-      "cattails" => Ok(Self::Cattails),
-      "cloud-large" => Ok(Self::CloudLarge),
-      "cloud-largeShadow" => Ok(Self::CloudLargeShadow),
-      "cloud-medium" => Ok(Self::CloudMedium),
-      "cloud-mediumShadow" => Ok(Self::CloudMediumShadow),
-      "cloudRain" => Ok(Self::CloudRain),
-      "cloudRain-puddle" => Ok(Self::CloudRainPuddle),
-      "cloudRainSplash" => Ok(Self::CloudRainSplash),
-      "cloudRain-sprinkle" => Ok(Self::CloudRainSprinkle),
-      "clover-0x0" => Ok(Self::Clover0x0),
-      "clover-0x1" => Ok(Self::Clover0x1),
-      "clover-0x2" => Ok(Self::Clover0x2),
-      "clover-0x3" => Ok(Self::Clover0x3),
-      "clover-0x4" => Ok(Self::Clover0x4),
-      "clover-1x0" => Ok(Self::Clover1x0),
-      "clover-1x1" => Ok(Self::Clover1x1),
-      "clover-1x2" => Ok(Self::Clover1x2),
-      "clover-1x3" => Ok(Self::Clover1x3),
-      "clover-1x4" => Ok(Self::Clover1x4),
-      "conifer" => Ok(Self::Conifer),
-      "conifer-shadow" => Ok(Self::ConiferShadow),
-      "egg-compartment-drawer" => Ok(Self::EggCompartmentDrawer),
-      "egg-compartment-unit" => Ok(Self::EggCompartmentUnit),
-      "eggCompartment-unitPressed" => Ok(Self::EggCompartmentUnitPressed),
-      "flag" => Ok(Self::Flag),
-      "flag-shadow" => Ok(Self::FlagShadow),
-      "flower" => Ok(Self::Flower),
-      "flower-shadow" => Ok(Self::FlowerShadow),
-      "frog-eat" => Ok(Self::FrogEat),
-      "frog-idle" => Ok(Self::FrogIdle),
-      "frog-idleShadow" => Ok(Self::FrogIdleShadow),
-      "frog-leap" => Ok(Self::FrogLeap),
-      "grass-00" => Ok(Self::Grass00),
-      "grass-01" => Ok(Self::Grass01),
-      "grass-02" => Ok(Self::Grass02),
-      "grass-03" => Ok(Self::Grass03),
-      "grass-04" => Ok(Self::Grass04),
-      "grass-05" => Ok(Self::Grass05),
-      "grass-06" => Ok(Self::Grass06),
-      "grass-07" => Ok(Self::Grass07),
-      "grass-08" => Ok(Self::Grass08),
-      "grass-09" => Ok(Self::Grass09),
-      "grass-10" => Ok(Self::Grass10),
-      "grass-11" => Ok(Self::Grass11),
-      "grass-12" => Ok(Self::Grass12),
-      "grass-13" => Ok(Self::Grass13),
-      "grass-14" => Ok(Self::Grass14),
-      "grass-15" => Ok(Self::Grass15),
-      "grass-shadow" => Ok(Self::GrassShadow),
-      "healthBauble" => Ok(Self::HealthBauble),
-      "itemApple" => Ok(Self::ItemApple),
-      "itemApple-shadow" => Ok(Self::ItemAppleShadow),
-      "lattice" => Ok(Self::Lattice),
-      "lifeCounter" => Ok(Self::LifeCounter),
-      "meleeButton-disabled" => Ok(Self::MeleeButtonDisabled),
-      "meleeButton-enabled" => Ok(Self::MeleeButtonEnabled),
-      "memFont-000" => Ok(Self::MemFont000),
-      "memFont-001" => Ok(Self::MemFont001),
-      "memFont-002" => Ok(Self::MemFont002),
-      "memFont-003" => Ok(Self::MemFont003),
-      "memFont-004" => Ok(Self::MemFont004),
-      "memFont-005" => Ok(Self::MemFont005),
-      "memFont-006" => Ok(Self::MemFont006),
-      "memFont-007" => Ok(Self::MemFont007),
-      "memFont-008" => Ok(Self::MemFont008),
-      "memFont-009" => Ok(Self::MemFont009),
-      "memFont-010" => Ok(Self::MemFont010),
-      "memFont-011" => Ok(Self::MemFont011),
-      "memFont-012" => Ok(Self::MemFont012),
-      "memFont-013" => Ok(Self::MemFont013),
-      "memFont-014" => Ok(Self::MemFont014),
-      "memFont-015" => Ok(Self::MemFont015),
-      "memFont-016" => Ok(Self::MemFont016),
-      "memFont-017" => Ok(Self::MemFont017),
-      "memFont-018" => Ok(Self::MemFont018),
-      "memFont-019" => Ok(Self::MemFont019),
-      "memFont-020" => Ok(Self::MemFont020),
-      "memFont-021" => Ok(Self::MemFont021),
-      "memFont-022" => Ok(Self::MemFont022),
-      "memFont-023" => Ok(Self::MemFont023),
-      "memFont-024" => Ok(Self::MemFont024),
-      "memFont-025" => Ok(Self::MemFont025),
-      "memFont-026" => Ok(Self::MemFont026),
-      "memFont-027" => Ok(Self::MemFont027),
-      "memFont-028" => Ok(Self::MemFont028),
-      "memFont-029" => Ok(Self::MemFont029),
-      "memFont-030" => Ok(Self::MemFont030),
-      "memFont-031" => Ok(Self::MemFont031),
-      "memFont-032" => Ok(Self::MemFont032),
-      "memFont-033" => Ok(Self::MemFont033),
-      "memFont-034" => Ok(Self::MemFont034),
-      "memFont-035" => Ok(Self::MemFont035),
-      "memFont-036" => Ok(Self::MemFont036),
-      "memFont-037" => Ok(Self::MemFont037),
-      "memFont-038" => Ok(Self::MemFont038),
-      "memFont-039" => Ok(Self::MemFont039),
-      "memFont-040" => Ok(Self::MemFont040),
-      "memFont-041" => Ok(Self::MemFont041),
-      "memFont-042" => Ok(Self::MemFont042),
-      "memFont-043" => Ok(Self::MemFont043),
-      "memFont-044" => Ok(Self::MemFont044),
-      "memFont-045" => Ok(Self::MemFont045),
-      "memFont-046" => Ok(Self::MemFont046),
-      "memFont-047" => Ok(Self::MemFont047),
-      "memFont-048" => Ok(Self::MemFont048),
-      "memFont-049" => Ok(Self::MemFont049),
-      "memFont-050" => Ok(Self::MemFont050),
-      "memFont-051" => Ok(Self::MemFont051),
-      "memFont-052" => Ok(Self::MemFont052),
-      "memFont-053" => Ok(Self::MemFont053),
-      "memFont-054" => Ok(Self::MemFont054),
-      "memFont-055" => Ok(Self::MemFont055),
-      "memFont-056" => Ok(Self::MemFont056),
-      "memFont-057" => Ok(Self::MemFont057),
-      "memFont-058" => Ok(Self::MemFont058),
-      "memFont-059" => Ok(Self::MemFont059),
-      "memFont-060" => Ok(Self::MemFont060),
-      "memFont-061" => Ok(Self::MemFont061),
-      "memFont-062" => Ok(Self::MemFont062),
-      "memFont-063" => Ok(Self::MemFont063),
-      "memFont-064" => Ok(Self::MemFont064),
-      "memFont-065" => Ok(Self::MemFont065),
-      "memFont-066" => Ok(Self::MemFont066),
-      "memFont-067" => Ok(Self::MemFont067),
-      "memFont-068" => Ok(Self::MemFont068),
-      "memFont-069" => Ok(Self::MemFont069),
-      "memFont-070" => Ok(Self::MemFont070),
-      "memFont-071" => Ok(Self::MemFont071),
-      "memFont-072" => Ok(Self::MemFont072),
-      "memFont-073" => Ok(Self::MemFont073),
-      "memFont-074" => Ok(Self::MemFont074),
-      "memFont-075" => Ok(Self::MemFont075),
-      "memFont-076" => Ok(Self::MemFont076),
-      "memFont-077" => Ok(Self::MemFont077),
-      "memFont-078" => Ok(Self::MemFont078),
-      "memFont-079" => Ok(Self::MemFont079),
-      "memFont-080" => Ok(Self::MemFont080),
-      "memFont-081" => Ok(Self::MemFont081),
-      "memFont-082" => Ok(Self::MemFont082),
-      "memFont-083" => Ok(Self::MemFont083),
-      "memFont-084" => Ok(Self::MemFont084),
-      "memFont-085" => Ok(Self::MemFont085),
-      "memFont-086" => Ok(Self::MemFont086),
-      "memFont-087" => Ok(Self::MemFont087),
-      "memFont-088" => Ok(Self::MemFont088),
-      "memFont-089" => Ok(Self::MemFont089),
-      "memFont-090" => Ok(Self::MemFont090),
-      "memFont-091" => Ok(Self::MemFont091),
-      "memFont-092" => Ok(Self::MemFont092),
-      "memFont-093" => Ok(Self::MemFont093),
-      "memFont-094" => Ok(Self::MemFont094),
-      "memFont-095" => Ok(Self::MemFont095),
-      "memFont-096" => Ok(Self::MemFont096),
-      "memFont-097" => Ok(Self::MemFont097),
-      "memFont-098" => Ok(Self::MemFont098),
-      "memFont-099" => Ok(Self::MemFont099),
-      "memFont-100" => Ok(Self::MemFont100),
-      "memFont-101" => Ok(Self::MemFont101),
-      "memFont-102" => Ok(Self::MemFont102),
-      "memFont-103" => Ok(Self::MemFont103),
-      "memFont-104" => Ok(Self::MemFont104),
-      "memFont-105" => Ok(Self::MemFont105),
-      "memFont-106" => Ok(Self::MemFont106),
-      "memFont-107" => Ok(Self::MemFont107),
-      "memFont-108" => Ok(Self::MemFont108),
-      "memFont-109" => Ok(Self::MemFont109),
-      "memFont-110" => Ok(Self::MemFont110),
-      "memFont-111" => Ok(Self::MemFont111),
-      "memFont-112" => Ok(Self::MemFont112),
-      "memFont-113" => Ok(Self::MemFont113),
-      "memFont-114" => Ok(Self::MemFont114),
-      "memFont-115" => Ok(Self::MemFont115),
-      "memFont-116" => Ok(Self::MemFont116),
-      "memFont-117" => Ok(Self::MemFont117),
-      "memFont-118" => Ok(Self::MemFont118),
-      "memFont-119" => Ok(Self::MemFont119),
-      "memFont-120" => Ok(Self::MemFont120),
-      "memFont-121" => Ok(Self::MemFont121),
-      "memFont-122" => Ok(Self::MemFont122),
-      "memFont-123" => Ok(Self::MemFont123),
-      "memFont-124" => Ok(Self::MemFont124),
-      "memFont-125" => Ok(Self::MemFont125),
-      "memFont-126" => Ok(Self::MemFont126),
-      "memFont-127" => Ok(Self::MemFont127),
-      "monument-medium" => Ok(Self::MonumentMedium),
-      "monument-mediumShadow" => Ok(Self::MonumentMediumShadow),
-      "monument-small" => Ok(Self::MonumentSmall),
-      "monument-smallShadow" => Ok(Self::MonumentSmallShadow),
-      "moon" => Ok(Self::Moon),
-      "mountain" => Ok(Self::Mountain),
-      "mountain-shadow" => Ok(Self::MountainShadow),
-      "oddoid" => Ok(Self::Oddoid),
-      "palette-black" => Ok(Self::PaletteBlack),
-      "palette-blue" => Ok(Self::PaletteBlue),
-      "palette-darkGreen" => Ok(Self::PaletteDarkGreen),
-      "palette-darkRed" => Ok(Self::PaletteDarkRed),
-      "palette-green" => Ok(Self::PaletteGreen),
-      "palette-grey" => Ok(Self::PaletteGrey),
-      "palette-lightBlue" => Ok(Self::PaletteLightBlue),
-      "palette-lightGreen" => Ok(Self::PaletteLightGreen),
-      "palette-lightGrey" => Ok(Self::PaletteLightGrey),
-      "palette-orange" => Ok(Self::PaletteOrange),
-      "palette-paleGreen" => Ok(Self::PalettePaleGreen),
-      "palette-red" => Ok(Self::PaletteRed),
-      "palette-transparent" => Ok(Self::PaletteTransparent),
-      "palette-white" => Ok(Self::PaletteWhite),
-      "path->" => Ok(Self::PathCornerE),
-      "path-^" => Ok(Self::PathCornerN),
-      "path-/" => Ok(Self::PathNe),
-      "pig" => Ok(Self::Pig),
-      "pig-shadow" => Ok(Self::PigShadow),
-      "backpackerIcon-idle" => Ok(Self::PlayerStatusIdle),
-      "backpackerIcon-walk" => Ok(Self::PlayerStatusWalk),
-      "pond" => Ok(Self::Pond),
-      "roseBauble" => Ok(Self::RoseBauble),
-      "snake-shadow" => Ok(Self::SnakeShadow),
-      "snake" => Ok(Self::Snake),
-      "subshrub-shadow" => Ok(Self::SubshrubShadow),
-      "subshrub" => Ok(Self::Subshrub),
-      "tree-largeBare" => Ok(Self::TreeLargeBare),
-      "tree-largeShadow" => Ok(Self::TreeLargeShadow),
-      "tree-large" => Ok(Self::TreeLarge),
-      "tree-smallShadow" => Ok(Self::TreeSmallShadow),
-      "tree-small" => Ok(Self::TreeSmall),
-      "uiButton-base" => Ok(Self::UiButtonBase),
-      "uiButton-create" => Ok(Self::UiButtonCreate),
-      "uiButton-decrement" => Ok(Self::UiButtonDecrement),
-      "uiButton-destroy" => Ok(Self::UiButtonDestroy),
-      "uiButton-increment" => Ok(Self::UiButtonIncrement),
-      "uiButton-menu" => Ok(Self::UiButtonMenu),
-      "uiButton-pressed" => Ok(Self::UiButtonPressed),
-      "uiButton-toggleGrid" => Ok(Self::UiButtonToggleGrid),
-      "uiCheckerboard-blackTransparent" => {
-        Ok(Self::UiCheckerboardBlackTransparent)
-      }
-      "uiCheckerboard-blackWhite" => Ok(Self::UiCheckerboardBlackWhite),
-      "uiCheckerboard-blueGrey" => Ok(Self::UiCheckerboardBlueGrey),
-      "uiCursor-hand-pick" => Ok(Self::UiCursorHandPick),
-      "uiCursor-hand-point" => Ok(Self::UiCursorHandPoint),
-      "uiReticle" => Ok(Self::UiCursorReticle),
-      "uiDestinationMarker" => Ok(Self::UiDestinationMarker),
-      "uiGrid" => Ok(Self::UiGrid),
-      "uiSwitch" => Ok(Self::UiSwitch),
-      "uiWindowModeChart" => Ok(Self::UiWindowModeChart),
-      "uiZoomMultiplierChart" => Ok(Self::UiZoomMultiplierChart),
-      "water" => Ok(Self::Water),
-      "wave" => Ok(Self::Wave),
-      _ => Err(format!("Animation ID invalid: \"{}\".", id).into()),
-    }
+    Self::from_tag(id)
+      .ok_or_else(|| format!("Animation ID invalid: \"{}\".", id).into())
   }
 }
 
@@ -337,6 +60,7 @@ pub fn parse_animation(
   slices: &[aseprite::Slice],
 ) -> Result<Animation, AtlasParseError> {
   let direction = Playback::parse(&frame_tag.direction)?;
+  let repeat = parse_repeat(frame_tag.repeat.as_ref())?;
   let frames = parse_tag_frames(frame_tag, frame_map);
   if frames.is_empty() {
     return Err(format!("No cels in \"{}\" animation.", frame_tag.name).into());
@@ -354,14 +78,12 @@ pub fn parse_animation(
         .into(),
       );
     }
-    cels.push(parse_cel(frame_tag, frame, i.try_into()?, slices)?);
+    // Slice keys are indexed by file frame, not cel.
+    let frame_number = u32::from(frame_tag.from) + u32::try_from(i)?;
+    cels.push(parse_cel(frame_tag, frame, frame_number, slices)?);
   }
 
-  let mut duration =
-    cels.iter().fold(0., |time, Cel { duration, .. }| time + duration);
-  if direction == Playback::PingPong && cels.len() > 2 {
-    duration += duration - cels[0].duration + cels[cels.len() - 1].duration;
-  }
+  let duration = parse_total_duration(&cels, direction, repeat);
 
   if duration == 0. {
     return Err(
@@ -385,7 +107,61 @@ pub fn parse_animation(
     }
   }
 
-  Ok(Animation { size: XY::new(size.w, size.h), cels, duration, direction })
+  Ok(Animation {
+    size: XY::new(size.w, size.h),
+    cels,
+    duration,
+    direction,
+    repeat,
+  })
+}
+
+/// Returns the length of a cycle when repeat is None or the total length
+/// otherwise.
+fn parse_total_duration(
+  cels: &[Cel],
+  direction: Playback,
+  repeat: Option<u16>,
+) -> Millis {
+  let sum = cels.iter().fold(0., |time, Cel { duration, .. }| time + duration);
+  if sum.is_infinite() {
+    return sum;
+  }
+  let first = cels[0].duration;
+  let last = cels[cels.len() - 1].duration;
+  match (direction, repeat) {
+    (Playback::Forward, _) | (Playback::Reverse, _) => {
+      sum * Millis::from(repeat.unwrap_or(1))
+    }
+    // The end cels are shared by the traversal forward and backward.
+    (_, None) if cels.len() > 1 => 2. * sum - first - last,
+    (_, None) => sum,
+    // Every traversal after the first excludes the cel it turns on.
+    (_, Some(repeat)) => (1..repeat).fold(sum, |time, traversal| {
+      let turn = if (traversal % 2 == 1) == (direction == Playback::PingPong) {
+        last
+      } else {
+        first
+      };
+      time + sum - turn
+    }),
+  }
+}
+
+/// Returns None for an absent or zero repeat which loops forever.
+fn parse_repeat(
+  repeat: Option<&String>,
+) -> Result<Option<u16>, AtlasParseError> {
+  match repeat {
+    None => Ok(None),
+    Some(repeat) => match repeat.parse() {
+      Ok(0) => Ok(None),
+      Ok(repeat) => Ok(Some(repeat)),
+      Err(_) => {
+        Err(format!("Animation repeat invalid: \"{}\".", repeat).into())
+      }
+    },
+  }
 }
 
 pub fn parse_tag_frames<'a>(
@@ -406,6 +182,7 @@ impl Playback {
       "forward" => Ok(Self::Forward),
       "reverse" => Ok(Self::Reverse),
       "pingpong" => Ok(Self::PingPong),
+      "pingpong_reverse" => Ok(Self::PingPongReverse),
       _ => Err(
         format!("Animation playback direction invalid: \"{}\".", direction)
           .into(),
@@ -420,30 +197,74 @@ pub fn parse_cel(
   frame_number: u32,
   slices: &[aseprite::Slice],
 ) -> Result<Cel, AtlasParseError> {
+  if frame.rotated {
+    return Err(
+      format!(
+        "Rotated frame {} of \"{}\" animation is unsupported; disable \
+         rotation when packing.",
+        frame_number, frame_tag.name
+      )
+      .into(),
+    );
+  }
   Ok(Cel {
     bounds: parse_bounds(frame)?,
+    trim: parse_trim(frame)?,
     duration: parse_duration(frame.duration)?,
     slices: parse_slices(frame_tag, frame_number, slices)?,
+    bodies: parse_bodies(frame_tag, frame_number, slices)?,
   })
 }
 
+/// Returns the untrimmed bounds within the atlas. For a trimmed frame, these
+/// bounds may extend beyond the frame and even the atlas.
 pub fn parse_bounds(frame: &aseprite::Frame) -> Result<R16, AtlasParseError> {
   let pad = XY16::try_from(parse_padding(frame)?)
     .or(Err("XY<u16> to XY16 conversion failed."))?;
+  let trim = parse_trim(frame)?;
   Ok(R16::new_size(
-    frame.frame.x + pad.x / 2,
-    frame.frame.y + pad.y / 2,
+    frame.frame.x + pad.x / 2 - trim.from.x,
+    frame.frame.y + pad.y / 2 - trim.from.y,
     frame.source_size.w.try_into()?,
     frame.source_size.h.try_into()?,
   ))
 }
 
+/// Returns the region of the source kept in the atlas in local pixels.
+pub fn parse_trim(
+  aseprite::Frame { trimmed, sprite_source_size, source_size, .. }: &aseprite::Frame,
+) -> Result<R16, AtlasParseError> {
+  if !trimmed {
+    return R16::cast_from_size(0, 0, source_size.w, source_size.h)
+      .ok_or_else(|| "Cel size conversion to R16 failed.".into());
+  }
+  let aseprite::Rect { x, y, w, h } = sprite_source_size;
+  let trim = R16::cast_from_size(*x, *y, *w, *h)
+    .ok_or("Cel trim conversion to R16 failed.")?;
+  let size = XY16::try_from(XY::new(source_size.w, source_size.h))
+    .or(Err("XY<u16> to XY16 conversion failed."))?;
+  if trim.from.x < 0
+    || trim.from.y < 0
+    || trim.to.x > size.x
+    || trim.to.y > size.y
+  {
+    return Err("Cel trim exceeds source size.".into());
+  }
+  Ok(trim)
+}
+
 /// Returns evenly divisible padding.
 pub fn parse_padding(
-  aseprite::Frame { frame, source_size, .. }: &aseprite::Frame,
+  aseprite::Frame { frame, trimmed, sprite_source_size, source_size, .. }: &aseprite::Frame,
 ) -> Result<XY<u16>, AtlasParseError> {
-  let w = frame.w - source_size.w;
-  let h = frame.h - source_size.h;
+  // The frame only includes the trimmed image.
+  let (w, h) = if *trimmed {
+    (sprite_source_size.w, sprite_source_size.h)
+  } else {
+    (source_size.w, source_size.h)
+  };
+  let w = frame.w.checked_sub(w).ok_or("Cel frame narrower than source.")?;
+  let h = frame.h.checked_sub(h).ok_or("Cel frame shorter than source.")?;
   if is_odd(w) || is_odd(h) {
     return Err("Cel padding is not evenly divisible.".into());
   }
@@ -466,7 +287,7 @@ pub fn parse_duration(
 
 pub fn parse_slices(
   aseprite::FrameTag { name, .. }: &aseprite::FrameTag,
-  index: u32,
+  frame_number: u32,
   slices: &[aseprite::Slice],
 ) -> Result<Vec<R16>, AtlasParseError> {
   let mut rects = Vec::new();
@@ -474,18 +295,66 @@ pub fn parse_slices(
     if slice.name != *name {
       continue;
     }
-    // For each Slice, get the greatest relevant Key.
-    let key =
-      slice.keys.iter().filter(|key| key.frame <= index).last().unwrap();
-    let aseprite::Key { bounds, .. } = key;
-    rects.push(
-      R16::cast_from_size(bounds.x, bounds.y, bounds.w, bounds.h)
-        .ok_or("Slice bounds conversion to R16 failed.")?,
-    );
+    if let Some(rect) = parse_key_bounds(slice, frame_number)? {
+      rects.push(rect);
+    }
   }
   Ok(rects)
 }
 
+/// Returns the collision bodies of slices named "{tag} {kind}" for the frame.
+/// Other slices, including those of tags that share a prefix, are ignored.
+pub fn parse_bodies(
+  aseprite::FrameTag { name, .. }: &aseprite::FrameTag,
+  frame_number: u32,
+  slices: &[aseprite::Slice],
+) -> Result<Vec<Body>, AtlasParseError> {
+  let prefix = format!("{} ", name);
+  let mut bodies = Vec::new();
+  for slice in slices {
+    if !slice.name.starts_with(&prefix) {
+      continue;
+    }
+    let kind = match BodyKind::parse(&slice.name[prefix.len()..]) {
+      Some(kind) => kind,
+      None => continue,
+    };
+    if let Some(bounds) = parse_key_bounds(slice, frame_number)? {
+      bodies.push(Body { kind, bounds });
+    }
+  }
+  Ok(bodies)
+}
+
+impl BodyKind {
+  fn parse(kind: &str) -> Option<Self> {
+    match kind {
+      "hitbox" => Some(Self::Hitbox),
+      "hurtbox" => Some(Self::Hurtbox),
+      "feet" => Some(Self::Feet),
+      _ => None,
+    }
+  }
+}
+
+/// Returns the bounds of the greatest Key at or before the frame or None if
+/// the Slice starts after it.
+fn parse_key_bounds(
+  slice: &aseprite::Slice,
+  frame_number: u32,
+) -> Result<Option<R16>, AtlasParseError> {
+  let key =
+    match slice.keys.iter().filter(|key| key.frame <= frame_number).last() {
+      Some(key) => key,
+      None => return Ok(None),
+    };
+  let aseprite::Key { bounds, .. } = key;
+  Ok(Some(
+    R16::cast_from_size(bounds.x, bounds.y, bounds.w, bounds.h)
+      .ok_or("Slice bounds conversion to R16 failed.")?,
+  ))
+}
+
 #[derive(Debug)]
 pub struct AtlasParseError(pub String);
 
@@ -612,14 +481,17 @@ mod test {
         size: XY::new(16, 16),
         cels: vec![Cel {
           bounds: R16::new_size(221, 19, 16, 16),
+          trim: R16::new_size(0, 0, 16, 16),
           duration: 1.,
           slices: vec![R16 {
             from: XY { x: 8, y: 12 },
             to: XY { x: 10, y: 15 },
           }],
+          bodies: vec![],
         }],
         duration: 1.,
         direction: Playback::Forward,
+        repeat: None,
       },
     );
     expected.insert(
@@ -628,14 +500,17 @@ mod test {
         size: XY::new(16, 16),
         cels: vec![Cel {
           bounds: R16::new_size(91, 55, 16, 16),
+          trim: R16::new_size(0, 0, 16, 16),
           duration: f64::INFINITY,
           slices: vec![R16 {
             from: XY { x: 7, y: 11 },
             to: XY { x: 10, y: 15 },
           }],
+          bodies: vec![],
         }],
         duration: f64::INFINITY,
         direction: Playback::Forward,
+        repeat: None,
       },
     );
     expected.insert(
@@ -644,14 +519,17 @@ mod test {
         size: XY::new(16, 16),
         cels: vec![Cel {
           bounds: R16::new_size(73, 55, 16, 16),
+          trim: R16::new_size(0, 0, 16, 16),
           duration: f64::INFINITY,
           slices: vec![R16 {
             from: XY { x: 7, y: 10 },
             to: XY { x: 10, y: 15 },
           }],
+          bodies: vec![],
         }],
         duration: f64::INFINITY,
         direction: Playback::Forward,
+        repeat: None,
       },
     );
     expected.insert(
@@ -660,14 +538,17 @@ mod test {
         size: XY::new(16, 16),
         cels: vec![Cel {
           bounds: R16::new_size(55, 55, 16, 16),
+          trim: R16::new_size(0, 0, 16, 16),
           duration: f64::INFINITY,
           slices: vec![R16 {
             from: XY { x: 7, y: 9 },
             to: XY { x: 10, y: 15 },
           }],
+          bodies: vec![],
         }],
         duration: f64::INFINITY,
         direction: Playback::Forward,
+        repeat: None,
       },
     );
     assert_eq!(super::parse_animation_map(&file).unwrap(), expected);
@@ -690,6 +571,7 @@ mod test {
       from: 1,
       to: 1,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let mut frames = HashMap::new();
     frames.insert(
@@ -757,14 +639,17 @@ mod test {
         size: XY::new(16, 16),
         cels: vec![Cel {
           bounds: R16::new_size(185, 37, 16, 16),
+          trim: R16::new_size(0, 0, 16, 16),
           duration: f64::INFINITY,
           slices: vec![R16 {
             from: XY { x: 4, y: 11 },
             to: XY { x: 13, y: 15 }
-          }]
+          }],
+          bodies: vec![]
         }],
         duration: f64::INFINITY,
-        direction: Playback::Forward
+        direction: Playback::Forward,
+        repeat: None
       }
     );
   }
@@ -774,6 +659,115 @@ mod test {
     assert_eq!(Playback::parse("forward").unwrap(), Playback::Forward);
     assert_eq!(Playback::parse("reverse").unwrap(), Playback::Reverse);
     assert_eq!(Playback::parse("pingpong").unwrap(), Playback::PingPong);
+    assert_eq!(
+      Playback::parse("pingpong_reverse").unwrap(),
+      Playback::PingPongReverse
+    );
+  }
+
+  #[test]
+  fn parse_repeat_valid() {
+    [
+      (None, None),
+      (Some("0"), None),
+      (Some("1"), Some(1)),
+      (Some("3"), Some(3)),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (repeat, expected))| {
+      let repeat = repeat.map(str::to_string);
+      assert_eq!(
+        parse_repeat(repeat.as_ref()).unwrap(),
+        *expected,
+        "Case {} failed.",
+        i
+      )
+    });
+  }
+
+  #[test]
+  fn parse_repeat_invalid() {
+    assert!(parse_repeat(Some(&"-1".to_string())).is_err());
+    assert!(parse_repeat(Some(&"many".to_string())).is_err());
+  }
+
+  #[test]
+  fn parse_total_duration() {
+    let cels: Vec<_> = [1., 2., 4.]
+      .iter()
+      .map(|&duration| Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration,
+        slices: vec![],
+        bodies: vec![],
+      })
+      .collect();
+    [
+      (Playback::Forward, None, 7.),
+      (Playback::Reverse, Some(3), 21.),
+      (Playback::PingPong, None, 9.),
+      (Playback::PingPongReverse, None, 9.),
+      (Playback::PingPong, Some(1), 7.),
+      // 0 1 2 1 0 1 2.
+      (Playback::PingPong, Some(3), 16.),
+      // 2 1 0 1 2 1 0.
+      (Playback::PingPongReverse, Some(3), 16.),
+      (Playback::PingPongReverse, Some(2), 13.),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (direction, repeat, expected))| {
+      assert_eq!(
+        super::parse_total_duration(&cels, *direction, *repeat),
+        *expected,
+        "Case {} failed.",
+        i
+      )
+    });
+  }
+
+  #[test]
+  fn parse_total_duration_infinite() {
+    let cels = vec![
+      Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      },
+      Cel {
+        bounds: R16::new(0, 0, 0, 0),
+        trim: R16::new(0, 0, 0, 0),
+        duration: f64::INFINITY,
+        slices: vec![],
+        bodies: vec![],
+      },
+    ];
+    assert_eq!(
+      super::parse_total_duration(&cels, Playback::PingPong, Some(2)),
+      f64::INFINITY
+    );
+  }
+
+  #[test]
+  fn parse_frame_tag_repeat() {
+    let frame_tag: aseprite::FrameTag = from_json!({
+      "name": "frog-eat",
+      "from": 0,
+      "to": 2,
+      "direction": "pingpong_reverse",
+      "repeat": "2"
+    })
+    .unwrap();
+    assert_eq!(frame_tag.repeat, Some("2".to_string()));
+    let frame_tag: aseprite::FrameTag = from_json!({
+      "name": "frog-eat", "from": 0, "to": 2, "direction": "forward"
+    })
+    .unwrap();
+    assert_eq!(frame_tag.repeat, None);
   }
 
   #[test]
@@ -788,6 +782,7 @@ mod test {
       from: 0,
       to: 0,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let frame = aseprite::Frame {
       frame: aseprite::Rect { x: 130, y: 18, w: 18, h: 18 },
@@ -809,8 +804,10 @@ mod test {
       super::parse_cel(&frame_tag, &frame, 0, &slices).unwrap(),
       Cel {
         bounds: R16::new_size(131, 19, 16, 16),
+        trim: R16::new_size(0, 0, 16, 16),
         duration: f64::INFINITY,
-        slices: vec![R16 { from: XY { x: 4, y: 4 }, to: XY { x: 12, y: 16 } }]
+        slices: vec![R16 { from: XY { x: 4, y: 4 }, to: XY { x: 12, y: 16 } }],
+        bodies: vec![]
       }
     );
   }
@@ -841,6 +838,67 @@ mod test {
     assert_eq!(parse_bounds(&frame).unwrap(), R16::new_size(2, 3, 3, 4));
   }
 
+  #[test]
+  fn parse_bounds_trimmed() {
+    let frame = aseprite::Frame {
+      frame: aseprite::Rect { x: 10, y: 20, w: 5, h: 6 },
+      rotated: false,
+      trimmed: true,
+      sprite_source_size: aseprite::Rect { x: 2, y: 1, w: 3, h: 4 },
+      source_size: aseprite::Size { w: 8, h: 9 },
+      duration: 1,
+    };
+    assert_eq!(parse_padding(&frame).unwrap(), XY::new(2, 2));
+    assert_eq!(parse_trim(&frame).unwrap(), R16::new_size(2, 1, 3, 4));
+    assert_eq!(parse_bounds(&frame).unwrap(), R16::new_size(9, 20, 8, 9));
+  }
+
+  #[test]
+  fn parse_trim_untrimmed() {
+    let frame = aseprite::Frame {
+      frame: aseprite::Rect { x: 1, y: 2, w: 3, h: 4 },
+      rotated: false,
+      trimmed: false,
+      sprite_source_size: aseprite::Rect { x: 0, y: 0, w: 3, h: 4 },
+      source_size: aseprite::Size { w: 3, h: 4 },
+      duration: 1,
+    };
+    assert_eq!(parse_trim(&frame).unwrap(), R16::new_size(0, 0, 3, 4));
+  }
+
+  #[test]
+  fn parse_trim_invalid() {
+    let frame = aseprite::Frame {
+      frame: aseprite::Rect { x: 1, y: 2, w: 3, h: 4 },
+      rotated: false,
+      trimmed: true,
+      sprite_source_size: aseprite::Rect { x: 1, y: 0, w: 3, h: 4 },
+      source_size: aseprite::Size { w: 3, h: 4 },
+      duration: 1,
+    };
+    assert!(parse_trim(&frame).is_err());
+  }
+
+  #[test]
+  fn parse_cel_rotated() {
+    let frame_tag = aseprite::FrameTag {
+      name: "stem ".to_string(),
+      from: 0,
+      to: 0,
+      direction: "forward".to_string(),
+      repeat: None,
+    };
+    let frame = aseprite::Frame {
+      frame: aseprite::Rect { x: 1, y: 2, w: 3, h: 4 },
+      rotated: true,
+      trimmed: false,
+      sprite_source_size: aseprite::Rect { x: 0, y: 0, w: 3, h: 4 },
+      source_size: aseprite::Size { w: 3, h: 4 },
+      duration: 1,
+    };
+    assert!(super::parse_cel(&frame_tag, &frame, 0, &[]).is_err());
+  }
+
   #[test]
   fn parse_padding_zero() {
     let frame = aseprite::Frame {
@@ -910,6 +968,7 @@ mod test {
       from: 0,
       to: 0,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [aseprite::Slice {
       name: "stem ".to_string(),
@@ -932,6 +991,7 @@ mod test {
       from: 0,
       to: 0,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [aseprite::Slice {
       name: "unrelated ".to_string(),
@@ -951,6 +1011,7 @@ mod test {
       from: 0,
       to: 2,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [aseprite::Slice {
       name: "stem ".to_string(),
@@ -983,6 +1044,7 @@ mod test {
       from: 0,
       to: 1,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [aseprite::Slice {
       name: "stem ".to_string(),
@@ -1011,6 +1073,7 @@ mod test {
       from: 0,
       to: 0,
       direction: "forward".to_string(),
+      repeat: None,
     };
     assert_eq!(parse_slices(&frame_tag, 0, &[]).unwrap(), vec![]);
   }
@@ -1022,6 +1085,7 @@ mod test {
       from: 0,
       to: 1,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [aseprite::Slice {
       name: "stem ".to_string(),
@@ -1044,6 +1108,7 @@ mod test {
       from: 0,
       to: 1,
       direction: "forward".to_string(),
+      repeat: None,
     };
     let slices = [
       aseprite::Slice {
@@ -1098,4 +1163,118 @@ mod test {
       ]
     );
   }
+
+  #[test]
+  fn parse_slices_before_key() {
+    let frame_tag = aseprite::FrameTag {
+      name: "stem ".to_string(),
+      from: 0,
+      to: 1,
+      direction: "forward".to_string(),
+      repeat: None,
+    };
+    let slices = [aseprite::Slice {
+      name: "stem ".to_string(),
+      color: "#00000000".to_string(),
+      keys: vec![aseprite::Key {
+        frame: 1,
+        bounds: aseprite::Rect { x: 0, y: 1, w: 2, h: 3 },
+      }],
+    }];
+    assert_eq!(parse_slices(&frame_tag, 0, &slices).unwrap(), vec![]);
+  }
+
+  #[test]
+  fn parse_bodies_kinds() {
+    let frame_tag = aseprite::FrameTag {
+      name: "frog".to_string(),
+      from: 0,
+      to: 1,
+      direction: "forward".to_string(),
+      repeat: None,
+    };
+    let slice = |name: &str, frame, x| aseprite::Slice {
+      name: name.to_string(),
+      color: "#0000ffff".to_string(),
+      keys: vec![aseprite::Key {
+        frame,
+        bounds: aseprite::Rect { x, y: 1, w: 2, h: 3 },
+      }],
+    };
+    let slices = [
+      slice("frog hitbox", 1, 0),
+      slice("frog hurtbox", 0, 1),
+      slice("frog feet", 0, 2),
+      slice("frog unknown", 0, 3),
+      slice("frog", 0, 4),
+      slice("frog-shadow hitbox", 0, 5),
+    ];
+    [
+      (
+        0,
+        vec![
+          Body { kind: BodyKind::Hurtbox, bounds: R16::new_size(1, 1, 2, 3) },
+          Body { kind: BodyKind::Feet, bounds: R16::new_size(2, 1, 2, 3) },
+        ],
+      ),
+      (
+        1,
+        vec![
+          Body { kind: BodyKind::Hitbox, bounds: R16::new_size(0, 1, 2, 3) },
+          Body { kind: BodyKind::Hurtbox, bounds: R16::new_size(1, 1, 2, 3) },
+          Body { kind: BodyKind::Feet, bounds: R16::new_size(2, 1, 2, 3) },
+        ],
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (frame_number, expected))| {
+      assert_eq!(
+        &parse_bodies(&frame_tag, *frame_number, &slices).unwrap(),
+        expected,
+        "Case {} failed.",
+        i
+      )
+    });
+  }
+
+  #[test]
+  fn parse_animation_file_frame_keys() {
+    let frame_tag = aseprite::FrameTag {
+      name: "bee".to_string(),
+      from: 2,
+      to: 3,
+      direction: "forward".to_string(),
+      repeat: None,
+    };
+    let mut frames = HashMap::new();
+    for i in 2..=3 {
+      frames.insert(
+        format!("bee {}", i),
+        aseprite::Frame {
+          frame: aseprite::Rect { x: i * 16, y: 0, w: 16, h: 16 },
+          rotated: false,
+          trimmed: false,
+          sprite_source_size: aseprite::Rect { x: 0, y: 0, w: 16, h: 16 },
+          source_size: aseprite::Size { w: 16, h: 16 },
+          duration: 1,
+        },
+      );
+    }
+    let slices = [aseprite::Slice {
+      name: "bee hitbox".to_string(),
+      color: "#0000ffff".to_string(),
+      keys: vec![aseprite::Key {
+        frame: 3,
+        bounds: aseprite::Rect { x: 4, y: 5, w: 6, h: 7 },
+      }],
+    }];
+    let animation =
+      super::parse_animation(&frame_tag, &frames, &slices).unwrap();
+    assert_eq!(animation.cels[0].bodies, vec![]);
+    assert_eq!(
+      animation.cels[1].bodies,
+      vec![Body { kind: BodyKind::Hitbox, bounds: R16::new_size(4, 5, 6, 7) }]
+    );
+  }
 }
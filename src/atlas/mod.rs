@@ -1,8 +1,10 @@
 mod animation_id;
 mod animator;
-mod aseprite;
+pub mod ase;
+pub mod aseprite;
 mod atlas;
 mod atlas_parser;
+pub mod packer;
 
 pub use animation_id::*;
 pub use animator::*;
@@ -0,0 +1,388 @@
+//! Packs the Frames of Aseprite files into a single power of two sheet and the
+//! JSON model describing it, the equivalent of the Aseprite CLI export options
+//! documented in aseprite.rs. Identical Frames are merged and placement
+//! depends only on the input so the same files always produce identical
+//! output.
+
+use super::ase::{self, Ase, AseParseError, Layout};
+use super::aseprite;
+use crate::math::{R16, XY};
+use crate::utils::png;
+use std::collections::HashMap;
+
+/// The largest sheet side supported. WebGL guarantees at least 2048 but 4096
+/// is nearly universal.
+const MAX_SIDE: u32 = 4096;
+
+pub struct Sheet {
+  /// The PNG image.
+  pub image: Vec<u8>,
+  pub file: aseprite::File,
+}
+
+/// Returns the sheet for the files which are expected in name order. filename
+/// is the image basename referenced by the JSON. Each Frame is surrounded by
+/// padding transparent pixels. When trim is set, the transparent border of
+/// each Frame is excluded from the sheet.
+pub fn pack(
+  ases: &[Ase],
+  filename: &str,
+  padding: u16,
+  trim: bool,
+) -> Result<Sheet, AseParseError> {
+  // The size and pixels of each distinct image.
+  let mut sources = Vec::new();
+  // The sources index of each Frame.
+  let mut frame_sources = HashMap::new();
+  let mut source_by_pixels = HashMap::new();
+  let mut trims = HashMap::new();
+  for (file, tag) in ase::tags(ases) {
+    for i in usize::from(tag.from)..=usize::from(tag.to) {
+      if frame_sources.contains_key(&(file, i)) {
+        continue;
+      }
+      let size = &ases[file].size;
+      let mut pixels = ases[file].render(i);
+      let mut image_size = size.clone();
+      if trim {
+        let bounds = opaque_bounds(&pixels, size);
+        pixels = crop(&pixels, size.x, &bounds);
+        image_size = XY::new(
+          (bounds.to.x - bounds.from.x) as u16,
+          (bounds.to.y - bounds.from.y) as u16,
+        );
+        trims.insert((file, i), bounds);
+      }
+      let len = sources.len();
+      let key = (image_size.x, image_size.y, pixels);
+      let source = *source_by_pixels.entry(key.clone()).or_insert(len);
+      if source == len {
+        sources.push(key);
+      }
+      frame_sources.insert((file, i), source);
+    }
+  }
+
+  let sizes: Vec<_> = sources
+    .iter()
+    .map(|&(w, h, _)| {
+      let pad = u32::from(padding) * 2;
+      XY::new(u32::from(w) + pad, u32::from(h) + pad)
+    })
+    .collect();
+  let (side, positions) = place(&sizes)?;
+
+  let mut pixels = vec![0; side as usize * side as usize * 4];
+  for ((w, _, image), position) in sources.iter().zip(&positions) {
+    let x = position.x + u32::from(padding);
+    let y = position.y + u32::from(padding);
+    blit(&mut pixels, side, image, *w, x, y);
+  }
+
+  let side16 = side as u16;
+  let layout = Layout {
+    image: filename.to_string(),
+    size: XY::new(side16, side16),
+    padding,
+    positions: frame_sources
+      .into_iter()
+      .map(|(frame, source)| {
+        let XY { x, y } = &positions[source];
+        (frame, XY::new(*x as u16, *y as u16))
+      })
+      .collect(),
+    trims,
+  };
+  Ok(Sheet {
+    image: png::encode(side, side, &pixels),
+    file: ase::to_file(ases, &layout)?,
+  })
+}
+
+/// Returns the bounds of the pixels that are not fully transparent. A fully
+/// transparent image keeps its top-left pixel since empty Frames are invalid.
+fn opaque_bounds(pixels: &[u8], size: &XY<u16>) -> R16 {
+  let width = usize::from(size.x);
+  let mut bounds: Option<R16> = None;
+  for (i, pixel) in pixels.chunks(4).enumerate() {
+    if pixel[3] == 0 {
+      continue;
+    }
+    let x = (i % width) as i16;
+    let y = (i / width) as i16;
+    let pixel = R16::new(x, y, x + 1, y + 1);
+    bounds = Some(match bounds {
+      Some(bounds) => bounds.union(&pixel),
+      None => pixel,
+    });
+  }
+  bounds.unwrap_or_else(|| R16::new(0, 0, 1, 1))
+}
+
+/// Returns the RGBA pixels of the region within an image of the given width.
+fn crop(pixels: &[u8], width: u16, region: &R16) -> Vec<u8> {
+  let row_len = usize::from(width) * 4;
+  let from = region.from.x as usize * 4;
+  let to = region.to.x as usize * 4;
+  pixels
+    .chunks(row_len)
+    .skip(region.from.y as usize)
+    .take((region.to.y - region.from.y) as usize)
+    .flat_map(|row| row[from..to].iter().cloned())
+    .collect()
+}
+
+/// Returns the smallest power of two square side the rectangles fit in and
+/// their positions. Rectangles are placed on shelves tallest first; ties are
+/// broken by width and then by order.
+fn place(sizes: &[XY<u32>]) -> Result<(u32, Vec<XY<u32>>), AseParseError> {
+  let mut order: Vec<_> = (0..sizes.len()).collect();
+  order.sort_by(|&lhs, &rhs| {
+    (sizes[rhs].y, sizes[rhs].x, lhs).cmp(&(sizes[lhs].y, sizes[lhs].x, rhs))
+  });
+
+  let area: u32 = sizes.iter().map(|size| size.x * size.y).sum();
+  let mut side =
+    ((area as f64).sqrt().ceil() as u32).max(1).next_power_of_two();
+  while side <= MAX_SIDE {
+    if let Some(positions) = place_shelves(sizes, &order, side) {
+      return Ok((side, positions));
+    }
+    side *= 2;
+  }
+  Err(format!("Frames exceed {}x{} sheet.", MAX_SIDE, MAX_SIDE).into())
+}
+
+fn place_shelves(
+  sizes: &[XY<u32>],
+  order: &[usize],
+  side: u32,
+) -> Option<Vec<XY<u32>>> {
+  let mut positions = vec![XY::new(0, 0); sizes.len()];
+  let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+  for &i in order {
+    let size = &sizes[i];
+    if x + size.x > side {
+      x = 0;
+      y += shelf_height;
+      shelf_height = 0;
+    }
+    if x + size.x > side || y + size.y > side {
+      return None;
+    }
+    positions[i] = XY::new(x, y);
+    x += size.x;
+    shelf_height = shelf_height.max(size.y);
+  }
+  Some(positions)
+}
+
+/// Copies RGBA pixels of the given width into the sheet at x, y.
+fn blit(
+  sheet: &mut [u8],
+  side: u32,
+  pixels: &[u8],
+  width: u16,
+  x: u32,
+  y: u32,
+) {
+  let row_len = usize::from(width) * 4;
+  if row_len == 0 {
+    return;
+  }
+  for (row, src) in pixels.chunks(row_len).enumerate() {
+    let start = ((y as usize + row) * side as usize + x as usize) * 4;
+    sheet[start..start + row_len].copy_from_slice(src);
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas;
+  use crate::utils::zlib;
+
+  fn read_assets() -> Vec<Ase> {
+    let mut paths: Vec<_> = std::fs::read_dir("assets/atlas")
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .collect();
+    paths.sort();
+    paths
+      .iter()
+      .map(|path| ase::read(&std::fs::read(path).unwrap()).unwrap())
+      .collect()
+  }
+
+  /// Returns the RGBA pixels of a PNG encoded by png::encode().
+  fn decode(png: &[u8]) -> (u32, Vec<u8>) {
+    let side = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let len = u32::from_be_bytes([png[33], png[34], png[35], png[36]]);
+    let scanlines = zlib::inflate(&png[41..41 + len as usize]).unwrap();
+    let pixels = scanlines
+      .chunks(side as usize * 4 + 1)
+      .flat_map(|scanline| scanline[1..].to_vec())
+      .collect();
+    (side, pixels)
+  }
+
+  #[test]
+  fn place_power_of_two() {
+    [
+      (vec![], 1),
+      (vec![XY::new(1, 1)], 1),
+      (vec![XY::new(3, 2)], 4),
+      (vec![XY::new(2, 2); 4], 4),
+      (vec![XY::new(2, 2); 5], 8),
+      (vec![XY::new(5, 1), XY::new(1, 5)], 8),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (sizes, side))| {
+      assert_eq!(place(sizes).unwrap().0, *side, "Case {} failed.", i)
+    });
+  }
+
+  #[test]
+  fn place_too_large() {
+    assert!(place(&[XY::new(MAX_SIDE + 1, 1)]).is_err());
+  }
+
+  #[test]
+  fn place_disjoint() {
+    let sizes: Vec<_> =
+      (1..40).map(|i| XY::new(i % 7 + 1, i % 5 + 1)).collect();
+    let (side, positions) = place(&sizes).unwrap();
+    for (i, (lhs, lhs_size)) in positions.iter().zip(&sizes).enumerate() {
+      assert!(lhs.x + lhs_size.x <= side && lhs.y + lhs_size.y <= side);
+      for (rhs, rhs_size) in positions.iter().zip(&sizes).skip(i + 1) {
+        let disjoint = lhs.x + lhs_size.x <= rhs.x
+          || rhs.x + rhs_size.x <= lhs.x
+          || lhs.y + lhs_size.y <= rhs.y
+          || rhs.y + rhs_size.y <= lhs.y;
+        assert!(disjoint, "{:?} overlaps {:?}.", lhs, rhs);
+      }
+    }
+  }
+
+  #[test]
+  fn pack_bee() {
+    let bee = ase::read(include_bytes!("../../assets/atlas/bee.aseprite"));
+    let ases = vec![bee.unwrap()];
+    let sheet = pack(&ases, "atlas.png", 1, false).unwrap();
+    let (side, pixels) = decode(&sheet.image);
+    // Five padded 7x7 Frames fit in 16x16 by area but not by shelf.
+    assert_eq!((sheet.file.meta.size.w, sheet.file.meta.size.h), (32, 32));
+    assert_eq!(side, 32);
+
+    for i in 0..5 {
+      let tag = &sheet.file.meta.frame_tags[sheet
+        .file
+        .meta
+        .frame_tags
+        .iter()
+        .position(|tag| usize::from(tag.from) <= i && i <= usize::from(tag.to))
+        .unwrap()];
+      let frame = &sheet.file.frames[&format!("{} {}", tag.name, i)];
+      assert_eq!((frame.frame.w, frame.frame.h), (7, 7));
+      let expected = ases[0].render(i);
+      for y in 0..5 {
+        let start = ((frame.frame.y as usize + 1 + y) * side as usize
+          + frame.frame.x as usize
+          + 1)
+          * 4;
+        assert_eq!(
+          pixels[start..start + 5 * 4],
+          expected[y * 5 * 4..(y + 1) * 5 * 4],
+          "Frame {} row {} mismatch.",
+          i,
+          y
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn pack_assets() {
+    let ases = read_assets();
+    let sheet = pack(&ases, "atlas.png", 0, false).unwrap();
+    assert!(sheet.file.meta.size.w.is_power_of_two());
+    atlas::parse(&sheet.file).unwrap_or_else(|error| panic!("{}", error.0));
+
+    // Identical images share bounds.
+    let frames = &sheet.file.frames;
+    assert_eq!(
+      frames["memFont-000 0"].frame.x,
+      frames["memFont-001 1"].frame.x
+    );
+    assert_eq!(
+      frames["memFont-000 0"].frame.y,
+      frames["memFont-001 1"].frame.y
+    );
+  }
+
+  #[test]
+  fn pack_deterministic() {
+    let ases = read_assets();
+    let lhs = pack(&ases, "atlas.png", 0, false).unwrap();
+    let rhs = pack(&ases, "atlas.png", 0, false).unwrap();
+    assert_eq!(lhs.image, rhs.image);
+    assert_eq!(
+      serde_json::to_string(&lhs.file).unwrap(),
+      serde_json::to_string(&rhs.file).unwrap()
+    );
+  }
+
+  #[test]
+  fn opaque_bounds_crop() {
+    let mut pixels = vec![0; 4 * 3 * 4];
+    // Opaque pixels at (1, 0) and (2, 1) of a 4x3 image.
+    pixels[7] = 0xff;
+    pixels[24..28].copy_from_slice(&[1, 2, 3, 4]);
+    let bounds = opaque_bounds(&pixels, &XY::new(4, 3));
+    assert_eq!(bounds, R16::new(1, 0, 3, 2));
+    assert_eq!(
+      crop(&pixels, 4, &bounds),
+      vec![0, 0, 0, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 4]
+    );
+    assert_eq!(
+      opaque_bounds(&vec![0; 2 * 2 * 4], &XY::new(2, 2)),
+      R16::new(0, 0, 1, 1)
+    );
+  }
+
+  #[test]
+  fn pack_trimmed() {
+    let ases = read_assets();
+    let untrimmed = pack(&ases, "atlas.png", 0, false).unwrap();
+    let sheet = pack(&ases, "atlas.png", 1, true).unwrap();
+    atlas::parse(&sheet.file).unwrap_or_else(|error| panic!("{}", error.0));
+    assert!(sheet.file.meta.size.w <= untrimmed.file.meta.size.w);
+
+    // Every Frame renders identically from the trimmed sheet.
+    let (side, pixels) = decode(&sheet.image);
+    for (file, tag) in ase::tags(&ases) {
+      let ase = &ases[file];
+      for i in usize::from(tag.from)..=usize::from(tag.to) {
+        let frame = &sheet.file.frames[&format!("{} {}", tag.name, i)];
+        let trim = &frame.sprite_source_size;
+        let mut actual = vec![0; ase.render(i).len()];
+        for y in 0..usize::from(trim.h) {
+          for x in 0..usize::from(trim.w) {
+            let src = ((frame.frame.y as usize + 1 + y) * side as usize
+              + frame.frame.x as usize
+              + 1
+              + x)
+              * 4;
+            let dst = ((trim.y as usize + y) * usize::from(ase.size.x)
+              + trim.x as usize
+              + x)
+              * 4;
+            actual[dst..dst + 4].copy_from_slice(&pixels[src..src + 4]);
+          }
+        }
+        assert_eq!(actual, ase.render(i), "{} {} mismatch.", tag.name, i);
+      }
+    }
+  }
+}
@@ -0,0 +1,50 @@
+//! Reports problems in the exported atlas and the Blueprints that reference it,
+//! including every error atlas parsing would reject, followed by a timing
+//! summary of every animation. Exits nonzero when the atlas is invalid or any
+//! error is reported. Warnings are informational.
+//!
+//! Usage: lint-atlas [source directory]
+//!
+//! The source directory defaults to src.
+
+use nature_elsewhere::lint::Report;
+use nature_elsewhere::manufacturer::blueprint_paths;
+use std::path::Path;
+use std::{env, fs, process};
+
+fn main() {
+  match run(env::args().skip(1).collect()) {
+    Ok(true) => (),
+    Ok(false) => process::exit(1),
+    Err(error) => {
+      eprintln!("{}", error);
+      process::exit(1);
+    }
+  }
+}
+
+/// Returns true when no errors are reported.
+fn run(args: Vec<String>) -> Result<bool, String> {
+  if args.len() > 1 {
+    return Err("Usage: lint-atlas [source directory]".to_string());
+  }
+  let input = Path::new(args.get(0).map_or("src", String::as_str));
+
+  // Served paths are absolute but relative to the source directory.
+  let report = Report::new(
+    |path| {
+      fs::read_to_string(input.join(path.trim_start_matches('/')))
+        .map_err(|error| error.to_string())
+    },
+    &blueprint_paths(input).map_err(|error| error.0)?,
+  )
+  .map_err(|error| error.0)?;
+
+  for timing in &report.timings {
+    println!("{}", timing);
+  }
+  for problem in &report.problems {
+    println!("{}", problem);
+  }
+  Ok(!report.has_errors())
+}
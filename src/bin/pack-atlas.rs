@@ -0,0 +1,79 @@
+//! Packs every .aseprite file in a directory into atlas.png and atlas.json
+//! without the Aseprite app. The output is equivalent to the Aseprite CLI
+//! export documented in src/atlas/aseprite.rs.
+//!
+//! Usage: pack-atlas [--inner-padding n] [--trim] [input directory] [output
+//! directory]
+//!
+//! The input directory defaults to assets/atlas and the output directory to
+//! src/atlas. --trim excludes the transparent border of each frame.
+
+use nature_elsewhere::atlas::{ase, packer};
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+static IMAGE_FILENAME: &str = "atlas.png";
+static JSON_FILENAME: &str = "atlas.json";
+
+fn main() {
+  if let Err(error) = run(env::args().skip(1).collect()) {
+    eprintln!("{}", error);
+    process::exit(1);
+  }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+  let mut padding = 0;
+  let mut trim = false;
+  let mut dirs = Vec::new();
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    if arg == "--inner-padding" {
+      let val = args.next().ok_or("--inner-padding value missing.")?;
+      padding = val
+        .parse()
+        .map_err(|_| format!("--inner-padding value invalid: \"{}\".", val))?;
+    } else if arg == "--trim" {
+      trim = true;
+    } else {
+      dirs.push(PathBuf::from(arg));
+    }
+  }
+  if dirs.len() > 2 {
+    return Err(
+      "Usage: pack-atlas [--inner-padding n] [--trim] [input directory] \
+       [output directory]"
+        .to_string(),
+    );
+  }
+  let input = dirs.get(0).map_or(Path::new("assets/atlas"), PathBuf::as_path);
+  let output = dirs.get(1).map_or(Path::new("src/atlas"), PathBuf::as_path);
+
+  let mut paths = Vec::new();
+  for entry in fs::read_dir(input).map_err(|error| error.to_string())? {
+    let path = entry.map_err(|error| error.to_string())?.path();
+    if path.extension().map_or(false, |extension| extension == "aseprite") {
+      paths.push(path);
+    }
+  }
+  // Sort for reproducible output independent of directory order.
+  paths.sort();
+
+  let mut ases = Vec::new();
+  for path in &paths {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    let ase = ase::read(&bytes)
+      .map_err(|error| format!("{} unreadable: {}", path.display(), error.0))?;
+    ases.push(ase);
+  }
+
+  let sheet = packer::pack(&ases, IMAGE_FILENAME, padding, trim)
+    .map_err(|error| format!("Packing failed: {}", error.0))?;
+  let json = serde_json::to_string_pretty(&sheet.file)
+    .map_err(|error| error.to_string())?;
+  fs::write(output.join(IMAGE_FILENAME), sheet.image)
+    .map_err(|error| error.to_string())?;
+  fs::write(output.join(JSON_FILENAME), json + "\n")
+    .map_err(|error| error.to_string())?;
+  Ok(())
+}
@@ -0,0 +1,45 @@
+//! Compiles the JSON assets into the bincode asset bundle loaded by the game.
+//! Every asset is parsed and validated so that errors are caught at build time
+//! instead of load time.
+//!
+//! Usage: pack-bundle [source directory] [output file]
+//!
+//! The source directory defaults to src and the output file to
+//! src/assets.bin.
+
+use nature_elsewhere::bundle::Bundle;
+use nature_elsewhere::manufacturer::blueprint_paths;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+fn main() {
+  if let Err(error) = run(env::args().skip(1).collect()) {
+    eprintln!("{}", error);
+    process::exit(1);
+  }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+  if args.len() > 2 {
+    return Err(
+      "Usage: pack-bundle [source directory] [output file]".to_string(),
+    );
+  }
+  let paths: Vec<_> = args.into_iter().map(PathBuf::from).collect();
+  let input = paths.get(0).map_or(Path::new("src"), PathBuf::as_path);
+  let output =
+    paths.get(1).map_or(Path::new("src/assets.bin"), PathBuf::as_path);
+
+  // Served paths are absolute but relative to the source directory.
+  let bundle = Bundle::compile(
+    |path| {
+      fs::read_to_string(input.join(path.trim_start_matches('/')))
+        .map_err(|error| error.to_string())
+    },
+    &blueprint_paths(input).map_err(|error| error.0)?,
+  )
+  .map_err(|error| format!("Compiling failed: {}", error.0))?;
+  let bytes =
+    bundle.encode().map_err(|error| format!("Encoding failed: {}", error.0))?;
+  fs::write(output, bytes).map_err(|error| error.to_string())
+}
@@ -0,0 +1,30 @@
+//! Writes the Blueprint JSON Schema used by editors to autocomplete and
+//! validate Blueprint files.
+//!
+//! Usage: pack-schema [source directory]
+//!
+//! The source directory defaults to src.
+
+use nature_elsewhere::manufacturer;
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+fn main() {
+  if let Err(error) = run(env::args().skip(1).collect()) {
+    eprintln!("{}", error);
+    process::exit(1);
+  }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+  if args.len() > 1 {
+    return Err("Usage: pack-schema [source directory]".to_string());
+  }
+  let input = args.get(0).map_or(PathBuf::from("src"), PathBuf::from);
+  let output = input.join(manufacturer::SCHEMA_PATH.trim_start_matches('/'));
+
+  let schema = manufacturer::blueprint_schema();
+  let json =
+    serde_json::to_string_pretty(&schema).map_err(|error| error.to_string())?;
+  fs::write(output, json + "\n").map_err(|error| error.to_string())
+}
@@ -0,0 +1,228 @@
+//! The asset bundle is the parsed and validated JSON assets encoded with
+//! bincode. It's compiled at build time by the pack-bundle binary so that the
+//! game loads every asset with a single fetch and decodes it without
+//! validating.
+
+use crate::atlas::{self, Atlas};
+use crate::components;
+use crate::graphics::ShaderLayout;
+use crate::levels::{self, Level, LevelID};
+use crate::manufacturer::{self, Blueprint, BlueprintID, Manufacturer};
+use crate::text::Font;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+/// The bundle format version. Increment whenever the encoding of any bundled
+/// type changes so that stale bundles are rejected instead of misread.
+pub const VERSION: u32 = 9;
+
+/// The served bundle path.
+pub const PATH: &str = "/assets.bin";
+
+const ATLAS_PATH: &str = "/atlas/atlas.json";
+const FONT_PATH: &str = "/text/mem_font.json";
+const SHADER_LAYOUT_PATH: &str = "/graphics/shader_layout.json";
+
+#[derive(Deserialize, Serialize)]
+pub struct Bundle {
+  /// Always VERSION and always encoded first.
+  version: u32,
+  pub atlas: Atlas,
+  pub font: Font,
+  pub shader_layout: ShaderLayout,
+  pub blueprints: HashMap<BlueprintID, Blueprint>,
+  pub levels: HashMap<LevelID, Level>,
+}
+
+#[derive(Debug)]
+pub struct BundleError(pub String);
+
+impl Bundle {
+  /// Parses and validates the JSON assets. `read` is passed each asset's
+  /// served path (e.g., "/atlas/atlas.json") and returns the file contents.
+  /// `blueprint_paths` are the served paths of every Blueprint in the catalog.
+  /// Every LevelID is read from its filename.
+  pub fn compile<R: Fn(&str) -> Result<String, String>>(
+    read: R,
+    blueprint_paths: &[String],
+  ) -> Result<Self, BundleError> {
+    let atlas =
+      atlas::parse(&parse_json(&read, ATLAS_PATH)?).map_err(|error| {
+        BundleError(format!("{} invalid: {}", ATLAS_PATH, error.0))
+      })?;
+    let font = parse_json(&read, FONT_PATH)?;
+    let shader_layout =
+      ShaderLayout::parse(parse_json(&read, SHADER_LAYOUT_PATH)?).ok_or_else(
+        || BundleError(format!("{} invalid.", SHADER_LAYOUT_PATH)),
+      )?;
+    let mut files = Vec::new();
+    for path in blueprint_paths {
+      let json = read(path).map_err(|error| {
+        BundleError(format!("{} unreadable: {}", path, error))
+      })?;
+      files.push((path.clone(), json));
+    }
+    let blueprints = manufacturer::parse_blueprints(&files)
+      .map_err(|error| BundleError(error.0))?;
+    let registry = components::component_registry();
+    Manufacturer::validate(&blueprints, &atlas, &registry)
+      .map_err(|error| BundleError(error.to_string()))?;
+    let mut levels = HashMap::new();
+    for id in LevelID::iter() {
+      let path = id.filename();
+      let json = read(&path).map_err(|error| {
+        BundleError(format!("{} unreadable: {}", path, error))
+      })?;
+      let level = levels::parse_level(&json)
+        .map_err(|error| BundleError(format!("{} invalid: {}", path, error)))?;
+      for (i, patch) in level.entities.iter().enumerate() {
+        Manufacturer::validate_patch(&blueprints, &atlas, &registry, patch)
+          .map_err(|error| {
+            BundleError(format!(
+              "{} invalid: $.entities[{}]: {}",
+              path, i, error
+            ))
+          })?;
+      }
+      levels.insert(id, level);
+    }
+    Ok(Self {
+      version: VERSION,
+      atlas,
+      font,
+      shader_layout,
+      blueprints,
+      levels,
+    })
+  }
+
+  pub fn encode(&self) -> Result<Vec<u8>, BundleError> {
+    bincode::serialize(self).map_err(|error| BundleError(error.to_string()))
+  }
+
+  /// Decodes a bundle encoded by `encode()` of the same VERSION.
+  pub fn decode(bytes: &[u8]) -> Result<Self, BundleError> {
+    let version: u32 = bincode::deserialize(bytes)
+      .map_err(|error| BundleError(error.to_string()))?;
+    if version != VERSION {
+      return Err(BundleError(format!(
+        "Bundle version {} unsupported; expected {}.",
+        version, VERSION
+      )));
+    }
+    bincode::deserialize(bytes).map_err(|error| BundleError(error.to_string()))
+  }
+}
+
+fn parse_json<R: Fn(&str) -> Result<String, String>, T>(
+  read: &R,
+  path: &str,
+) -> Result<T, BundleError>
+where
+  T: for<'a> Deserialize<'a>,
+{
+  let json = read(path)
+    .map_err(|error| BundleError(format!("{} unreadable: {}", path, error)))?;
+  serde_json::from_str(&json)
+    .map_err(|error| BundleError(format!("{} invalid: {}", path, error)))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn read(path: &str) -> Result<String, String> {
+    match path {
+      ATLAS_PATH => Ok(include_str!("atlas/atlas.json").to_string()),
+      FONT_PATH => Ok(include_str!("text/mem_font.json").to_string()),
+      SHADER_LAYOUT_PATH => {
+        Ok(include_str!("graphics/shader_layout.json").to_string())
+      }
+      "/manufacturer/blueprints/bee.json" => {
+        Ok(include_str!("manufacturer/blueprints/bee.json").to_string())
+      }
+      "/manufacturer/blueprints/button.json" => {
+        Ok(include_str!("manufacturer/blueprints/button.json").to_string())
+      }
+      "/manufacturer/blueprints/cursor.json" => {
+        Ok(include_str!("manufacturer/blueprints/cursor.json").to_string())
+      }
+      "/manufacturer/blueprints/rain_cloud.json" => {
+        Ok(include_str!("manufacturer/blueprints/rain_cloud.json").to_string())
+      }
+      "/manufacturer/blueprints/save_dialog.json" => {
+        Ok(include_str!("manufacturer/blueprints/save_dialog.json").to_string())
+      }
+      "/levels/title.json" => Ok(include_str!("levels/title.json").to_string()),
+      _ => Err("File not found.".to_string()),
+    }
+  }
+
+  fn blueprint_paths() -> Vec<String> {
+    [
+      BlueprintID::Bee,
+      BlueprintID::Button,
+      BlueprintID::Cursor,
+      BlueprintID::RainCloud,
+      BlueprintID::SaveDialog,
+    ]
+    .iter()
+    .map(BlueprintID::filename)
+    .collect()
+  }
+
+  #[test]
+  fn roundtrip() {
+    let bundle = Bundle::compile(read, &blueprint_paths()).unwrap();
+    let decoded = Bundle::decode(&bundle.encode().unwrap()).unwrap();
+    assert_eq!(decoded.atlas.animations, bundle.atlas.animations);
+    assert_eq!(decoded.atlas.size, bundle.atlas.size);
+    assert_eq!(
+      decoded.shader_layout.per_instance.stride,
+      bundle.shader_layout.per_instance.stride
+    );
+    assert_eq!(decoded.blueprints.len(), bundle.blueprints.len());
+    for (id, blueprint) in &bundle.blueprints {
+      assert_eq!(
+        serde_json::to_value(&decoded.blueprints[id]).unwrap(),
+        serde_json::to_value(blueprint).unwrap(),
+        "{:?}",
+        id
+      );
+    }
+    let level = &bundle.levels[&LevelID::Title];
+    let decoded_level = &decoded.levels[&LevelID::Title];
+    assert_eq!(decoded_level.properties, level.properties);
+    assert_eq!(
+      serde_json::to_value(&decoded_level.entities).unwrap(),
+      serde_json::to_value(&level.entities).unwrap()
+    );
+  }
+
+  #[test]
+  fn decode_version_mismatch() {
+    let mut bytes =
+      Bundle::compile(read, &blueprint_paths()).unwrap().encode().unwrap();
+    bytes[0] = bytes[0].wrapping_add(1);
+    assert!(Bundle::decode(&bytes).is_err());
+  }
+
+  #[test]
+  fn compile_unreadable() {
+    let result = Bundle::compile(
+      |path| {
+        if path == FONT_PATH {
+          Err("File not found.".to_string())
+        } else {
+          read(path)
+        }
+      },
+      &blueprint_paths(),
+    );
+    assert_eq!(
+      result.err().map(|error| error.0),
+      Some("/text/mem_font.json unreadable: File not found.".to_string())
+    );
+  }
+}
@@ -1,11 +1,14 @@
 use crate::math::R16;
 use crate::math::XY16;
 use serde::{Deserialize, Serialize};
+use specs::error::NoError;
 use specs::prelude::DenseVecStorage;
+use specs::saveload::{ConvertSaveload, Marker};
 use specs::{Component, Entity};
+use strum_macros::EnumIter;
 
 /// Relative position within a rectangle.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, Serialize, PartialEq)]
 pub enum Alignment {
   /// Top-center.
   North,
@@ -36,12 +39,84 @@ pub enum Alignment {
 pub struct AlignTo {
   alignment: Alignment,
   margin: XY16,
-  to: Option<Entity>, // how does hte parser know what to set this to? need an enum? dynamic? idlk
+  /// Manufactured Entitys are linked after their Blueprint tree is built.
+  to: Option<Entity>,
+  /// Aligned to the Viewport's cam, which isn't an Entity, instead of `to`.
+  to_cam: bool,
+}
+
+/// AlignTo with its Entity as a saveload Marker.
+#[derive(Deserialize, Serialize)]
+pub struct AlignToData<M> {
+  alignment: Alignment,
+  margin: XY16,
+  to: Option<M>,
+  #[serde(default)]
+  to_cam: bool,
+}
+
+impl<M: Marker + Serialize> ConvertSaveload<M> for AlignTo
+where
+  for<'de> M: Deserialize<'de>,
+{
+  type Data = AlignToData<M>;
+  type Error = NoError;
+
+  fn convert_into<F: FnMut(Entity) -> Option<M>>(
+    &self,
+    mut ids: F,
+  ) -> Result<Self::Data, Self::Error> {
+    Ok(AlignToData {
+      alignment: self.alignment,
+      margin: self.margin.clone(),
+      to: self.to.and_then(|to| ids(to)),
+      to_cam: self.to_cam,
+    })
+  }
+
+  fn convert_from<F: FnMut(M) -> Option<Entity>>(
+    data: Self::Data,
+    mut ids: F,
+  ) -> Result<Self, Self::Error> {
+    Ok(Self {
+      alignment: data.alignment,
+      margin: data.margin,
+      to: data.to.and_then(|to| ids(to)),
+      to_cam: data.to_cam,
+    })
+  }
 }
 
 impl AlignTo {
   pub fn new(alignment: Alignment, margin: XY16, to: Option<Entity>) -> Self {
-    Self { alignment, margin, to }
+    Self { alignment, margin, to, to_cam: false }
+  }
+
+  pub fn get_alignment(&self) -> Alignment {
+    self.alignment
+  }
+
+  pub fn get_margin(&self) -> &XY16 {
+    &self.margin
+  }
+
+  pub fn get_to(&self) -> Option<Entity> {
+    self.to
+  }
+
+  pub fn set_to(&mut self, to: Option<Entity>) {
+    self.to = to;
+    self.to_cam = false;
+  }
+
+  pub fn is_to_cam(&self) -> bool {
+    self.to_cam
+  }
+
+  /// Aligns to the Viewport's cam instead of an Entity.
+  pub fn set_to_cam(&mut self) {
+    self.to = None;
+    self.to_cam = true;
   }
 
   pub fn plot(&self, bounds: &R16, to: &R16) -> XY16 {
@@ -98,6 +173,7 @@ mod test {
         alignment: alignment.clone(),
         margin: margin.clone(),
         to: None,
+        to_cam: false,
       };
       assert_eq!(
         align_to
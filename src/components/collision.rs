@@ -1,74 +1,313 @@
+use crate::atlas::Body;
 use crate::math::R16;
-use serde::Deserialize;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
 use specs::prelude::DenseVecStorage;
 use specs::Component;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitOrAssign};
 
 /// Different collision classifications. For example, a deep pond could specify
 /// both deep and harm (drowning) collision types. This pond may not need to
 /// check for collision with other entities but the backpacker and bunny should
 /// probably check for collision with it.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
-pub enum CollidesWith {
+///
+/// A set of flags, one per bit. Serialized as a list of flag names or, when
+/// not human-readable, as the bits.
+#[derive(Clone, Component, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CollidesWith(u16);
+
+/// The name of each flag by bit.
+static NAMES: &[&str] = &[
+  "Obstacle",
+  "NearObstacle",
+  "Impediment",
+  "NearImpediment",
+  "DeepWater",
+  "TypeScenery",
+  "TypeCharacter",
+  "TypeUI",
+  "TypeBackpacker",
+  "NearTypeBackpacker",
+  "TypeItem",
+  "NearTypeItem",
+  "Harmful",
+  "NearHarmful",
+  "Fatal",
+  "NearFatal",
+];
+
+impl CollidesWith {
   /// Collision detection with the owner is possible but collisions have no
   /// default effect on the owner or initiator.
-  Inert = 0,
+  pub const INERT: Self = Self(0);
 
   /// Collisions obstruct the initiator's movement. When a moving initiator
   /// collides with an OBSTACLE owner, such as a tree, the moving entity should
   /// not be permitted to overlap boundaries with it.
-  Obstacle = 1 << 0,
+  pub const OBSTACLE: Self = Self(1 << 0);
 
   /// The collision owner is near an obstacle.
-  NearObstacle = 1 << 1,
+  pub const NEAR_OBSTACLE: Self = Self(1 << 1);
 
   /// Collisions impede the initiator's movement but do not obstruct. Overlap
   /// between the owner and initiator's boundaries is permitted.
-  Impediment = 1 << 2,
+  pub const IMPEDIMENT: Self = Self(1 << 2);
 
   /// The collision owner is near an obstacle.
-  NearImpediment = 1 << 3,
+  pub const NEAR_IMPEDIMENT: Self = Self(1 << 3);
 
   /// Collisions indicate a movement impediment for the initiator that is
   /// obscured. E.g., knee-deep water. This may be represented by vertically
   /// truncating the entering initiator at the knees, for example.
-  DeepWater = 1 << 4,
+  pub const DEEP_WATER: Self = Self(1 << 4);
 
   /// The collision owner is a scenery type such as a tree or a cloud.
-  TypeScenery = 1 << 5,
+  pub const TYPE_SCENERY: Self = Self(1 << 5);
 
   /// The collision owner is a character type such as a bee.
-  TypeCharacter = 1 << 6,
+  pub const TYPE_CHARACTER: Self = Self(1 << 6);
 
   /// The collision owner is a user interface type such as a button or a
   /// toolbar.
-  TypeUI = 1 << 7,
+  pub const TYPE_UI: Self = Self(1 << 7);
 
   /// The collision owner is the backpacker.
-  TypeBackpacker = 1 << 8,
+  pub const TYPE_BACKPACKER: Self = Self(1 << 8);
 
   /// The collision owner is near the backpacker.
-  NearTypeBackpacker = 1 << 9,
+  pub const NEAR_TYPE_BACKPACKER: Self = Self(1 << 9);
 
   /// The collision owner is an item such as an arrow.
-  TypeItem = 1 << 10,
+  pub const TYPE_ITEM: Self = Self(1 << 10);
 
   /// The collision owner is near an item owner.
-  NearTypeItem = 1 << 11,
+  pub const NEAR_TYPE_ITEM: Self = Self(1 << 11);
 
   /// The initiator may receive damage upon collision with the owner.
-  Harmful = 1 << 12,
+  pub const HARMFUL: Self = Self(1 << 12);
 
   /// The collision owner is near a harmful owner.
-  NearHarmful = 1 << 13,
+  pub const NEAR_HARMFUL: Self = Self(1 << 13);
 
   /// The initiator may die instantly upon collision with the owner.
-  Fatal = 1 << 14,
+  pub const FATAL: Self = Self(1 << 14);
 
   /// The collision owner is near a fatal owner.
-  NearFatal = 1 << 15,
+  pub const NEAR_FATAL: Self = Self(1 << 15);
+
+  /// The flags with a Near counterpart, each one bit below it.
+  const NEARABLE: Self = Self(
+    Self::OBSTACLE.0
+      | Self::IMPEDIMENT.0
+      | Self::TYPE_BACKPACKER.0
+      | Self::TYPE_ITEM.0
+      | Self::HARMFUL.0
+      | Self::FATAL.0,
+  );
+
+  pub fn from_bits(bits: u16) -> Self {
+    Self(bits)
+  }
+
+  pub fn bits(self) -> u16 {
+    self.0
+  }
+
+  /// Returns the flag names in bit order. E.g., "Obstacle".
+  pub fn names() -> &'static [&'static str] {
+    NAMES
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    NAMES.iter().position(|flag| *flag == name).map(|bit| Self(1 << bit))
+  }
+
+  pub fn is_empty(self) -> bool {
+    self.0 == 0
+  }
+
+  /// Returns true if every flag of `flags` is set.
+  pub fn contains(self, flags: Self) -> bool {
+    self.0 & flags.0 == flags.0
+  }
+
+  /// Returns true if any flag of `flags` is set.
+  pub fn intersects(self, flags: Self) -> bool {
+    self.0 & flags.0 != 0
+  }
+
+  /// Returns the Near flags of an owner near one with these flags. E.g.,
+  /// NEAR_OBSTACLE for OBSTACLE.
+  pub fn near(self) -> Self {
+    Self((self.0 & Self::NEARABLE.0) << 1)
+  }
+
+  /// Returns the names of the flags set in bit order.
+  fn set_names(self) -> Vec<&'static str> {
+    NAMES
+      .iter()
+      .enumerate()
+      .filter(|(bit, _)| self.0 & (1 << bit) != 0)
+      .map(|(_, name)| *name)
+      .collect()
+  }
+}
+
+impl BitOr for CollidesWith {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self {
+    Self(self.0 | rhs.0)
+  }
+}
+
+impl BitOrAssign for CollidesWith {
+  fn bitor_assign(&mut self, rhs: Self) {
+    self.0 |= rhs.0;
+  }
 }
 
-#[derive(Component, Clone)]
+impl BitAnd for CollidesWith {
+  type Output = Self;
+
+  fn bitand(self, rhs: Self) -> Self {
+    Self(self.0 & rhs.0)
+  }
+}
+
+impl Serialize for CollidesWith {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    if !serializer.is_human_readable() {
+      return serializer.serialize_u16(self.0);
+    }
+    let names = self.set_names();
+    let mut seq = serializer.serialize_seq(Some(names.len()))?;
+    for name in names {
+      seq.serialize_element(name)?;
+    }
+    seq.end()
+  }
+}
+
+impl<'de> Deserialize<'de> for CollidesWith {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    if deserializer.is_human_readable() {
+      deserializer.deserialize_seq(CollidesWithVisitor)
+    } else {
+      deserializer.deserialize_u16(CollidesWithVisitor)
+    }
+  }
+}
+
+struct CollidesWithVisitor;
+
+impl<'de> Visitor<'de> for CollidesWithVisitor {
+  type Value = CollidesWith;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a list of CollidesWith flag names")
+  }
+
+  fn visit_u16<E: de::Error>(self, bits: u16) -> Result<Self::Value, E> {
+    Ok(CollidesWith(bits))
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(
+    self,
+    mut seq: A,
+  ) -> Result<Self::Value, A::Error> {
+    let mut flags = CollidesWith::INERT;
+    while let Some(name) = seq.next_element::<String>()? {
+      flags |= CollidesWith::from_name(&name)
+        .ok_or_else(|| de::Error::unknown_variant(&name, NAMES))?;
+    }
+    Ok(flags)
+  }
+}
+
+#[derive(Clone, Component, Deserialize, Serialize)]
 pub struct Collision {
+  /// Bodies in local pixels.
   pub bodies: Vec<R16>,
+  /// The typed bodies of the current Cels in local pixels. Replaced by
+  /// AnimatorSystem whenever the owner's sprites animate so they're not saved.
+  #[serde(skip)]
+  pub cel_bodies: Vec<Body>,
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn flags() {
+    let flags = CollidesWith::OBSTACLE | CollidesWith::TYPE_SCENERY;
+    assert!(flags.contains(CollidesWith::OBSTACLE));
+    assert!(!flags.contains(CollidesWith::OBSTACLE | CollidesWith::HARMFUL));
+    assert!(flags.intersects(CollidesWith::OBSTACLE | CollidesWith::HARMFUL));
+    assert!(!flags.intersects(CollidesWith::HARMFUL));
+    assert_eq!(flags & CollidesWith::TYPE_SCENERY, CollidesWith::TYPE_SCENERY);
+    assert!(CollidesWith::INERT.is_empty());
+    assert_eq!(
+      (CollidesWith::OBSTACLE | CollidesWith::TYPE_UI | CollidesWith::FATAL)
+        .near(),
+      CollidesWith::NEAR_OBSTACLE | CollidesWith::NEAR_FATAL
+    );
+    assert_eq!(CollidesWith::NEAR_HARMFUL.near(), CollidesWith::INERT);
+    assert_eq!(
+      CollidesWith::from_name("NearFatal"),
+      Some(CollidesWith::NEAR_FATAL)
+    );
+    assert_eq!(CollidesWith::from_name("Inert"), None);
+  }
+
+  #[test]
+  fn serde() {
+    [
+      (CollidesWith::INERT, json!([])),
+      (CollidesWith::TYPE_UI, json!(["TypeUI"])),
+      (
+        CollidesWith::FATAL | CollidesWith::OBSTACLE,
+        json!(["Obstacle", "Fatal"]),
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (flags, expected))| {
+      assert_eq!(
+        serde_json::to_value(flags).unwrap(),
+        *expected,
+        "Case {} failed.",
+        i
+      );
+      assert_eq!(
+        serde_json::from_value::<CollidesWith>(expected.clone()).unwrap(),
+        *flags,
+        "Case {} failed.",
+        i
+      );
+      let bytes = bincode::serialize(flags).unwrap();
+      assert_eq!(bytes, flags.bits().to_le_bytes(), "Case {} failed.", i);
+      assert_eq!(
+        bincode::deserialize::<CollidesWith>(&bytes).unwrap(),
+        *flags,
+        "Case {} failed.",
+        i
+      );
+    });
+    assert_eq!(
+      serde_json::from_value::<CollidesWith>(json!(["Nope"]))
+        .err()
+        .unwrap()
+        .to_string()
+        .splitn(2, ',')
+        .next(),
+      Some("unknown variant `Nope`")
+    );
+  }
 }
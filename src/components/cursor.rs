@@ -1,4 +1,5 @@
-use serde::Deserialize;
+use crate::manufacturer::RegisteredBlueprint;
+use serde::{Deserialize, Serialize};
 use specs::prelude::DenseVecStorage;
 use specs::Component;
 
@@ -9,14 +10,14 @@ pub struct Cursor {
   pub icon: CursorIcon,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum CursorMode {
   Hidden,
   Point,
   Pick,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum CursorIcon {
   Dot,
   Reticle,
@@ -28,3 +29,31 @@ impl Cursor {
     Self { mode: CursorMode::Hidden, icon: CursorIcon::Dot }
   }
 }
+
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CursorBlueprint {
+  /// Defaults to Hidden.
+  pub mode: Option<CursorMode>,
+  /// Defaults to Dot.
+  pub icon: Option<CursorIcon>,
+}
+
+impl RegisteredBlueprint for CursorBlueprint {
+  type Component = Cursor;
+
+  const KEY: &'static str = "cursor";
+
+  fn patch(&self, patch: &Self) -> Self {
+    Self { mode: patch.mode.or(self.mode), icon: patch.icon.or(self.icon) }
+  }
+
+  fn manufacture(&self) -> Cursor {
+    let Cursor { mode, icon } = Cursor::new();
+    Cursor { mode: self.mode.unwrap_or(mode), icon: self.icon.unwrap_or(icon) }
+  }
+
+  fn from_component(cursor: &Cursor) -> Self {
+    Self { mode: Some(cursor.mode), icon: Some(cursor.icon) }
+  }
+}
@@ -9,45 +9,81 @@ pub use cursor::*;
 pub use wraparound::*;
 
 use crate::atlas::AnimationID;
+use crate::manufacturer::{BlueprintID, ComponentRegistry};
 use crate::math::{R16, XY, XY16};
-use crate::sprites::{Sprite, SpriteComposition, SpriteLayer};
+use crate::sprites::{SpriteComposition, SpriteLayer, SpriteMap};
+use serde::{Deserialize, Serialize};
+use specs::error::NoError;
 use specs::prelude::DenseVecStorage;
-use specs::{Component, Entity};
+use specs::saveload::{ConvertSaveload, Marker};
+use specs::{Component, Entity, FlaggedStorage};
 use std::any::Any;
-use std::collections::HashMap;
 
 #[derive(Component)]
 pub struct Player;
 
-#[derive(Component)]
-pub struct FollowMouse; // Or LockOn + alignment options
+/// Returns the components manufactured from Blueprints outside of the
+/// Manufacturer core. Add components here.
+pub fn component_registry() -> ComponentRegistry {
+  ComponentRegistry::new().with::<CursorBlueprint>()
+}
 
-#[derive(Component)]
+// Braced since unit structs serialize as null which deserializes as None when
+// wrapped in an Option.
+#[derive(Clone, Component, Deserialize, Serialize)]
+pub struct FollowMouse {} // Or LockOn + alignment options
+
+/// Relative to the parent's Position, if any, or the level. Changes are
+/// tracked so that only moved subtrees are transformed.
+#[derive(Clone, Component, Deserialize, Serialize)]
+#[storage(FlaggedStorage)]
 pub struct Position {
   pub position: XY16,
 }
 
-#[derive(Component)]
+/// The Position in level coordinates computed by TransformSystem.
+#[derive(Clone, Component, Debug, PartialEq)]
+pub struct WorldPosition {
+  pub position: XY16,
+}
+
+#[derive(Clone, Component, Deserialize, Serialize)]
 pub struct Velocity {
   pub velocity: XY16,
 }
 
-#[derive(Component)]
+#[derive(Clone, Component, Deserialize, Serialize)]
 pub struct Text {
   pub text: String,
 }
 
-#[derive(Component)]
+#[derive(Clone, Component, Deserialize, Serialize)]
 pub struct MaxSize {
   pub size: XY<u16>,
 }
 
-#[derive(Component)]
+#[derive(Clone, Component, Deserialize, Serialize)]
 pub struct Cam {
   pub size: XY<u16>,
 }
 
+/// Labels that AlignTo targets reference by name.
+#[derive(Clone, Component, Deserialize, Serialize)]
+pub struct Tags {
+  pub tags: Vec<String>,
+}
+
+/// The definition Blueprint of a root Entity, the one given to the
+/// Manufacturer. Levels are exported as patches of these.
+#[derive(Clone, Component, Deserialize, Serialize)]
+pub struct Manufactured {
+  pub id: BlueprintID,
+}
+
+/// Changes are tracked so that reparented subtrees are transformed. Children
+/// must be kept consistent.
 #[derive(Clone, Component)]
+#[storage(FlaggedStorage)]
 pub struct Parent {
   pub parent: Entity,
 }
@@ -57,6 +93,72 @@ pub struct Children {
   pub children: Vec<Entity>,
 }
 
+/// Entity linkages are saved as Markers. Every Entity linked must be marked
+/// (`SerializeComponents::serialize_recursive()` marks them) or allocated
+/// (`DeserializeComponents` does).
+static UNMARKED: &str = "Linked Entity unmarked.";
+
+#[derive(Deserialize, Serialize)]
+pub struct ParentData<M> {
+  pub parent: M,
+}
+
+impl<M: Marker + Serialize> ConvertSaveload<M> for Parent
+where
+  for<'de> M: Deserialize<'de>,
+{
+  type Data = ParentData<M>;
+  type Error = NoError;
+
+  fn convert_into<F: FnMut(Entity) -> Option<M>>(
+    &self,
+    mut ids: F,
+  ) -> Result<Self::Data, Self::Error> {
+    Ok(ParentData { parent: ids(self.parent).expect(UNMARKED) })
+  }
+
+  fn convert_from<F: FnMut(M) -> Option<Entity>>(
+    data: Self::Data,
+    mut ids: F,
+  ) -> Result<Self, Self::Error> {
+    Ok(Self { parent: ids(data.parent).expect(UNMARKED) })
+  }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ChildrenData<M> {
+  pub children: Vec<M>,
+}
+
+impl<M: Marker + Serialize> ConvertSaveload<M> for Children
+where
+  for<'de> M: Deserialize<'de>,
+{
+  type Data = ChildrenData<M>;
+  type Error = NoError;
+
+  fn convert_into<F: FnMut(Entity) -> Option<M>>(
+    &self,
+    mut ids: F,
+  ) -> Result<Self::Data, Self::Error> {
+    let children =
+      self.children.iter().map(|child| ids(*child).expect(UNMARKED)).collect();
+    Ok(ChildrenData { children })
+  }
+
+  fn convert_from<F: FnMut(M) -> Option<Entity>>(
+    data: Self::Data,
+    mut ids: F,
+  ) -> Result<Self, Self::Error> {
+    let children = data
+      .children
+      .into_iter()
+      .map(|child| ids(child).expect(UNMARKED))
+      .collect();
+    Ok(Self { children })
+  }
+}
+
 #[derive(Component)]
 pub struct StateBestFriend<T: Any + Send + Sync + Default> {
   pub state: T,
@@ -64,18 +166,17 @@ pub struct StateBestFriend<T: Any + Send + Sync + Default> {
 
 // this could have the state in it but then how does behavior line up? it'd be like i'd have to stick behavior and collision in every renderable. i could do that but not everything has behavior.
 // another approach is to make BeeState, AppleTreeState, etc but querying all those different types wouldn't work.
-#[derive(Component)]
-pub struct Renderable<T: Any + Send + Sync + Default> {
-  pub sprites: HashMap<T, Vec<Sprite>>,
+#[derive(Clone, Component, Deserialize, Serialize)]
+pub struct Renderable {
+  pub sprites: SpriteMap,
 }
 
+/// Bounds in local pixels. Overrides the union of the sprites when aligning.
 #[derive(Component)]
 pub struct Bounds {
   pub bounds: R16,
 }
 
-// it sucks that this requires full sprite inflation for every state of everything whether it is in use or not. manufacturing anything is super expensive
-// the state machine should have to consult the blueprint to change states instead.
 pub struct RenderBuddy {
   // sprite
   pub id: AnimationID,
@@ -1,17 +1,22 @@
 use super::assets::Assets;
 use super::graphics::RendererStateMachine;
 use crate::atlas::{AnimationID, Animator, Atlas};
-use crate::components::{FollowMouse, Position, RenderBuddy, Renderable};
+use crate::components::{self, RenderBuddy};
 use crate::graphics::Renderer;
-use crate::graphics::Viewport;
+use crate::graphics::{self, Viewport};
 use crate::inputs::InputPoller;
-use crate::manufacturer::{BlueprintID, Manufacturer};
+use crate::levels::{self, Level, LevelID, LevelProperties};
+use crate::manufacturer::Manufacturer;
 use crate::math::Millis;
 use crate::math::R16;
 use crate::math::{XY, XY16};
 use crate::resources::Timing;
+use crate::save;
 use crate::sprites::{Sprite, SpriteComposition, SpriteLayer};
-use crate::systems::{InputProcessorSystem, RendererSystem};
+use crate::systems::{
+  AlignSystem, AnimatorSystem, CamSystem, Collider, InputProcessorSystem,
+  RendererSystem, TransformSystem,
+};
 use specs::{Builder, World, WorldExt};
 use specs::{Dispatcher, DispatcherBuilder};
 use std::cell::RefCell;
@@ -37,14 +42,20 @@ pub struct Game {
 // https://w3c-test.org/screen-orientation/lock-basic.html
 // https://w3c.github.io/screen-orientation/#dom-screenorientation-lock
 impl Game {
-  fn create_entities(&mut self) {
+  fn create_entities(&mut self, level: &Level) -> Result<(), String> {
     let mut ecs = self.ecs.borrow_mut();
-    ecs.register::<FollowMouse>();
-    ecs.register::<Position>();
-    ecs.register::<Renderable<String>>();
+    save::register(&mut ecs);
+    self.manufacturer.register(&mut ecs);
 
-    self.manufacturer.manufacture(&mut ecs, BlueprintID::Bee);
+    // Set up first so that TransformSystem sees the Positions inserted.
     self.dispatcher.borrow_mut().setup(&mut ecs);
+    levels::load_level(&mut ecs, &self.manufacturer, level)
+      .map_err(|error| error.to_string())?;
+    ecs.insert(Viewport::new(
+      graphics::canvas_size(&self.document),
+      &level.properties.min_viewport,
+      level.properties.cam.clone(),
+    ));
     ecs.insert(self.atlas.clone());
 
     // i can get the entity ID at construction time of composed in entities
@@ -125,6 +136,7 @@ impl Game {
     //     XY16 { x: -64, y: 32 },
     //   ))
     //   .build();
+    Ok(())
   }
 
   pub fn new(
@@ -132,14 +144,35 @@ impl Game {
     document: Document,
     canvas: HtmlCanvasElement,
     assets: Assets,
-  ) -> Self {
-    let Assets { renderer_assets, atlas, font, blueprints } = assets;
+  ) -> Result<Self, String> {
+    let Assets { renderer_assets, atlas, font, blueprints, levels } = assets;
     let atlas = Rc::new(atlas);
 
-    let manufacturer = Rc::new(Manufacturer::new(blueprints, atlas.clone()));
+    let manufacturer = Rc::new(
+      Manufacturer::new(
+        blueprints,
+        atlas.clone(),
+        components::component_registry(),
+      )
+      .map_err(|error| error.to_string())?,
+    );
     let dispatcher = DispatcherBuilder::new()
       .with(InputProcessorSystem, "input_processor_system", &[])
-      .with(RendererSystem, "render_system", &["input_processor_system"])
+      .with(AnimatorSystem, "animator_system", &[])
+      .with(
+        Collider::default(),
+        "collider",
+        &["input_processor_system", "animator_system"],
+      )
+      .with(TransformSystem::default(), "transform_system", &["collider"])
+      .with(CamSystem::default(), "cam_system", &["transform_system"])
+      // Aligns against targets and the cam only after they've moved.
+      .with(AlignSystem, "align_system", &["cam_system"])
+      .with(
+        RendererSystem,
+        "render_system",
+        &["input_processor_system", "animator_system", "align_system"],
+      )
       .build();
 
     let mut game = Game {
@@ -154,7 +187,7 @@ impl Game {
       manufacturer,
     };
 
-    game.create_entities();
+    game.create_entities(&levels[&LevelID::Title])?;
     let renderer_state_machine =
       RendererStateMachine::new(window, document, canvas, renderer_assets, {
         let mut clone = game.clone();
@@ -164,7 +197,7 @@ impl Game {
       });
     *game.renderer_state_machine.borrow_mut() = Some(renderer_state_machine);
 
-    game
+    Ok(game)
   }
 
   pub fn start(&mut self) {
@@ -192,7 +225,10 @@ impl Game {
     ecs.insert(Timing { play_time, delta });
     ecs.insert(self.input_poller.borrow().read());
     ecs.insert(renderer);
-    ecs.insert(Viewport::new(&self.document));
+    let min_size = ecs.read_resource::<LevelProperties>().min_viewport.clone();
+    ecs
+      .write_resource::<Viewport>()
+      .resize(graphics::canvas_size(&self.document), &min_size);
     self.dispatcher.borrow_mut().dispatch(&ecs);
     ecs.maintain();
   }
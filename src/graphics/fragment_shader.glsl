@@ -5,6 +5,9 @@
 #define COMPOSITION_SOURCE_IN 2.
 #define COMPOSITION_CONSTITUENT_MASK 3.
 
+// Loops must have constant bounds. Palette rows are truncated to this length.
+#define MAX_PALETTE_LEN 64
+
 precision mediump int;
 precision highp float;
 precision mediump sampler2D;
@@ -17,12 +20,45 @@ varying vec4 v_constituent;
 varying float v_composition;
 varying vec2 v_offset;
 varying vec2 v_constituent_offset;
+varying vec4 v_source_trim;
+varying vec4 v_constituent_trim;
+varying vec4 v_palette_source;
+varying vec2 v_palette_target;
+
+// Returns 1 if the local position is within the trim region, 0 otherwise.
+float is_kept(vec2 position, vec4 trim) {
+  vec2 kept = step(trim.xy, position) * (1. - step(trim.zw, position));
+  return kept.x * kept.y;
+}
+
+// Returns the atlas pixel at the given position.
+vec4 atlas_px(vec2 position) {
+  return texture2D(atlas, position / vec2(atlas_size));
+}
+
+// Returns the target palette color in the column of the first source palette
+// color matching px, or px if none matches or px is transparent.
+vec4 remap(vec4 px) {
+  if (px.a == 0.) return px;
+  float len = v_palette_source.z - v_palette_source.x;
+  for (int i = 0; i < MAX_PALETTE_LEN; ++i) {
+    vec2 column = vec2(float(i), 0.);
+    if (column.x >= len) break;
+    if (distance(px, atlas_px(v_palette_source.xy + column)) < .001) {
+      return atlas_px(v_palette_target + column);
+    }
+  }
+  return px;
+}
 
 void main() {
-  vec2 source_position = v_source.xy + vec2(ivec2(mod(v_offset, v_source.zw)));
-  vec4 source_px = texture2D(atlas, source_position / vec2(atlas_size));
-  vec2 constituentPosition = v_constituent.xy + vec2(ivec2(mod(v_constituent_offset, v_constituent.zw)));
-  vec4 constituentPx = texture2D(atlas, constituentPosition / vec2(atlas_size));
+  // Source and constituent are given as start (xy) and end (zw) positions.
+  vec2 source_local = vec2(ivec2(mod(v_offset, v_source.zw - v_source.xy)));
+  vec4 source_px = atlas_px(v_source.xy + source_local);
+  source_px *= is_kept(source_local, v_source_trim);
+  vec2 constituent_local = vec2(ivec2(mod(v_constituent_offset, v_constituent.zw - v_constituent.xy)));
+  vec4 constituentPx = atlas_px(v_constituent.xy + constituent_local);
+  constituentPx *= is_kept(constituent_local, v_constituent_trim);
 
   if (v_composition == COMPOSITION_SOURCE) {
     gl_FragColor = vec4(source_px.rgb, source_px.a);
@@ -33,4 +69,5 @@ void main() {
   } else /*if (v_composition == COMPOSITION_CONSTITUENT_MASK)*/ {
     gl_FragColor = vec4(source_px.rgb, constituentPx.a);
   }
+  gl_FragColor = remap(gl_FragColor);
 }
@@ -1,9 +1,9 @@
 use crate::math::TryCeilMultiple;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use web_sys::WebGlRenderingContext as Gl;
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct ShaderLayout {
   /// Uniform name to uniform name. Kind of silly because these are identical.
   pub uniforms: HashMap<String, String>,
@@ -11,7 +11,7 @@ pub struct ShaderLayout {
   pub per_instance: AttributeBuffer,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct AttributeBuffer {
   pub len: i32,
   pub stride: i32,
@@ -19,7 +19,7 @@ pub struct AttributeBuffer {
   pub attributes: Vec<Attribute>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Attribute {
   pub data_type: GlDataType,
   pub name: String,
@@ -43,7 +43,7 @@ pub struct AttributeConfig {
 
 /// An enumeration of WebGL primitives.
 #[repr(u32)]
-#[derive(Clone, Copy, Deserialize)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum GlDataType {
   Byte = Gl::BYTE,
   UnsignedByte = Gl::UNSIGNED_BYTE,
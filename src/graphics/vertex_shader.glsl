@@ -23,12 +23,25 @@ attribute vec2 scale;
 // Translation (x, y) in units of .1 pixels and translation velocity (z, w) and
 // .1 pixels per second (or 1 px / 10 000 ms or 1 px / 10 s).
 attribute vec4 translate;
+// The regions of source and constituent kept when trimmed in local pixels. x,
+// y, end x (z), and end y (w). Pixels outside are transparent.
+attribute vec4 source_trim;
+attribute vec4 constituent_trim;
+// The palette remap. The source palette row within the atlas given as x, y, end
+// x (z), and end y (w) in pixels, and the target palette row start (x, y). An
+// empty source row remaps nothing.
+attribute vec4 palette_source;
+attribute vec2 palette_target;
 
 varying vec4 v_source;
 varying vec4 v_constituent;
 varying float v_composition;
 varying vec2 v_offset;
 varying vec2 v_constituent_offset;
+varying vec4 v_source_trim;
+varying vec4 v_constituent_trim;
+varying vec4 v_palette_source;
+varying vec2 v_palette_target;
 
 void main() {
   // Offset flipped images by their width or height.
@@ -40,4 +53,8 @@ void main() {
   v_offset = v_offset - mod(v_offset, 1. / vec2(abs(scale)));
   v_constituent_offset = vec2(uv * (destination.zw - destination.xy)) / vec2(scale);
   v_constituent_offset = v_constituent_offset - mod(v_constituent_offset, 1. / vec2(abs(scale)));
+  v_source_trim = source_trim;
+  v_constituent_trim = constituent_trim;
+  v_palette_source = palette_source;
+  v_palette_target = palette_target;
 }
@@ -1,8 +1,5 @@
 use crate::math::{R16, XY, XY16, XY32};
-use std::{
-  convert::{TryFrom, TryInto},
-  num::NonZeroU16,
-};
+use std::{convert::TryFrom, num::NonZeroU16};
 use web_sys::Document;
 
 pub struct Viewport {
@@ -12,17 +9,31 @@ pub struct Viewport {
 }
 
 impl Viewport {
-  pub fn new(document: &Document) -> Self {
-    let canvas_size = canvas_size(document);
-    let scale = scale(&canvas_size, &XY::try_from((128, 128)).unwrap(), 0);
-    let cam_size = cam_size(&canvas_size, scale);
-    Self {
-      canvas_size,
-      scale,
-      cam: (0, 0, cam_size.x, cam_size.y)
-        .try_into()
-        .expect("Rect<u16> to R16 conversion failed."),
-    }
+  /// cam The position of the cam in level pixels.
+  pub fn new(
+    canvas_size: XY<u16>,
+    min_size: &XY<NonZeroU16>,
+    cam: XY16,
+  ) -> Self {
+    let mut viewport = Self {
+      canvas_size: XY::new(0, 0),
+      scale: NonZeroU16::new(1).unwrap(),
+      cam: R16 { from: cam.clone(), to: cam },
+    };
+    viewport.resize(canvas_size, min_size);
+    viewport
+  }
+
+  /// Rescales for the canvas and resizes the cam to match. The cam's position
+  /// is kept.
+  pub fn resize(&mut self, canvas_size: XY<u16>, min_size: &XY<NonZeroU16>) {
+    let scale = scale(&canvas_size, min_size, 0);
+    let size = XY16::try_from(cam_size(&canvas_size, scale))
+      .expect("Cam u16 to i16 conversion failed.");
+    self.cam =
+      R16 { from: self.cam.from.clone(), to: self.cam.from.clone() + size };
+    self.canvas_size = canvas_size;
+    self.scale = scale;
   }
 }
 
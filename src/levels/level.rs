@@ -0,0 +1,59 @@
+//! Levels are the properties of a level and the Blueprint patches placed in it.
+//! Placed Blueprints are patches of definition Blueprints, just like children,
+//! so a level only describes how each placement deviates from its definition.
+//!
+//! Level files are versioned. Files of an older version are migrated to the
+//! current VERSION when parsed so old levels continue to load as the format
+//! evolves. See level_parser.rs.
+
+use crate::manufacturer::Blueprint;
+use crate::math::{R16, XY, XY16};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::num::NonZeroU16;
+
+/// The Level format version. Increment whenever the format changes and add a
+/// migration from the prior version.
+pub const VERSION: u32 = 3;
+
+/// The tag that marks the player placement. The first placed Entity with it in
+/// its `tags` is the player.
+pub const PLAYER_TAG: &str = "player";
+
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Level {
+  /// Always VERSION once parsed.
+  pub version: u32,
+  pub properties: LevelProperties,
+  /// Patches of definition Blueprints manufactured in order as root Entitys.
+  #[serde(default)]
+  pub entities: Vec<Blueprint>,
+}
+
+/// The properties of the loaded Level are inserted into the World as a
+/// resource.
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LevelProperties {
+  /// The bounds of the plane in level pixels. Nothing is played outside.
+  pub bounds: R16,
+  /// The minimum level area always visible regardless of canvas size.
+  pub min_viewport: XY<NonZeroU16>,
+  /// The initial position of the cam.
+  pub cam: XY16,
+  /// The position the player spawns at. See PLAYER_TAG.
+  pub player: XY16,
+  /// The Random seed Blueprint variants are picked with. The same seed always
+  /// reproduces the same level.
+  pub seed: i32,
+}
+
+#[derive(Debug)]
+pub struct LevelError(pub String);
+
+impl fmt::Display for LevelError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(formatter, "{}", self.0)
+  }
+}
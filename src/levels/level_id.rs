@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use strum_macros::EnumIter;
+
+/// Identifier for defining and referencing Levels.
+#[derive(
+  Clone,
+  Copy,
+  Debug,
+  Deserialize,
+  PartialEq,
+  Eq,
+  Hash,
+  Serialize,
+  PartialOrd,
+  Ord,
+  EnumIter,
+)]
+pub enum LevelID {
+  Title,
+}
+
+impl fmt::Display for LevelID {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(formatter, "{:?}", self)
+  }
+}
+
+impl LevelID {
+  /// Returns the served path of the Level.
+  pub fn filename(&self) -> String {
+    let name = match self {
+      Self::Title => "title",
+    };
+    format!("/levels/{}.json", name)
+  }
+}
@@ -0,0 +1,583 @@
+use super::{Level, LevelError, LevelProperties, PLAYER_TAG, VERSION};
+use crate::components::{
+  AlignTo, Cam, CollidesWith, FollowMouse, Manufactured, MaxSize, Parent,
+  Position, Tags, Text, Velocity,
+};
+use crate::manufacturer::{
+  AlignTarget, AlignToBlueprint, Blueprint, ComponentBlueprints, ComponentKind,
+  ManufactureBlueprint, Manufacturer, MarkerBlueprint, SizeBlueprint,
+  XYBlueprint,
+};
+use crate::math::{Random, XY};
+use crate::resources::CamFollow;
+use specs::{Entities, Entity, Join, ReadStorage, World, WorldExt};
+use std::collections::BTreeMap;
+
+/// Manufactures every placed Blueprint into the World and inserts the
+/// LevelProperties resource. Variants are picked by a Random resource seeded
+/// by the level so loading is reproducible. The player, the first placed Entity
+/// tagged PLAYER_TAG, is moved to the player position and becomes the
+/// CamFollow target. Returns the root Entitys in placement order.
+/// Bundled levels are validated at build time so this only fails for levels
+/// from elsewhere, like the editor. Entitys manufactured before the failing
+/// placement remain.
+pub fn load_level(
+  ecs: &mut World,
+  manufacturer: &Manufacturer,
+  level: &Level,
+) -> Result<Vec<Entity>, LevelError> {
+  ecs.insert(Random::new(level.properties.seed));
+  let mut entities = Vec::new();
+  for (i, patch) in level.entities.iter().enumerate() {
+    let entity = manufacturer
+      .manufacture_patch(ecs, patch)
+      .map_err(|error| LevelError(format!("$.entities[{}]: {}", i, error)))?;
+    entities.push(entity);
+  }
+  ecs.insert(level.properties.clone());
+  let player = {
+    let tags = ecs.read_storage::<Tags>();
+    entities.iter().copied().find(|entity| is_player(tags.get(*entity)))
+  };
+  if let Some(player) = player {
+    ecs
+      .write_storage::<Position>()
+      .insert(player, Position { position: level.properties.player.clone() })
+      .expect("Position component not inserted.");
+  }
+  ecs.entry::<CamFollow>().or_insert_with(CamFollow::default).target = player;
+  Ok(entities)
+}
+
+fn is_player(tags: Option<&Tags>) -> bool {
+  tags.map_or(false, |tags| tags.tags.iter().any(|tag| tag == PLAYER_TAG))
+}
+
+/// Returns the World as a Level of the current VERSION. Every root
+/// Manufactured Entity is exported as a patch of its definition Blueprint
+/// holding only the components that differ and removal directives for those
+/// missing. Position, velocity, text, max_wh, cam, follow_mouse, collides_with,
+/// tags, align_to, and registered components round-trip. Components that patch
+/// by merging are replaced when changed. An AlignTo target must be the Camera
+/// or an Entity that a tag resolves to or the export fails. Sprites,
+/// collision, and children are exported as defined so changes to them are lost
+/// and variants are picked again on load. Roots are exported in Entity order,
+/// which is placement order unless deleted Entitys were reused. The
+/// LevelProperties resource is exported as is except that the player position
+/// is the player's.
+pub fn export_level(
+  ecs: &World,
+  manufacturer: &Manufacturer,
+) -> Result<Level, LevelError> {
+  let mut properties = ecs
+    .try_fetch::<LevelProperties>()
+    .map(|properties| LevelProperties::clone(&properties))
+    .ok_or_else(|| LevelError("LevelProperties missing.".to_string()))?;
+  let (
+    entities,
+    manufactureds,
+    parents,
+    positions,
+    velocities,
+    texts,
+    max_sizes,
+    cams,
+    follow_mouses,
+    collides_withs,
+    tags,
+    align_tos,
+  ) = ecs.system_data::<(
+    Entities,
+    ReadStorage<Manufactured>,
+    ReadStorage<Parent>,
+    ReadStorage<Position>,
+    ReadStorage<Velocity>,
+    ReadStorage<Text>,
+    ReadStorage<MaxSize>,
+    ReadStorage<Cam>,
+    ReadStorage<FollowMouse>,
+    ReadStorage<CollidesWith>,
+    ReadStorage<Tags>,
+    ReadStorage<AlignTo>,
+  )>();
+  let registry = manufacturer.get_registry();
+  let player = (&entities, &tags, &positions, !&parents)
+    .join()
+    .find(|(_, tags, _, _)| is_player(Some(tags)))
+    .map(|(_, _, position, _)| position.position.clone());
+  if let Some(player) = player {
+    properties.player = player;
+  }
+
+  let mut patches = Vec::new();
+  for (entity, manufactured, _) in (&entities, &manufactureds, !&parents).join()
+  {
+    let to_error =
+      |error| LevelError(format!("$.entities[{}]: {}", patches.len(), error));
+    let definition =
+      manufacturer.get_blueprint(manufactured.id).ok_or_else(|| {
+        LevelError(format!("Blueprint {} undefined.", manufactured.id))
+      })?;
+    let defined = &definition.components;
+    let mut remove = Vec::new();
+    let align_to = match align_tos.get(entity) {
+      Some(align_to) => Some((
+        align_to.get_alignment(),
+        align_to.get_margin().clone(),
+        align_target(align_to, defined.align_to.as_ref(), &entities, &tags)
+          .map_err(to_error)?,
+      )),
+      None => None,
+    };
+    let mut registered = BTreeMap::new();
+    let mut saved = registry.save(ecs, entity).map_err(to_error)?;
+    for (key, layers) in &defined.registered {
+      let defined = registry.saved(key, layers).map_err(to_error)?;
+      let current = saved.remove(key);
+      if let Some(current) = replace(
+        Some(defined),
+        current,
+        ComponentKind::Registered(key.clone()),
+        &mut remove,
+      ) {
+        registered.insert(key.clone(), vec![current]);
+      }
+    }
+    for (key, current) in saved {
+      registered.insert(key, vec![current]);
+    }
+    let components = ComponentBlueprints {
+      position: diff(
+        defined.position.manufacture(),
+        positions.get(entity).map(|position| position.position.clone()),
+        ComponentKind::Position,
+        &mut remove,
+      )
+      .map(XYBlueprint::from),
+      velocity: diff(
+        defined.velocity.manufacture(),
+        velocities.get(entity).map(|velocity| velocity.velocity.clone()),
+        ComponentKind::Velocity,
+        &mut remove,
+      )
+      .map(XYBlueprint::from),
+      text: diff(
+        defined.text.manufacture(),
+        texts.get(entity).map(|text| text.text.clone()),
+        ComponentKind::Text,
+        &mut remove,
+      ),
+      max_wh: diff(
+        defined.max_wh.manufacture(),
+        max_sizes.get(entity).map(|max_size| max_size.size.clone()),
+        ComponentKind::MaxWh,
+        &mut remove,
+      )
+      .map(SizeBlueprint::from),
+      cam: diff(
+        defined.cam.manufacture(),
+        cams.get(entity).map(|cam| cam.size.clone()),
+        ComponentKind::Cam,
+        &mut remove,
+      )
+      .map(SizeBlueprint::from),
+      follow_mouse: diff(
+        defined.follow_mouse.manufacture(),
+        follow_mouses.get(entity).map(|_| ()),
+        ComponentKind::FollowMouse,
+        &mut remove,
+      )
+      .map(|_| MarkerBlueprint {}),
+      collides_with: diff(
+        defined.collides_with,
+        collides_withs.get(entity).copied(),
+        ComponentKind::CollidesWith,
+        &mut remove,
+      ),
+      tags: replace(
+        Some(defined.tags.clone()).filter(|tags| !tags.is_empty()),
+        tags.get(entity).map(|tags| tags.tags.clone()),
+        ComponentKind::Tags,
+        &mut remove,
+      )
+      .unwrap_or_default(),
+      align_to: replace(
+        defined.align_to.as_ref().map(|align_to| {
+          (
+            align_to.alignment,
+            align_to.margin.manufacture().unwrap_or_else(|| XY::new(0, 0)),
+            align_to.to.clone(),
+          )
+        }),
+        align_to,
+        ComponentKind::AlignTo,
+        &mut remove,
+      )
+      .map(|(alignment, margin, to)| AlignToBlueprint {
+        alignment,
+        margin: Some(XYBlueprint::from(margin)),
+        to,
+      }),
+      registered,
+      ..Default::default()
+    };
+    patches.push(Blueprint {
+      id: manufactured.id,
+      components,
+      children: vec![],
+      name: None,
+      remove,
+      remove_children: vec![],
+      replace_sprites: vec![],
+    });
+  }
+
+  Ok(Level { version: VERSION, properties, entities: patches })
+}
+
+/// Returns the component value to patch, if any. A component defined but no
+/// longer present is recorded as a removal directive instead.
+fn diff<T: PartialEq>(
+  defined: Option<T>,
+  current: Option<T>,
+  kind: ComponentKind,
+  remove: &mut Vec<ComponentKind>,
+) -> Option<T> {
+  match (defined, current) {
+    (Some(_), None) => {
+      remove.push(kind);
+      None
+    }
+    (defined, current) if defined != current => current,
+    _ => None,
+  }
+}
+
+/// Like `diff()` but for components that patch by merging. A changed component
+/// is removed too so that the patch replaces it.
+fn replace<T: PartialEq>(
+  defined: Option<T>,
+  current: Option<T>,
+  kind: ComponentKind,
+  remove: &mut Vec<ComponentKind>,
+) -> Option<T> {
+  if defined.is_some() && defined != current {
+    remove.push(kind);
+  }
+  if defined != current {
+    current
+  } else {
+    None
+  }
+}
+
+/// Returns the symbolic target of a root's AlignTo. Roots have no parent or
+/// siblings so an Entity target is named by a tag that resolves to it,
+/// preferring the defined tag.
+fn align_target(
+  align_to: &AlignTo,
+  defined: Option<&AlignToBlueprint>,
+  entities: &Entities,
+  tags: &ReadStorage<Tags>,
+) -> Result<Option<AlignTarget>, String> {
+  if align_to.is_to_cam() {
+    return Ok(Some(AlignTarget::Camera));
+  }
+  let to = match align_to.get_to() {
+    Some(to) => to,
+    None => return Ok(None),
+  };
+  let resolves = |tag: &String| {
+    (entities, tags)
+      .join()
+      .find(|(_, tags)| tags.tags.contains(tag))
+      .map(|(entity, _)| entity)
+      == Some(to)
+  };
+  let defined = match defined.and_then(|defined| defined.to.as_ref()) {
+    Some(AlignTarget::Tag(tag)) => Some(tag),
+    _ => None,
+  };
+  defined
+    .into_iter()
+    .chain(tags.get(to).into_iter().flat_map(|tags| tags.tags.iter()))
+    .find(|tag| resolves(tag))
+    .map(|tag| Some(AlignTarget::Tag(tag.clone())))
+    .ok_or_else(|| "AlignTo target has no tag to export.".to_string())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas;
+  use crate::components::{component_registry, Alignment, Cursor, CursorMode};
+  use crate::graphics::Viewport;
+  use crate::levels::{parse_level, stringify_level};
+  use crate::manufacturer::{parse_blueprints, BlueprintID};
+  use crate::math::{R16, XY};
+  use crate::save;
+  use crate::systems::AlignSystem;
+  use serde_json::Value;
+  use specs::{RunNow, System};
+  use std::num::NonZeroU16;
+  use std::rc::Rc;
+
+  static LEVEL: &str = r#"{
+    "version": 3,
+    "properties": {
+      "bounds": {"from": {"x": 0, "y": 0}, "to": {"x": 512, "y": 256}},
+      "min_viewport": {"x": 128, "y": 128},
+      "cam": {"x": 0, "y": 0},
+      "player": {"x": 20, "y": 50},
+      "seed": 1
+    },
+    "entities": [
+      {
+        "id": "Bee",
+        "components": {"position": {"x": 20, "y": 50}, "tags": ["player"]}
+      },
+      {"id": "Cursor", "components": {"position": {"x": 32, "y": 32}}},
+      {"id": "Bee"}
+    ]
+  }"#;
+
+  fn manufacturer() -> Manufacturer {
+    let atlas = Rc::new(
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap(),
+    );
+    let files: Vec<_> = [
+      (BlueprintID::Bee, include_str!("../manufacturer/blueprints/bee.json")),
+      (
+        BlueprintID::Cursor,
+        include_str!("../manufacturer/blueprints/cursor.json"),
+      ),
+    ]
+    .iter()
+    .map(|(id, json)| (id.filename(), json.to_string()))
+    .collect();
+    let blueprints = parse_blueprints(&files).unwrap();
+    Manufacturer::new(blueprints, atlas, component_registry()).unwrap()
+  }
+
+  #[test]
+  fn load_export_roundtrip() {
+    let manufacturer = manufacturer();
+    let level = parse_level(LEVEL).unwrap();
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+
+    let entities = load_level(&mut ecs, &manufacturer, &level).unwrap();
+
+    assert_eq!(entities.len(), 3);
+    assert_eq!(*ecs.fetch::<LevelProperties>(), level.properties);
+    let positions = ecs.read_storage::<Position>();
+    let positions: Vec<_> = entities
+      .iter()
+      .map(|entity| positions.get(*entity).unwrap().position.clone())
+      .collect();
+    assert_eq!(
+      positions,
+      vec![XY::new(20, 50), XY::new(32, 32), XY::new(0, 0)]
+    );
+    assert!(!ecs.read_storage::<FollowMouse>().contains(entities[1]));
+    assert_eq!(ecs.fetch::<CamFollow>().target, Some(entities[0]));
+
+    let exported = export_level(&ecs, &manufacturer).unwrap();
+    assert_eq!(
+      serde_json::from_str::<Value>(&stringify_level(&exported).unwrap())
+        .unwrap(),
+      serde_json::from_str::<Value>(LEVEL).unwrap()
+    );
+  }
+
+  #[test]
+  fn load_spawns_player() {
+    let manufacturer = manufacturer();
+    let mut level = parse_level(LEVEL).unwrap();
+    level.properties.player = XY::new(5, 6);
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+
+    let entities = load_level(&mut ecs, &manufacturer, &level).unwrap();
+
+    assert_eq!(
+      ecs.read_storage::<Position>().get(entities[0]).unwrap().position,
+      XY::new(5, 6)
+    );
+    assert_eq!(ecs.fetch::<CamFollow>().target, Some(entities[0]));
+    let exported = export_level(&ecs, &manufacturer).unwrap();
+    assert_eq!(exported.properties.player, XY::new(5, 6));
+  }
+
+  #[test]
+  fn load_keeps_cam_follow() {
+    let manufacturer = manufacturer();
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+    ecs.insert(CamFollow { snap: true, ..CamFollow::default() });
+
+    let entities =
+      load_level(&mut ecs, &manufacturer, &parse_level(LEVEL).unwrap())
+        .unwrap();
+
+    let follow = ecs.fetch::<CamFollow>();
+    assert_eq!(follow.target, Some(entities[0]));
+    assert!(follow.snap);
+  }
+
+  #[test]
+  fn load_aligns_to_cam() {
+    let manufacturer = manufacturer();
+    let mut level = parse_level(include_str!("title.json")).unwrap();
+    level.entities.push(
+      from_json!({
+        "id": "Bee",
+        "components": {
+          "align_to": {
+            "alignment": "NorthWest",
+            "margin": {"x": 1, "y": 2},
+            "to": "Camera"
+          }
+        }
+      })
+      .unwrap(),
+    );
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+    System::setup(&mut AlignSystem, &mut ecs);
+    ecs.insert(Viewport {
+      canvas_size: XY::new(128, 128),
+      scale: NonZeroU16::new(1).unwrap(),
+      cam: R16::new_size(30, 40, 128, 128),
+    });
+
+    let entities = load_level(&mut ecs, &manufacturer, &level).unwrap();
+    AlignSystem.run_now(&ecs);
+
+    let hud = *entities.last().unwrap();
+    assert_eq!(
+      ecs.read_storage::<Position>().get(hud).unwrap().position,
+      XY::new(31, 42)
+    );
+  }
+
+  #[test]
+  fn export_changes() {
+    let manufacturer = manufacturer();
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+    let entities =
+      load_level(&mut ecs, &manufacturer, &parse_level(LEVEL).unwrap())
+        .unwrap();
+    ecs.write_storage::<Position>().remove(entities[0]);
+    ecs
+      .write_storage::<Tags>()
+      .insert(
+        entities[0],
+        Tags { tags: vec!["player".to_string(), "bee".to_string()] },
+      )
+      .unwrap();
+    ecs
+      .write_storage::<CollidesWith>()
+      .insert(entities[0], CollidesWith::OBSTACLE)
+      .unwrap();
+    ecs
+      .write_storage::<Position>()
+      .insert(entities[1], Position { position: XY::new(1, 2) })
+      .unwrap();
+    ecs
+      .write_storage::<FollowMouse>()
+      .insert(entities[1], FollowMouse {})
+      .unwrap();
+    ecs.write_storage::<Cursor>().get_mut(entities[1]).unwrap().mode =
+      CursorMode::Point;
+    let mut align_to =
+      AlignTo::new(Alignment::Center, XY::new(0, 0), Some(entities[0]));
+    align_to.set_to_cam();
+    ecs.write_storage::<AlignTo>().insert(entities[1], align_to).unwrap();
+    ecs
+      .write_storage::<Text>()
+      .insert(entities[2], Text { text: "hello".to_string() })
+      .unwrap();
+    ecs
+      .write_storage::<AlignTo>()
+      .insert(
+        entities[2],
+        AlignTo::new(Alignment::North, XY::new(1, 2), Some(entities[0])),
+      )
+      .unwrap();
+
+    let exported = export_level(&ecs, &manufacturer).unwrap();
+
+    let json: Value =
+      serde_json::from_str(&stringify_level(&exported).unwrap()).unwrap();
+    assert_eq!(
+      json["entities"],
+      serde_json::json!([
+        {
+          "id": "Bee",
+          "components": {
+            "collides_with": ["Obstacle"],
+            "tags": ["player", "bee"]
+          },
+          "remove": ["position"]
+        },
+        {
+          "id": "Cursor",
+          "components": {
+            "align_to": {
+              "alignment": "Center",
+              "margin": {"x": 0, "y": 0},
+              "to": "Camera"
+            },
+            "cursor": {"mode": "Point", "icon": "Dot"},
+            "follow_mouse": {},
+            "position": {"x": 1, "y": 2}
+          },
+          "remove": ["cursor"]
+        },
+        {
+          "id": "Bee",
+          "components": {
+            "align_to": {
+              "alignment": "North",
+              "margin": {"x": 1, "y": 2},
+              "to": {"Tag": "player"}
+            },
+            "text": "hello"
+          }
+        }
+      ])
+    );
+  }
+
+  #[test]
+  fn export_untagged_align_to() {
+    let manufacturer = manufacturer();
+    let mut ecs = World::new();
+    save::register(&mut ecs);
+    manufacturer.register(&mut ecs);
+    let entities =
+      load_level(&mut ecs, &manufacturer, &parse_level(LEVEL).unwrap())
+        .unwrap();
+    ecs
+      .write_storage::<AlignTo>()
+      .insert(
+        entities[2],
+        AlignTo::new(Alignment::North, XY::new(0, 0), Some(entities[1])),
+      )
+      .unwrap();
+
+    let result = export_level(&ecs, &manufacturer);
+
+    assert_eq!(
+      result.err().map(|error| error.0),
+      Some("$.entities[2]: AlignTo target has no tag to export.".to_string())
+    );
+  }
+}
@@ -0,0 +1,247 @@
+use super::{Level, LevelError, PLAYER_TAG};
+use serde_json::Value;
+
+/// Upgrades Level JSON of one version to the next in place. The version field
+/// is updated by the caller.
+pub type Migration = fn(&mut Value) -> Result<(), LevelError>;
+
+/// MIGRATIONS[i] upgrades version i + 1 to i + 2 so there's always one fewer
+/// migration than the VERSION.
+static MIGRATIONS: &[Migration] = &[add_seed, tag_player];
+
+/// Parses Level JSON of any supported version, migrating it to VERSION.
+pub fn parse_level(json: &str) -> Result<Level, LevelError> {
+  let mut level = serde_json::from_str(json)
+    .map_err(|error| LevelError(error.to_string()))?;
+  migrate(&mut level, MIGRATIONS)?;
+  serde_json::from_value(level).map_err(|error| LevelError(error.to_string()))
+}
+
+/// Returns the Level as pretty JSON. Blueprints omit absent values and empty
+/// lists so that exported levels are as terse as handwritten ones.
+pub fn stringify_level(level: &Level) -> Result<String, LevelError> {
+  serde_json::to_string_pretty(level)
+    .map_err(|error| LevelError(error.to_string()))
+}
+
+/// Version 2 added the variant seed. Older levels are seeded with zero.
+fn add_seed(level: &mut Value) -> Result<(), LevelError> {
+  let properties =
+    level
+      .get_mut("properties")
+      .and_then(Value::as_object_mut)
+      .ok_or_else(|| LevelError("$.properties: missing.".to_string()))?;
+  properties.insert("seed".to_string(), Value::from(0));
+  Ok(())
+}
+
+/// Version 3 marks the player with a tag instead of its position. The first
+/// placement positioned at the player property is tagged. Unset coordinates
+/// are zero.
+fn tag_player(level: &mut Value) -> Result<(), LevelError> {
+  let coordinate =
+    |xy: &Value, key| xy.get(key).and_then(Value::as_i64).unwrap_or(0);
+  let player = level
+    .pointer("/properties/player")
+    .map(|player| (coordinate(player, "x"), coordinate(player, "y")))
+    .ok_or_else(|| LevelError("$.properties.player: missing.".to_string()))?;
+  let placement = level
+    .get_mut("entities")
+    .and_then(Value::as_array_mut)
+    .and_then(|entities| {
+      entities.iter_mut().find(|entity| {
+        entity.pointer("/components/position").map_or(false, |position| {
+          (coordinate(position, "x"), coordinate(position, "y")) == player
+        })
+      })
+    });
+  if let Some(tags) = placement
+    .and_then(|placement| placement["components"].as_object_mut())
+    .and_then(|components| {
+      components
+        .entry("tags")
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+    })
+  {
+    tags.push(Value::from(PLAYER_TAG));
+  }
+  Ok(())
+}
+
+/// Applies every migration needed to bring `level` from its version to the
+/// version after the last migration.
+fn migrate(
+  level: &mut Value,
+  migrations: &[Migration],
+) -> Result<(), LevelError> {
+  let latest = migrations.len() as u64 + 1;
+  let version = level
+    .get("version")
+    .and_then(Value::as_u64)
+    .ok_or_else(|| LevelError("$.version: missing.".to_string()))?;
+  if version == 0 || version > latest {
+    return Err(LevelError(format!(
+      "$.version: {} unsupported; expected 1 to {}.",
+      version, latest
+    )));
+  }
+  for (i, migration) in migrations.iter().enumerate().skip(version as usize - 1)
+  {
+    migration(level)?;
+    level["version"] = Value::from(i as u64 + 2);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::levels::VERSION;
+  use serde_json::json;
+
+  #[test]
+  fn migrations_match_version() {
+    assert_eq!(MIGRATIONS.len() as u32 + 1, VERSION);
+  }
+
+  #[test]
+  fn migrate_versions() {
+    fn rename_props(level: &mut Value) -> Result<(), LevelError> {
+      let level = level.as_object_mut().unwrap();
+      let properties = level.remove("props").unwrap_or_default();
+      level.insert("properties".to_string(), properties);
+      Ok(())
+    }
+    fn fail(_: &mut Value) -> Result<(), LevelError> {
+      Err(LevelError("Failed.".to_string()))
+    }
+    let rename: &[Migration] = &[rename_props];
+    let failing: &[Migration] = &[fail];
+    [
+      (json!({"props": 1}), rename, Err("$.version: missing.")),
+      (
+        json!({"version": 0}),
+        rename,
+        Err("$.version: 0 unsupported; expected 1 to 2."),
+      ),
+      (
+        json!({"version": 3}),
+        rename,
+        Err("$.version: 3 unsupported; expected 1 to 2."),
+      ),
+      (
+        json!({"version": 1, "props": 1}),
+        rename,
+        Ok(json!({"version": 2, "properties": 1})),
+      ),
+      (
+        json!({"version": 2, "properties": 1}),
+        rename,
+        Ok(json!({"version": 2, "properties": 1})),
+      ),
+      (json!({"version": 1}), failing, Err("Failed.")),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (level, migrations, expected))| {
+      let mut level = level.clone();
+      let result = migrate(&mut level, migrations).map(|_| level);
+      assert_eq!(
+        result.map_err(|error| error.0),
+        expected.clone().map_err(str::to_string),
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn migrate_add_seed() {
+    let mut level = json!({"version": 1, "properties": {"cam": {"x": 1}}});
+
+    migrate(&mut level, &MIGRATIONS[..1]).unwrap();
+
+    assert_eq!(
+      level,
+      json!({"version": 2, "properties": {"cam": {"x": 1}, "seed": 0}})
+    );
+  }
+
+  #[test]
+  fn migrate_tag_player() {
+    [
+      (
+        json!([
+          {"id": "Bee"},
+          {"id": "Bee", "components": {"position": {"x": 20}}},
+          {"id": "Bee", "components": {"position": {"x": 20, "y": 50}}},
+          {"id": "Bee", "components": {"position": {"x": 20, "y": 50}}}
+        ]),
+        json!([
+          {"id": "Bee"},
+          {"id": "Bee", "components": {"position": {"x": 20}}},
+          {
+            "id": "Bee",
+            "components": {"position": {"x": 20, "y": 50}, "tags": ["player"]}
+          },
+          {"id": "Bee", "components": {"position": {"x": 20, "y": 50}}}
+        ]),
+      ),
+      (
+        json!([{
+          "id": "Bee",
+          "components": {"position": {"x": 20, "y": 50}, "tags": ["bee"]}
+        }]),
+        json!([{
+          "id": "Bee",
+          "components": {
+            "position": {"x": 20, "y": 50},
+            "tags": ["bee", "player"]
+          }
+        }]),
+      ),
+      (json!([{"id": "Bee"}]), json!([{"id": "Bee"}])),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (entities, expected))| {
+      let mut level = json!({
+        "version": 2,
+        "properties": {"player": {"x": 20, "y": 50}},
+        "entities": entities
+      });
+      migrate(&mut level, MIGRATIONS).unwrap();
+      assert_eq!(level["version"], json!(3), "Case {} failed.", i);
+      assert_eq!(level["entities"], *expected, "Case {} failed.", i);
+    });
+  }
+
+  #[test]
+  fn stringify_roundtrip() {
+    let json = r#"{
+      "version": 3,
+      "properties": {
+        "bounds": {"from": {"x": 0, "y": 0}, "to": {"x": 512, "y": 256}},
+        "min_viewport": {"x": 128, "y": 128},
+        "cam": {"x": 0, "y": 0},
+        "player": {"x": 20, "y": 50},
+        "seed": 1
+      },
+      "entities": [
+        {"id": "Bee", "components": {"position": {"x": 1}}},
+        {"id": "Cursor", "components": {"follow_mouse": {}}},
+        {"id": "Bee", "components": {"sprites": {"Default": [{"id": "Bee"}]}}}
+      ]
+    }"#;
+    let level = parse_level(json).unwrap();
+
+    let stringified = stringify_level(&level).unwrap();
+
+    assert_eq!(
+      serde_json::from_str::<Value>(&stringified).unwrap(),
+      serde_json::from_str::<Value>(json).unwrap()
+    );
+    assert_eq!(parse_level(&stringified).unwrap().properties, level.properties);
+  }
+}
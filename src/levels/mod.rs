@@ -0,0 +1,9 @@
+mod level;
+mod level_id;
+mod level_loader;
+mod level_parser;
+
+pub use level::*;
+pub use level_id::*;
+pub use level_loader::*;
+pub use level_parser::*;
@@ -1,14 +1,18 @@
 #[macro_use]
 mod utils;
 mod assets;
-mod atlas;
+pub mod atlas;
+pub mod bundle;
 mod components;
 mod game;
 mod graphics;
 mod inputs;
-mod manufacturer;
+pub mod levels;
+pub mod lint;
+pub mod manufacturer;
 mod math;
 mod resources;
+pub mod save;
 mod settings;
 mod sprites;
 mod systems;
@@ -28,7 +32,7 @@ pub async fn main_wasm() -> Result<(), JsValue> {
   let document = window.document().ok_or("Document missing.")?;
   let canvas = wasm::get_element_by_id(&document, "game_canvas")?;
   let assets = Assets::load(&window, &document).await?;
-  let mut game = Game::new(window, document, canvas, assets);
+  let mut game = Game::new(window, document, canvas, assets)?;
   game.start();
 
   Ok(())
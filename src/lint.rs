@@ -0,0 +1,482 @@
+//! Checks of atlas exports, including and beyond the hard errors of
+//! `atlas::parse()`. The lint-atlas binary reports these so that artists can
+//! check exports locally.
+
+use crate::atlas::{
+  self, aseprite, Animation, AnimationID, AnimationMap, Cel, Playback,
+};
+use crate::manufacturer::{self, Blueprint, SpriteBlueprint};
+use crate::math::{Millis, R16, XY};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter;
+
+const ATLAS_PATH: &str = "/atlas/atlas.json";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+  /// Likely unintended but harmless.
+  Warning,
+  /// Renders or animates incorrectly.
+  Error,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+  pub severity: Severity,
+  pub message: String,
+}
+
+/// The playback of an animation for the timing summary.
+pub struct Timing {
+  pub id: AnimationID,
+  pub cels: usize,
+  pub direction: Playback,
+  pub repeat: Option<u16>,
+  /// See Animation.duration.
+  pub duration: Millis,
+}
+
+pub struct Report {
+  /// Animation problems followed by Blueprint problems.
+  pub problems: Vec<Problem>,
+  /// Ordered by animation tag.
+  pub timings: Vec<Timing>,
+}
+
+#[derive(Debug)]
+pub struct LintError(pub String);
+
+impl Report {
+  /// Lints the atlas and every Blueprint. `read` is passed each asset's served
+  /// path (e.g., "/atlas/atlas.json") and returns the file contents.
+  /// `blueprint_paths` are the served paths of every Blueprint in the catalog.
+  /// Atlas errors that prevent parsing its animations fail the report. Every
+  /// other error `atlas::parse()` would reject, and Blueprint errors, are
+  /// reported as Problems.
+  pub fn new<R: Fn(&str) -> Result<String, String>>(
+    read: R,
+    blueprint_paths: &[String],
+  ) -> Result<Self, LintError> {
+    let json = read(ATLAS_PATH).map_err(|error| {
+      LintError(format!("{} unreadable: {}", ATLAS_PATH, error))
+    })?;
+    let file: aseprite::File =
+      serde_json::from_str(&json).map_err(|error| {
+        LintError(format!("{} invalid: {}", ATLAS_PATH, error))
+      })?;
+    // The map is parsed instead of the atlas so that missing IDs are reported
+    // with the Blueprints that reference them.
+    let animations = atlas::parse_animation_map(&file).map_err(|error| {
+      LintError(format!("{} invalid: {}", ATLAS_PATH, error.0))
+    })?;
+
+    let mut problems = Vec::new();
+    if let Err(parse_error) = atlas::parse(&file) {
+      problems
+        .push(error(format!("{} invalid: {}", ATLAS_PATH, parse_error.0)));
+    }
+    problems.append(&mut lint_animations(
+      &XY::new(file.meta.size.w, file.meta.size.h),
+      &animations,
+    ));
+    let mut blueprints = Vec::new();
+    for path in blueprint_paths {
+      match read(path)
+        .map_err(|error| format!("{} unreadable: {}", path, error))
+        .and_then(|json| {
+          manufacturer::parse_blueprint(path, &json).map_err(|error| error.0)
+        }) {
+        Ok((_, blueprint)) => blueprints.push((path.clone(), blueprint)),
+        Err(message) => problems.push(error(message)),
+      }
+    }
+    problems.append(&mut lint_references(&animations, &blueprints));
+
+    let timings = sort_by_tag(&animations)
+      .into_iter()
+      .map(|(id, animation)| Timing {
+        id,
+        cels: animation.cels.len(),
+        direction: animation.direction,
+        repeat: animation.repeat,
+        duration: animation.duration,
+      })
+      .collect();
+    Ok(Self { problems, timings })
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self
+      .problems
+      .iter()
+      .any(|Problem { severity, .. }| *severity == Severity::Error)
+  }
+}
+
+/// Returns the problems within the animations themselves.
+pub fn lint_animations(
+  atlas_size: &XY<u16>,
+  animations: &AnimationMap,
+) -> Vec<Problem> {
+  let mut problems = Vec::new();
+  let atlas_bounds = R16::new(
+    0,
+    0,
+    i16::try_from(atlas_size.x).unwrap_or(i16::max_value()),
+    i16::try_from(atlas_size.y).unwrap_or(i16::max_value()),
+  );
+  for (id, animation) in sort_by_tag(animations) {
+    let tag = id.tag();
+    // Any infinite cel stalls a loop.
+    let loops = animation.repeat.is_none() && animation.cels.len() > 1;
+
+    let size = R16::new(
+      0,
+      0,
+      i16::try_from(animation.size.x).unwrap_or(i16::max_value()),
+      i16::try_from(animation.size.y).unwrap_or(i16::max_value()),
+    );
+    for (i, Cel { bounds, trim, duration, slices, bodies }) in
+      animation.cels.iter().enumerate()
+    {
+      if loops && duration.is_infinite() {
+        problems.push(error(format!(
+          "\"{}\" loops but its cel {} has an infinite duration.",
+          tag, i
+        )));
+      }
+      let region = trim.clone() + bounds.from.clone();
+      if !within(&region, &atlas_bounds) {
+        problems.push(error(format!(
+          "\"{}\" cel {} region {} exceeds the atlas size {}.",
+          tag, i, region, atlas_size
+        )));
+      }
+      let outside = slices
+        .iter()
+        .chain(bodies.iter().map(|body| &body.bounds))
+        .filter(|slice| !within(slice, &size));
+      for slice in outside {
+        problems.push(error(format!(
+          "\"{}\" cel {} slice {} falls outside the cel {}.",
+          tag, i, slice, size
+        )));
+      }
+    }
+  }
+  problems
+}
+
+/// Returns the problems with the animations the Blueprints reference, and the
+/// animations no Blueprint references. Blueprints are paired with their paths.
+pub fn lint_references(
+  animations: &AnimationMap,
+  blueprints: &[(String, Blueprint)],
+) -> Vec<Problem> {
+  let mut problems = Vec::new();
+  let mut referenced = HashSet::new();
+  for (path, blueprint) in blueprints {
+    for sprite in sprite_blueprints(blueprint) {
+      let mut ids = vec![sprite.id];
+      ids.extend(sprite.constituent_id);
+      if let Some(palette) = &sprite.palette {
+        ids.push(palette.source);
+        ids.push(palette.target);
+      }
+      for id in ids {
+        reference(animations, path, id, &mut referenced, &mut problems);
+      }
+
+      if let (Some(source), Some(constituent)) = (
+        animations.get(&sprite.id),
+        sprite.constituent_id.and_then(|id| animations.get(&id)),
+      ) {
+        if source.size != constituent.size {
+          problems.push(Problem {
+            severity: Severity::Warning,
+            message: format!(
+              "{} composes \"{}\" {} with \"{}\" {} of a different size.",
+              path,
+              sprite.id.tag(),
+              source.size,
+              sprite.constituent_id.map_or("", |id| id.tag()),
+              constituent.size
+            ),
+          });
+        }
+      }
+    }
+    for id in collision_slices(blueprint) {
+      reference(animations, path, id, &mut referenced, &mut problems);
+    }
+  }
+
+  for (id, _) in sort_by_tag(animations) {
+    if !referenced.contains(&id) {
+      problems.push(Problem {
+        severity: Severity::Warning,
+        message: format!("\"{}\" is unreferenced by any Blueprint.", id.tag()),
+      });
+    }
+  }
+  problems
+}
+
+/// Records the reference and the problem if it's missing from the atlas.
+fn reference(
+  animations: &AnimationMap,
+  path: &str,
+  id: AnimationID,
+  referenced: &mut HashSet<AnimationID>,
+  problems: &mut Vec<Problem>,
+) {
+  referenced.insert(id);
+  if !animations.contains_key(&id) {
+    problems.push(error(format!(
+      "{} references \"{}\" which is missing from the atlas.",
+      path,
+      id.tag()
+    )));
+  }
+}
+
+/// Returns the collision slice Animations of the Blueprint and its
+/// descendants.
+fn collision_slices(blueprint: &Blueprint) -> Vec<AnimationID> {
+  let mut ids: Vec<_> = blueprint
+    .components
+    .collision
+    .iter()
+    .flat_map(|collision| collision.slices.iter().cloned())
+    .collect();
+  for child in &blueprint.children {
+    ids.append(&mut collision_slices(child));
+  }
+  ids
+}
+
+/// Returns all SpriteBlueprints, including variants, of the Blueprint and its
+/// descendants.
+fn sprite_blueprints(blueprint: &Blueprint) -> Vec<&SpriteBlueprint> {
+  let components = &blueprint.components;
+  let variants = components.variants.iter().map(|variant| &variant.sprites);
+  let mut sprites: Vec<_> = iter::once(&components.sprites)
+    .chain(variants)
+    .flat_map(HashMap::values)
+    .flatten()
+    .collect();
+  for child in &blueprint.children {
+    sprites.append(&mut sprite_blueprints(child));
+  }
+  sprites
+}
+
+fn sort_by_tag(animations: &AnimationMap) -> Vec<(AnimationID, &Animation)> {
+  let mut animations: Vec<_> =
+    animations.iter().map(|(id, animation)| (*id, animation)).collect();
+  animations.sort_by_key(|(id, _)| id.tag());
+  animations
+}
+
+fn within(rect: &R16, bounds: &R16) -> bool {
+  rect.union(bounds) == *bounds
+}
+
+fn error(message: String) -> Problem {
+  Problem { severity: Severity::Error, message }
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(formatter, "{}: {}", severity, self.message)
+  }
+}
+
+impl fmt::Display for Timing {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let repeat = self
+      .repeat
+      .map_or("looping".to_string(), |repeat| format!("x{}", repeat));
+    let duration = if self.duration.is_infinite() {
+      "infinite".to_string()
+    } else {
+      format!("{} ms", self.duration)
+    };
+    write!(
+      formatter,
+      "{}: {} cels, {:?}, {}, {}",
+      self.id.tag(),
+      self.cels,
+      self.direction,
+      repeat,
+      duration
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas::{Body, BodyKind};
+
+  fn animation(cels: Vec<Cel>, repeat: Option<u16>) -> Animation {
+    Animation {
+      size: XY::new(4, 4),
+      duration: cels.iter().map(|cel| cel.duration).sum(),
+      cels,
+      direction: Playback::Forward,
+      repeat,
+    }
+  }
+
+  fn cel(x: i16, duration: Millis, slices: Vec<R16>) -> Cel {
+    Cel {
+      bounds: R16::new_size(x, 0, 4, 4),
+      trim: R16::new_size(0, 0, 4, 4),
+      duration,
+      slices,
+      bodies: vec![],
+    }
+  }
+
+  #[test]
+  fn new_reports_atlas_parse_errors() {
+    let mut json: serde_json::Value =
+      serde_json::from_str(include_str!("atlas/atlas.json")).unwrap();
+    json["meta"]["frameTags"].as_array_mut().unwrap().remove(0);
+    let report = Report::new(|_| Ok(json.to_string()), &[]).unwrap();
+
+    assert_eq!(
+      report.problems[0],
+      error(format!(
+        "{} invalid: Missing ID AppleTree in atlas animations.",
+        ATLAS_PATH
+      ))
+    );
+  }
+
+  #[test]
+  fn lint_animations_problems() {
+    let infinite = Millis::INFINITY;
+    let feet = Cel {
+      bodies: vec![Body {
+        kind: BodyKind::Feet,
+        bounds: R16::new_size(-1, 0, 1, 1),
+      }],
+      ..cel(0, 1., vec![])
+    };
+    [
+      (vec![cel(0, 1., vec![])], None, vec![]),
+      (
+        vec![cel(0, 1., vec![]), cel(4, infinite, vec![])],
+        None,
+        vec!["\"bee\" loops but its cel 1 has an infinite duration."],
+      ),
+      (
+        vec![cel(0, infinite, vec![]), cel(4, 1., vec![])],
+        None,
+        vec!["\"bee\" loops but its cel 0 has an infinite duration."],
+      ),
+      (vec![cel(0, 1., vec![]), cel(4, infinite, vec![])], Some(1), vec![]),
+      (
+        vec![cel(6, 1., vec![])],
+        None,
+        vec![
+          "\"bee\" cel 0 region [(6, 0), (10, 4)] exceeds the atlas size (8, \
+           8).",
+        ],
+      ),
+      (
+        vec![cel(0, 1., vec![R16::new_size(3, 3, 2, 1)])],
+        None,
+        vec![
+          "\"bee\" cel 0 slice [(3, 3), (5, 4)] falls outside the cel [(0, \
+           0), (4, 4)].",
+        ],
+      ),
+      (
+        vec![feet],
+        None,
+        vec![
+          "\"bee\" cel 0 slice [(-1, 0), (0, 1)] falls outside the cel [(0, \
+           0), (4, 4)].",
+        ],
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (cels, repeat, expected))| {
+      let mut animations = HashMap::new();
+      animations.insert(AnimationID::Bee, animation(cels.clone(), *repeat));
+      let problems = lint_animations(&XY::new(8, 8), &animations);
+      let expected: Vec<_> =
+        expected.iter().map(|message| error(message.to_string())).collect();
+      assert_eq!(problems, expected, "Case {} failed.", i);
+    });
+  }
+
+  #[test]
+  fn lint_references_problems() {
+    let mut animations = HashMap::new();
+    animations
+      .insert(AnimationID::Bee, animation(vec![cel(0, 1., vec![])], None));
+    animations.insert(
+      AnimationID::PaletteRed,
+      Animation {
+        size: XY::new(1, 1),
+        ..animation(vec![cel(0, 1., vec![])], None)
+      },
+    );
+    animations.insert(
+      AnimationID::PaletteBlack,
+      animation(vec![cel(0, 1., vec![])], None),
+    );
+    let blueprint: Blueprint = from_json!({
+      "id": "Bee",
+      "components": {
+        "sprites": {"Default": [{"id": "Bee", "constituent_id": "PaletteRed"}]},
+        "variants": [{"sprites": {"Default": [{"id": "BeeDead"}]}}],
+        "collision": {"slices": ["BeeShadow"]}
+      },
+      "children": [{
+        "id": "Bee",
+        "components": {"sprites": {"Default": [{"id": "AppleTree"}]}}
+      }]
+    })
+    .unwrap();
+    assert_eq!(
+      lint_references(&animations, &[("bee.json".to_string(), blueprint)]),
+      vec![
+        Problem {
+          severity: Severity::Warning,
+          message: "bee.json composes \"bee\" (4, 4) with \"palette-red\" (1, \
+                    1) of a different size."
+            .to_string()
+        },
+        error(
+          "bee.json references \"bee-dead\" which is missing from the atlas."
+            .to_string()
+        ),
+        error(
+          "bee.json references \"appleTree\" which is missing from the atlas."
+            .to_string()
+        ),
+        error(
+          "bee.json references \"bee-shadow\" which is missing from the atlas."
+            .to_string()
+        ),
+        Problem {
+          severity: Severity::Warning,
+          message: "\"palette-black\" is unreferenced by any Blueprint."
+            .to_string()
+        },
+      ]
+    );
+  }
+}
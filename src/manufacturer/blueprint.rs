@@ -30,45 +30,112 @@
 //!
 //! `Option`s are used so that patching knows when a value is set or should fall
 //! should fallthrough to the base. Defaults are only used for collection types
-//! that merge by aggregation (the consequence is that it's impossible to remove
-//! the base value with an empty override but is similarly impossibly with a
-//! nonempty override to remove base values). Serialization should test against
-//! default values in the Manufacturer.
+//! that merge by aggregation. Neither an absent value nor an empty collection
+//! can remove a base value so patches carry explicit directives instead:
+//! `remove` drops base components (which, followed by a value, replaces instead
+//! of merges), `replace_sprites` does the same for individual sprite states,
+//! and `remove_children` drops named base children. Serialization should test
+//! against default values in the Manufacturer.
+//!
+//! Absent values are skipped when serializing to JSON so that it stays terse.
+//! bincode isn't self-describing and decodes fields by position so every field
+//! is serialized when not human-readable. See `serialize_blueprint!`.
+//!
+//! Components outside the Manufacturer core are added to a ComponentRegistry
+//! instead of ComponentBlueprints. Any unknown key in ComponentBlueprints is
+//! assumed registered and kept as JSON until the Manufacturer validates and
+//! manufactures it through the registry. See component_registry.rs.
+//!
+//! The JSON Schema in blueprint_schema.rs mirrors these types and must be
+//! updated with them.
 
 use super::BlueprintID;
 use crate::atlas::{AnimationID, AnimatorPeriod};
-use crate::components::{Alignment, Children, Parent};
-use crate::math::Millis;
-use crate::sprites::{SpriteComposition, SpriteLayer};
-use serde::{Deserialize, Serialize};
-use specs::Entity;
-use std::collections::HashMap;
-use std::num::NonZeroI16;
+use crate::components::{Alignment, Children, CollidesWith, Parent};
+use crate::math::{Millis, XY};
+use crate::sprites::{SpriteComposition, SpriteLayer, SpritePalette};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::num::{NonZeroI16, NonZeroU16};
+
+/// Implements Serialize for a struct given its required fields then its Absent
+/// fields, each in declaration order. Absent fields are skipped when
+/// human-readable. The derived Deserialize reads the fields in the same order
+/// when not.
+macro_rules! serialize_blueprint {
+  ($name:ident $(<$param:ident>)? {
+    $($field:ident),*; $($absent:ident),* $(,)?
+  }) => {
+    impl$(<$param: Serialize>)? Serialize for $name$(<$param>)? {
+      fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+      ) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let len = [$(stringify!($field),)* $(stringify!($absent),)*].len();
+        let mut state = serializer.serialize_struct(stringify!($name), len)?;
+        $(state.serialize_field(stringify!($field), &self.$field)?;)*
+        $(
+          if human_readable && self.$absent.is_absent() {
+            state.skip_field(stringify!($absent))?;
+          } else {
+            state.serialize_field(stringify!($absent), &self.$absent)?;
+          }
+        )*
+        state.end()
+      }
+    }
+  };
+}
 
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
 pub struct AlignToBlueprint {
   pub alignment: Alignment,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub margin: Option<XY16Blueprint>,
-  // todo: how does the Manufacturer link this? It describes some Relationship,
-  // so maybe a Relationship enum Component? Or maybe it's dynamic and set by
-  // some kind of Behavior, like a "FindNearestEntityInitialization" behavior?
-  #[serde(skip)]
-  pub to: Option<Entity>,
+  /// Defaults to no target.
+  pub to: Option<AlignTarget>,
+}
+serialize_blueprint!(AlignToBlueprint { alignment; margin, to });
+
+/// A symbolic AlignTo target. The Manufacturer resolves it to an Entity once
+/// the whole Blueprint tree is built.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum AlignTarget {
+  /// The Entity's parent.
+  Parent,
+  /// The Viewport's cam. Resolved by AlignSystem since it's not an Entity.
+  Camera,
+  /// The parent's child with the name.
+  Sibling(String),
+  /// The first Entity with the tag.
+  Tag(String),
 }
 
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
 pub struct AnimatorBlueprint {
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub period: Option<AnimatorPeriod>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub exposure: Option<Millis>,
 }
+serialize_blueprint!(AnimatorBlueprint { ; period, exposure });
 
+/// Collision bodies in local pixels, given directly or derived from slices.
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
+pub struct CollisionBlueprint {
+  #[serde(default)]
+  pub bodies: Vec<R16Blueprint>,
+  /// Animations whose first Cel slices are also bodies.
+  #[serde(default)]
+  pub slices: Vec<AnimationID>,
+}
+serialize_blueprint!(CollisionBlueprint { ; bodies, slices });
+
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Deserialize)]
 pub struct Blueprint {
   /// Either the identity of the definition Blueprint (root, non-child) or the
   /// identity of the definition Blueprint to look up as a baseline to patch.
@@ -87,55 +154,169 @@ pub struct Blueprint {
   /// linkage is a _m_-ary tree.
   ///
   /// [tagging]: https://serde.rs/enum-representations.html#externally-tagged
-  #[serde(default, skip_serializing_if = "ComponentBlueprints::is_empty")]
+  #[serde(default)]
   pub components: ComponentBlueprints,
   /// These are kind of "Blueprint specifications" or "Blueprint properties."
   /// They're true Blueprints just like the root but they're manufactured by
   /// using their ID to obtain the root Blueprint as a baseline and then the
   /// specs or props. It's invalid to reference a parent ID as that would create
-  /// a cycle. The child ID always refers to a Manufacturer cached ID, not a new
+  /// a cycle which the Manufacturer rejects when constructed. The child ID always refers to a Manufacturer cached ID, not a new
   /// definition. Because it's not a definition, it's not possible to reference
   /// a child Blueprint by ID.
-  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  #[serde(default)]
   pub children: Vec<Blueprint>,
+  /// An optional label for a child so that patches can remove it.
+  pub name: Option<String>,
+  /// Patch directive. Components to remove from the base before merging.
+  #[serde(default)]
+  pub remove: Vec<ComponentKind>,
+  /// Patch directive. Names of base children to remove before merging. A
+  /// child without a name is named by its definition, as for Siblings.
+  #[serde(default)]
+  pub remove_children: Vec<String>,
+  /// Patch directive. Sprite states whose base lists are replaced by the
+  /// patch's instead of merged. A state absent from the patch is removed.
+  #[serde(default)]
+  pub replace_sprites: Vec<String>,
 }
+serialize_blueprint!(Blueprint {
+  id;
+  components,
+  children,
+  name,
+  remove,
+  remove_children,
+  replace_sprites,
+});
 
-#[serde(deny_unknown_fields)]
-#[derive(Clone, Default, Deserialize, Serialize)]
-pub struct ComponentBlueprints {
-  pub align_to: Option<AlignToBlueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub cam: Option<SizeU16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub follow_mouse: Option<MarkerBlueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub position: Option<XY16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub velocity: Option<XY16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub text: Option<String>,
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub max_wh: Option<SizeU16Blueprint>,
-  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-  pub sprites: HashMap<String, Vec<SpriteBlueprint>>,
+/// The builtin components, one per line: the ComponentKind, the
+/// ComponentBlueprints field and key, and the Blueprint type. The table is
+/// passed to `$apply` so that ComponentBlueprints, ComponentKind, patching, and
+/// serialization are all generated from it. Add builtin components here and to
+/// the schema.
+macro_rules! builtin_components {
+  ($apply:ident) => {
+    $apply! {
+      AlignTo align_to: Option<AlignToBlueprint>,
+      Cam cam: Option<SizeU16Blueprint>,
+      Collision collision: Option<CollisionBlueprint>,
+      CollidesWith collides_with: Option<CollidesWith>,
+      FollowMouse follow_mouse: Option<MarkerBlueprint>,
+      Position position: Option<XY16Blueprint>,
+      Velocity velocity: Option<XY16Blueprint>,
+      Text text: Option<String>,
+      MaxWh max_wh: Option<SizeU16Blueprint>,
+      Sprites sprites: HashMap<String, Vec<SpriteBlueprint>>,
+      /// Labels for AlignTarget::Tag.
+      Tags tags: Vec<String>,
+      /// Weighted alternatives to sprites. The Manufacturer picks one with the
+      /// World's Random.
+      Variants variants: Vec<VariantBlueprint>,
+    }
+  };
+}
+
+macro_rules! component_blueprints {
+  ($($(#[$doc:meta])* $kind:ident $field:ident: $type:ty,)*) => {
+    /// Serialized by hand in component_blueprints_serde.rs since unknown keys
+    /// are registered components.
+    #[derive(Clone, Default)]
+    pub struct ComponentBlueprints {
+      $($(#[$doc])* pub $field: $type,)*
+      /// Registered component blueprints by key. The types are unknown until
+      /// manufactured so each is a list of JSON layers, the definition followed
+      /// by any patches, that the ComponentRegistry merges in order.
+      pub registered: BTreeMap<String, Vec<Value>>,
+
+      /// These linkages are established during manufacturing only.
+      pub parent: Option<Parent>,
+      pub children: Option<Children>,
+    }
+
+    impl ComponentBlueprints {
+      pub fn is_empty(blueprints: &ComponentBlueprints) -> bool {
+        $(blueprints.$field.is_absent() &&)* blueprints.registered.is_empty()
+      }
+    }
+
+    /// A ComponentBlueprints key. Every builtin field has a kind and so does
+    /// every registered component. Kinds are (de)serialized as their key.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ComponentKind {
+      $($kind,)*
+      /// The key of a component added to the ComponentRegistry.
+      Registered(String),
+    }
+
+    impl ComponentKind {
+      /// Returns every kind but Registered.
+      pub fn builtins() -> Vec<Self> {
+        vec![$(Self::$kind),*]
+      }
+
+      pub fn key(&self) -> &str {
+        match self {
+          $(Self::$kind => stringify!($field),)*
+          Self::Registered(key) => key,
+        }
+      }
+    }
+  };
+}
+
+builtin_components!(component_blueprints);
+
+impl ComponentKind {
+  /// Returns the builtin kind of key or else a Registered kind.
+  pub fn from_key(key: &str) -> Self {
+    Self::builtins()
+      .into_iter()
+      .find(|kind| kind.key() == key)
+      .unwrap_or_else(|| Self::Registered(key.to_string()))
+  }
+}
+
+/// A builtin ComponentBlueprints field or Blueprint property, which is absent
+/// when unset or empty. Absent fields aren't serialized to JSON.
+pub(crate) trait Absent {
+  fn is_absent(&self) -> bool;
+}
+
+impl<T> Absent for Option<T> {
+  fn is_absent(&self) -> bool {
+    self.is_none()
+  }
+}
 
-  /// These linkages are established during manufacturing only.
-  #[serde(skip)]
-  pub parent: Option<Parent>,
-  #[serde(skip)]
-  pub children: Option<Children>,
+impl<T> Absent for Vec<T> {
+  fn is_absent(&self) -> bool {
+    self.is_empty()
+  }
+}
+
+impl<K, V> Absent for HashMap<K, V> {
+  fn is_absent(&self) -> bool {
+    self.is_empty()
+  }
 }
 
-impl ComponentBlueprints {
-  pub fn is_empty(blueprints: &ComponentBlueprints) -> bool {
-    blueprints.align_to.is_none()
-      && blueprints.cam.is_none()
-      && blueprints.follow_mouse.is_none()
-      && blueprints.position.is_none()
-      && blueprints.velocity.is_none()
-      && blueprints.text.is_none()
-      && blueprints.max_wh.is_none()
-      && blueprints.sprites.is_empty()
+impl Absent for ComponentBlueprints {
+  fn is_absent(&self) -> bool {
+    Self::is_empty(self)
+  }
+}
+
+impl Serialize for ComponentKind {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.key())
+  }
+}
+
+impl<'de> Deserialize<'de> for ComponentKind {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    String::deserialize(deserializer).map(|key| Self::from_key(&key))
   }
 }
 
@@ -147,17 +328,14 @@ impl ComponentBlueprints {
 pub struct MarkerBlueprint {}
 
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
 pub struct R16Blueprint {
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub x: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub y: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub w: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub h: Option<i16>,
 }
+serialize_blueprint!(R16Blueprint { ; x, y, w, h });
 
 /// This Blueprint is special. It's prevalent and so provides destructured
 /// property shorthands for most properties, even composed objects. Precedence
@@ -178,86 +356,116 @@ pub struct R16Blueprint {
 /// and `h` values. If `bounds` had been omitted the result would be an `x` of
 /// 2, `y` of 1, `w` of default value, and `h` of 6.
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
 pub struct SpriteBlueprint {
   pub id: AnimationID,
   /// Defaults to source.
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub constituent_id: Option<AnimationID>,
   /// Defaults to SpriteComposition::Default.
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub composition: Option<SpriteComposition>,
 
   /// Defaults to a rectangle with Animation source width and height at 0, 0.
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub bounds: Option<R16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub position: Option<XY16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub x: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub y: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub size: Option<SizeU16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub w: Option<u16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub h: Option<u16>,
 
   /** Defaults to SpriteLayer::Default. */
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub layer: Option<SpriteLayer>,
 
   /** Defaults to (1, 1). */
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub scale: Option<XYBlueprint<NonZeroI16>>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub sx: Option<NonZeroI16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub sy: Option<NonZeroI16>,
 
   /** Defaults to (0, 0). */
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wrap: Option<XY16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wx: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wy: Option<i16>,
 
   /** Defaults to (0, 0). */
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wrap_velocity: Option<XY16Blueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wvx: Option<i16>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub wvy: Option<i16>,
 
   /** Defaults to (0, 0). */
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub animator: Option<AnimatorBlueprint>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub period: Option<AnimatorPeriod>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub exposure: Option<Millis>,
+
+  /** Defaults to no remap. */
+  pub palette: Option<SpritePalette>,
 }
+serialize_blueprint!(SpriteBlueprint {
+  id;
+  constituent_id,
+  composition,
+  bounds,
+  position,
+  x,
+  y,
+  size,
+  w,
+  h,
+  layer,
+  scale,
+  sx,
+  sy,
+  wrap,
+  wx,
+  wy,
+  wrap_velocity,
+  wvx,
+  wvy,
+  animator,
+  period,
+  exposure,
+  palette,
+});
 
+/// A visual variant. Its sprites are merged over the Blueprint's by state so
+/// it may vary IDs, scale flips, wrap offsets, animator periods, or anything
+/// else a sprite has.
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
+pub struct VariantBlueprint {
+  /// The relative likelihood of the variant. Defaults to 1.
+  pub weight: Option<NonZeroU16>,
+  #[serde(default)]
+  pub sprites: HashMap<String, Vec<SpriteBlueprint>>,
+}
+serialize_blueprint!(VariantBlueprint { ; weight, sprites });
+
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Deserialize)]
 pub struct XYBlueprint<T> {
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub x: Option<T>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub y: Option<T>,
 }
+serialize_blueprint!(XYBlueprint<T> { ; x, y });
 pub type XY16Blueprint = XYBlueprint<i16>;
+
+impl<T> From<XY<T>> for XYBlueprint<T> {
+  fn from(xy: XY<T>) -> Self {
+    Self { x: Some(xy.x), y: Some(xy.y) }
+  }
+}
 pub type XYU16Blueprint = XYBlueprint<u16>;
 
 #[serde(deny_unknown_fields)]
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize)]
 pub struct SizeBlueprint<T> {
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub w: Option<T>,
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub h: Option<T>,
 }
+serialize_blueprint!(SizeBlueprint<T> { ; w, h });
 pub type SizeU16Blueprint = SizeBlueprint<u16>;
+
+impl<T> From<XY<T>> for SizeBlueprint<T> {
+  fn from(size: XY<T>) -> Self {
+    Self { w: Some(size.x), h: Some(size.y) }
+  }
+}
@@ -1,3 +1,4 @@
+use super::BLUEPRINTS_DIR;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ord;
 use std::fmt;
@@ -7,6 +8,7 @@ use strum_macros::EnumIter;
 #[derive(
   Clone,
   Copy,
+  Debug,
   Deserialize,
   PartialEq,
   Eq,
@@ -76,6 +78,68 @@ pub static UI_KEY_PREFIX: &str = "UI";
 
 impl fmt::Display for BlueprintID {
   fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(formatter, "{}", self)
+    write!(formatter, "{:?}", self)
+  }
+}
+
+impl BlueprintID {
+  /// Returns the served path of the Blueprint definition.
+  pub fn filename(&self) -> String {
+    let name = match self {
+      Self::Bee => "bee",
+      Self::RainCloud => "rain_cloud",
+      Self::Button => "button",
+      Self::SaveDialog => "save_dialog",
+      Self::Cursor => "cursor",
+      Self::Map => "map",
+      Self::Fruit => "fruit",
+      Self::AppleTree => "apple_tree",
+      Self::Item => "item",
+      Self::Backpacker => "backpacker",
+      Self::Bunny => "bunny",
+      Self::Fly => "fly",
+      Self::Flower => "flower",
+      Self::Frog => "frog",
+      Self::Pig => "pig",
+      Self::Snake => "snake",
+      Self::Group => "group",
+      Self::LevelEditorSandbox => "level_editor_sandbox",
+      Self::Bush => "bush",
+      Self::Cattails => "cattails",
+      Self::Cloud => "cloud",
+      Self::Clover => "clover",
+      Self::Compartment => "compartment",
+      Self::Conifer => "conifer",
+      Self::Flag => "flag",
+      Self::Grass => "grass",
+      Self::Monument => "monument",
+      Self::Mountain => "mountain",
+      Self::Path => "path",
+      Self::Plane => "plane",
+      Self::Pond => "pond",
+      Self::Subshrub => "subshrub",
+      Self::Tree => "tree",
+      Self::UIButton => "ui_button",
+      Self::LifeCounter => "life_counter",
+      Self::NinePatch => "nine_patch",
+      Self::PlayerStatus => "player_status",
+      Self::UICheckbox => "ui_checkbox",
+      Self::UICursor => "ui_cursor",
+      Self::UICursorDot => "ui_cursor_dot",
+      Self::UICursorHand => "ui_cursor_hand",
+      Self::UICursorReticle => "ui_cursor_reticle",
+      Self::UIDateVersionHash => "ui_date_version_hash",
+      Self::UIDestinationMarker => "ui_destination_marker",
+      Self::UIEntityPicker => "ui_entity_picker",
+      Self::UILevelEditorPanelMenu => "ui_level_editor_panel_menu",
+      Self::UILevelEditorPanel => "ui_level_editor_panel",
+      Self::UILevelEditorPanelBackground => "ui_level_editor_panel_background",
+      Self::UILevelLink => "ui_level_link",
+      Self::UIMarquee => "ui_marquee",
+      Self::UIRadioCheckboxGroup => "ui_radio_checkbox_group",
+      Self::UIText => "ui_text",
+      Self::UIToolbar => "ui_toolbar",
+    };
+    format!("{}/{}.json", BLUEPRINTS_DIR, name)
   }
 }
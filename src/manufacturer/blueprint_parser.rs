@@ -0,0 +1,251 @@
+use super::{Blueprint, BlueprintID};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// The served directory of the Blueprint catalog.
+pub static BLUEPRINTS_DIR: &str = "/manufacturer/blueprints";
+
+#[derive(Debug)]
+pub struct BlueprintParseError(pub String);
+
+/// Returns the served paths of every Blueprint file in the catalog directory of
+/// the source directory, sorted for output independent of directory order.
+pub fn blueprint_paths(
+  source_dir: &Path,
+) -> Result<Vec<String>, BlueprintParseError> {
+  let to_error = |error: std::io::Error| BlueprintParseError(error.to_string());
+  let mut paths = Vec::new();
+  let dir = source_dir.join(BLUEPRINTS_DIR.trim_start_matches('/'));
+  for entry in fs::read_dir(dir).map_err(to_error)? {
+    let path = entry.map_err(to_error)?.path();
+    if path.extension().map_or(false, |extension| extension == "json") {
+      let filename = path.file_name().unwrap_or_default().to_string_lossy();
+      paths.push(format!("{}/{}", BLUEPRINTS_DIR, filename));
+    }
+  }
+  paths.sort();
+  Ok(paths)
+}
+
+/// Parses the Blueprint catalog given as served path and JSON file pairs. Each
+/// path must be the filename of a BlueprintID and each Blueprint must have that
+/// ID. Every malformed file is reported, not just the first.
+pub fn parse_blueprints(
+  files: &[(String, String)],
+) -> Result<HashMap<BlueprintID, Blueprint>, BlueprintParseError> {
+  let mut blueprints = HashMap::new();
+  let mut errors = Vec::new();
+  for (path, json) in files {
+    match parse_blueprint(path, json) {
+      Ok((id, blueprint)) => {
+        blueprints.insert(id, blueprint);
+      }
+      Err(error) => errors.push(error.0),
+    }
+  }
+  if errors.is_empty() {
+    Ok(blueprints)
+  } else {
+    Err(BlueprintParseError(format!(
+      "{} malformed Blueprint(s):\n{}",
+      errors.len(),
+      errors.join("\n")
+    )))
+  }
+}
+
+/// Parses a Blueprint file given its served path and JSON. Errors name the
+/// path and the JSONPath of the field that broke.
+pub fn parse_blueprint(
+  path: &str,
+  json: &str,
+) -> Result<(BlueprintID, Blueprint), BlueprintParseError> {
+  let id =
+    BlueprintID::iter().find(|id| id.filename() == path).ok_or_else(|| {
+      BlueprintParseError(format!(
+        "{}: no BlueprintID has this filename.",
+        path
+      ))
+    })?;
+  let blueprint: Blueprint = serde_json::from_str(json).map_err(|error| {
+    let field = json_path(json, error.line(), error.column());
+    BlueprintParseError(format!("{}: {}: {}", path, field, error))
+  })?;
+  if blueprint.id != id {
+    return Err(BlueprintParseError(format!(
+      "{}: $.id: {} mismatches the filename's {}.",
+      path, blueprint.id, id
+    )));
+  }
+  Ok((id, blueprint))
+}
+
+/// Returns the JSONPath of the innermost key or element being read at the
+/// one-based line and column, as reported by serde_json errors. E.g.,
+/// "$.components.sprites.Default[1].x".
+pub fn json_path(json: &str, line: usize, column: usize) -> String {
+  enum Scope {
+    Object(Option<String>),
+    Array(usize),
+  }
+  let mut scopes = Vec::new();
+  // The key being read, if any.
+  let mut key: Option<String> = None;
+  let mut in_string = false;
+  let mut escaped = false;
+  let mut expect_key = false;
+  let (mut char_line, mut char_column) = (1, 0);
+  for char in json.chars() {
+    if char == '\n' {
+      char_line += 1;
+      char_column = 0;
+      continue;
+    }
+    char_column += 1;
+    if (char_line, char_column) > (line, column) {
+      break;
+    }
+
+    if in_string {
+      if escaped {
+        escaped = false;
+      } else if char == '\\' {
+        escaped = true;
+      } else if char == '"' {
+        in_string = false;
+        let name = key.take();
+        if let Some(Scope::Object(object_key)) = scopes.last_mut() {
+          if let Some(name) = name {
+            *object_key = Some(name);
+            expect_key = false;
+          }
+        }
+      }
+      if let Some(name) = &mut key {
+        name.push(char);
+      }
+      continue;
+    }
+    match char {
+      '"' => {
+        in_string = true;
+        if expect_key {
+          key = Some(String::new());
+        }
+      }
+      '{' => {
+        scopes.push(Scope::Object(None));
+        expect_key = true;
+      }
+      '[' => scopes.push(Scope::Array(0)),
+      '}' | ']' => {
+        scopes.pop();
+      }
+      ',' => match scopes.last_mut() {
+        Some(Scope::Object(object_key)) => {
+          *object_key = None;
+          expect_key = true;
+        }
+        Some(Scope::Array(index)) => *index += 1,
+        None => (),
+      },
+      _ => (),
+    }
+  }
+
+  scopes.iter().fold("$".to_string(), |path, scope| match scope {
+    Scope::Object(Some(key)) => format!("{}.{}", path, key),
+    Scope::Object(None) => path,
+    Scope::Array(index) => format!("{}[{}]", path, index),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn json_path_at_error() {
+    [
+      (r#"{"id": "Nope"}"#, "$.id"),
+      (
+        r#"{"id": "Bee", "components": {"position": {"z": 1}}}"#,
+        "$.components.position.z",
+      ),
+      (
+        r#"{"id": "Bee", "components": {"sprites": {"Default": [{"id": "Bee"}, {"id": "Bee", "x": "1"}]}}}"#,
+        "$.components.sprites.Default[1].x",
+      ),
+      (
+        "{\n  \"id\": \"Bee\",\n  \"children\": [\n    {\"id\": \"Bee\", \"k\\\"\": 1}\n  ]\n}",
+        "$.children[0].k\\\"",
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (json, expected))| {
+      let error = serde_json::from_str::<Blueprint>(json).err().unwrap();
+      assert_eq!(
+        json_path(json, error.line(), error.column()),
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn blueprint_paths_lists_catalog() {
+    let paths = blueprint_paths(Path::new("src")).unwrap();
+    assert!(paths.contains(&BlueprintID::Bee.filename()));
+    for path in &paths {
+      assert!(BlueprintID::iter().any(|id| id.filename() == *path), "{}", path);
+    }
+  }
+
+  #[test]
+  fn parse_blueprints_aggregates_errors() {
+    let files = vec![
+      (
+        "/manufacturer/blueprints/bee.json".to_string(),
+        r#"{"id": "Bee", "components": {"position": {"x": 1}}}"#.to_string(),
+      ),
+      (
+        "/manufacturer/blueprints/button.json".to_string(),
+        r#"{"id": "Button", "components": {"velocity": {"x": "1"}}}"#
+          .to_string(),
+      ),
+      (
+        "/manufacturer/blueprints/cursor.json".to_string(),
+        r#"{"id": "Bee"}"#.to_string(),
+      ),
+      ("/manufacturer/blueprints/foo.json".to_string(), "{}".to_string()),
+    ];
+    let error = parse_blueprints(&files).err().unwrap().0;
+    let lines: Vec<_> = error.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", error);
+    assert_eq!(lines[0], "3 malformed Blueprint(s):");
+    assert!(
+      lines[1].starts_with(
+        "/manufacturer/blueprints/button.json: $.components.velocity.x: \
+         invalid type: string \"1\""
+      ),
+      "{}",
+      lines[1]
+    );
+    assert_eq!(
+      lines[2],
+      "/manufacturer/blueprints/cursor.json: $.id: Bee mismatches the \
+       filename's Cursor."
+    );
+    assert_eq!(
+      lines[3],
+      "/manufacturer/blueprints/foo.json: no BlueprintID has this filename."
+    );
+
+    let blueprints = parse_blueprints(&files[0..1]).unwrap();
+    assert!(blueprints.contains_key(&BlueprintID::Bee));
+  }
+}
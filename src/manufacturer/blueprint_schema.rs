@@ -0,0 +1,555 @@
+//! A JSON Schema for hand-written Blueprint JSON files so that editors can
+//! autocomplete and validate them. The schema mirrors the serde definitions in
+//! blueprint.rs by hand but enumerations are generated so they never go stale.
+//! Every builtin component must have a schema to compile.
+//! The drift tests below check the schema against what serde actually accepts.
+//! Registered components are those of `components::component_registry()` and
+//! any other component key is rejected. Their Blueprints are only checked to be
+//! objects.
+
+use super::{BlueprintID, ComponentKind};
+use crate::atlas::AnimationID;
+use crate::components::{self, Alignment, CollidesWith};
+use crate::sprites::{SpriteComposition, SpriteLayer};
+use serde_json::{json, Map, Value};
+use std::fmt::Debug;
+use strum::IntoEnumIterator;
+
+/// The committed schema file path relative to the source directory.
+pub static SCHEMA_PATH: &str = "/manufacturer/blueprint.schema.json";
+
+/// Returns the JSON Schema (draft-07) of a Blueprint file.
+pub fn blueprint_schema() -> Value {
+  json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "title": "Blueprint",
+    "$ref": "#/definitions/Blueprint",
+    "definitions": {
+      "Blueprint": object(
+        json!({
+          "id": reference("BlueprintID"),
+          "components": reference("ComponentBlueprints"),
+          "children": {
+            "description": "Patches of other definition Blueprints.",
+            "type": "array",
+            "items": reference("Blueprint")
+          },
+          "name": {
+            "description": "A child label referenced by remove_children.",
+            "type": "string"
+          },
+          "remove": {
+            "description": "Base components to remove before merging.",
+            "type": "array",
+            "items": reference("ComponentKind")
+          },
+          "remove_children": {
+            "description": "Names of base children to remove before merging.",
+            "type": "array",
+            "items": {"type": "string"}
+          },
+          "replace_sprites": {
+            "description": "Base sprite states to replace instead of merge.",
+            "type": "array",
+            "items": {"type": "string"}
+          }
+        }),
+        &["id"]
+      ),
+      "ComponentBlueprints": component_blueprints(),
+      "AlignToBlueprint": object(
+        json!({
+          "alignment": reference("Alignment"),
+          "margin": reference("XY16Blueprint"),
+          "to": describe(reference("AlignTarget"), "Defaults to no target.")
+        }),
+        &["alignment"]
+      ),
+      "AlignTarget": {
+        "anyOf": [
+          {"enum": ["Parent", "Camera"]},
+          object(
+            json!({
+              "Sibling": {
+                "description": "The name of a child of the parent.",
+                "type": "string"
+              }
+            }),
+            &["Sibling"]
+          ),
+          object(json!({"Tag": {"type": "string"}}), &["Tag"])
+        ]
+      },
+      "AnimatorBlueprint": object(
+        json!({"period": animator_period(), "exposure": millis()}),
+        &[]
+      ),
+      "CollisionBlueprint": object(
+        json!({
+          "bodies": {
+            "description": "Bodies in local pixels.",
+            "type": "array",
+            "items": reference("R16Blueprint")
+          },
+          "slices": {
+            "description": "Animations whose first Cel slices are bodies.",
+            "type": "array",
+            "items": reference("AnimationID")
+          }
+        }),
+        &[]
+      ),
+      "MarkerBlueprint": object(json!({}), &[]),
+      "R16Blueprint": object(
+        json!({"x": i16(), "y": i16(), "w": i16(), "h": i16()}),
+        &[]
+      ),
+      "SizeU16Blueprint": object(json!({"w": u16(), "h": u16()}), &[]),
+      "SpriteBlueprint": object(
+        json!({
+          "id": reference("AnimationID"),
+          "constituent_id": describe(
+            reference("AnimationID"),
+            "Defaults to id."
+          ),
+          "composition": describe(
+            reference("SpriteComposition"),
+            "Defaults to Source."
+          ),
+          "bounds": describe(
+            reference("R16Blueprint"),
+            "Defaults to the Animation size at 0, 0. Takes precedence over \
+             position, size, and their shorthands."
+          ),
+          "position": describe(
+            reference("XY16Blueprint"),
+            "Takes precedence over x and y."
+          ),
+          "x": describe(i16(), "Shorthand for position.x."),
+          "y": describe(i16(), "Shorthand for position.y."),
+          "size": describe(
+            reference("SizeU16Blueprint"),
+            "Takes precedence over w and h."
+          ),
+          "w": describe(u16(), "Shorthand for size.w."),
+          "h": describe(u16(), "Shorthand for size.h."),
+          "layer": describe(reference("SpriteLayer"), "Defaults to Default."),
+          "scale": describe(
+            reference("XYNonZeroI16Blueprint"),
+            "Defaults to 1, 1. Takes precedence over sx and sy."
+          ),
+          "sx": describe(non_zero_i16(), "Shorthand for scale.x."),
+          "sy": describe(non_zero_i16(), "Shorthand for scale.y."),
+          "wrap": describe(
+            reference("XY16Blueprint"),
+            "Defaults to 0, 0. Takes precedence over wx and wy."
+          ),
+          "wx": describe(i16(), "Shorthand for wrap.x."),
+          "wy": describe(i16(), "Shorthand for wrap.y."),
+          "wrap_velocity": describe(
+            reference("XY16Blueprint"),
+            "Defaults to 0, 0. Takes precedence over wvx and wvy."
+          ),
+          "wvx": describe(i16(), "Shorthand for wrap_velocity.x."),
+          "wvy": describe(i16(), "Shorthand for wrap_velocity.y."),
+          "animator": describe(
+            reference("AnimatorBlueprint"),
+            "Takes precedence over period and exposure."
+          ),
+          "period": describe(
+            animator_period(),
+            "Shorthand for animator.period."
+          ),
+          "exposure": describe(millis(), "Shorthand for animator.exposure."),
+          "palette": describe(
+            reference("SpritePalette"),
+            "Defaults to no remap."
+          )
+        }),
+        &["id"]
+      ),
+      "SpritePalette": object(
+        json!({
+          "source": reference("AnimationID"),
+          "target": reference("AnimationID"),
+          "row": describe(u16(), "Defaults to 0.")
+        }),
+        &["source", "target"]
+      ),
+      "VariantBlueprint": object(
+        json!({
+          "weight": describe(non_zero_u16(), "Defaults to 1."),
+          "sprites": describe(
+            sprite_states(),
+            "Sprites by state merged over the Blueprint's."
+          )
+        }),
+        &[]
+      ),
+      "XY16Blueprint": object(json!({"x": i16(), "y": i16()}), &[]),
+      "XYNonZeroI16Blueprint": object(
+        json!({"x": non_zero_i16(), "y": non_zero_i16()}),
+        &[]
+      ),
+      "Alignment": variants::<Alignment>(),
+      "AnimationID": variants::<AnimationID>(),
+      "BlueprintID": variants::<BlueprintID>(),
+      "CollidesWithFlag": {"enum": CollidesWith::names()},
+      "ComponentKind": component_kinds(),
+      "SpriteComposition": variants::<SpriteComposition>(),
+      "SpriteLayer": variants::<SpriteLayer>()
+    }
+  })
+}
+
+fn object(properties: Value, required: &[&str]) -> Value {
+  let mut object = json!({
+    "type": "object",
+    "properties": properties,
+    "additionalProperties": false
+  });
+  if !required.is_empty() {
+    object["required"] = json!(required);
+  }
+  object
+}
+
+fn reference(definition: &str) -> Value {
+  json!({ "$ref": format!("#/definitions/{}", definition) })
+}
+
+fn describe(mut schema: Value, description: &str) -> Value {
+  schema["description"] = json!(description);
+  schema
+}
+
+fn sprite_states() -> Value {
+  json!({
+    "description": "Sprites by state.",
+    "type": "object",
+    "additionalProperties": {
+      "type": "array",
+      "items": reference("SpriteBlueprint")
+    }
+  })
+}
+
+/// Variants are (de)serialized by name.
+fn variants<T: IntoEnumIterator + Debug>() -> Value {
+  let names: Vec<_> =
+    T::iter().map(|variant| format!("{:?}", variant)).collect();
+  json!({ "enum": names })
+}
+
+/// Every builtin and registered component.
+fn component_blueprints() -> Value {
+  let properties: Map<_, _> = kinds()
+    .iter()
+    .map(|kind| (kind.key().to_string(), component(kind)))
+    .collect();
+  object(Value::Object(properties), &[])
+}
+
+/// Every builtin kind followed by every registered kind.
+fn kinds() -> Vec<ComponentKind> {
+  let mut kinds = ComponentKind::builtins();
+  kinds.extend(
+    components::component_registry()
+      .keys()
+      .into_iter()
+      .map(|key| ComponentKind::Registered(key.to_string())),
+  );
+  kinds
+}
+
+/// The schema of a kind's component.
+fn component(kind: &ComponentKind) -> Value {
+  match kind {
+    ComponentKind::AlignTo => reference("AlignToBlueprint"),
+    ComponentKind::Cam => reference("SizeU16Blueprint"),
+    ComponentKind::Collision => reference("CollisionBlueprint"),
+    ComponentKind::CollidesWith => json!({
+      "description": "Collision classifications of the owner.",
+      "type": "array",
+      "items": reference("CollidesWithFlag")
+    }),
+    ComponentKind::FollowMouse => reference("MarkerBlueprint"),
+    ComponentKind::Position => reference("XY16Blueprint"),
+    ComponentKind::Velocity => reference("XY16Blueprint"),
+    ComponentKind::Text => json!({"type": "string"}),
+    ComponentKind::MaxWh => reference("SizeU16Blueprint"),
+    ComponentKind::Sprites => sprite_states(),
+    ComponentKind::Tags => json!({
+      "description": "Labels for Tag alignment targets.",
+      "type": "array",
+      "items": {"type": "string"}
+    }),
+    ComponentKind::Variants => json!({
+      "description": "Weighted alternatives to sprites.",
+      "type": "array",
+      "items": reference("VariantBlueprint")
+    }),
+    ComponentKind::Registered(_) => json!({
+      "description": "A component added to the ComponentRegistry.",
+      "type": "object"
+    }),
+  }
+}
+
+/// Kinds are (de)serialized by their keys.
+fn component_kinds() -> Value {
+  let keys: Vec<_> =
+    kinds().iter().map(|kind| kind.key().to_string()).collect();
+  json!({ "enum": keys })
+}
+
+fn integer(minimum: i64, maximum: i64) -> Value {
+  json!({"type": "integer", "minimum": minimum, "maximum": maximum})
+}
+
+fn i16() -> Value {
+  integer(i16::min_value().into(), i16::max_value().into())
+}
+
+fn u16() -> Value {
+  integer(u16::min_value().into(), u16::max_value().into())
+}
+
+fn non_zero_i16() -> Value {
+  let mut schema = i16();
+  schema["not"] = json!({"const": 0});
+  schema
+}
+
+fn non_zero_u16() -> Value {
+  integer(1, u16::max_value().into())
+}
+
+fn animator_period() -> Value {
+  integer(i32::min_value().into(), i32::max_value().into())
+}
+
+fn millis() -> Value {
+  json!({"type": "number"})
+}
+
+#[cfg(test)]
+mod test {
+  use super::super::{
+    AlignTarget, AlignToBlueprint, AnimatorBlueprint, Blueprint,
+    CollisionBlueprint, ComponentBlueprints, MarkerBlueprint, R16Blueprint,
+    SizeU16Blueprint, SpriteBlueprint, VariantBlueprint, XY16Blueprint,
+    XYBlueprint,
+  };
+  use super::*;
+  use crate::sprites::SpritePalette;
+  use serde::de::DeserializeOwned;
+  use std::num::NonZeroI16;
+
+  #[test]
+  fn committed_schema_is_current() {
+    let committed: Value = include_json!("blueprint.schema.json").unwrap();
+    assert!(
+      committed == blueprint_schema(),
+      "{} is stale. Regenerate it with `npm run build:schema`.",
+      SCHEMA_PATH
+    );
+  }
+
+  /// Every object definition must have exactly the fields serde accepts and
+  /// every enumeration exactly the variants. Variants with data are the
+  /// properties of the alternatives.
+  #[test]
+  fn schema_names_match_serde() {
+    let schema = blueprint_schema();
+    for (name, definition) in schema["definitions"].as_object().unwrap() {
+      let mut expected: Vec<String> = if let Some(names) =
+        enumeration(definition)
+      {
+        let alternatives = definition["anyOf"].as_array();
+        names
+          .as_array()
+          .unwrap()
+          .iter()
+          .map(|name| name.as_str().unwrap().to_string())
+          .chain(
+            alternatives
+              .into_iter()
+              .flatten()
+              .filter_map(|alternative| alternative["properties"].as_object())
+              .flat_map(|properties| properties.keys().cloned()),
+          )
+          .collect()
+      } else {
+        definition["properties"].as_object().unwrap().keys().cloned().collect()
+      };
+      expected.sort();
+      let mut actual = serde_names(name, definition);
+      actual.sort();
+      assert_eq!(actual, expected, "Definition {} drifted.", name);
+    }
+  }
+
+  #[test]
+  fn component_kinds_match_components() {
+    let schema = blueprint_schema();
+    let definitions = &schema["definitions"];
+    let mut kinds: Vec<_> = enumeration(&definitions["ComponentKind"])
+      .unwrap()
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|kind| kind.as_str().unwrap())
+      .collect();
+    let mut properties: Vec<_> = definitions["ComponentBlueprints"]
+      ["properties"]
+      .as_object()
+      .unwrap()
+      .keys()
+      .map(String::as_str)
+      .collect();
+    kinds.sort();
+    properties.sort();
+    assert_eq!(kinds, properties);
+  }
+
+  #[test]
+  fn component_keys_are_closed() {
+    let schema = blueprint_schema();
+    let components = &schema["definitions"]["ComponentBlueprints"];
+    assert_eq!(components["additionalProperties"], json!(false));
+    assert!(components["properties"]["cursor"].is_object());
+    assert!(components["properties"]["positon"].is_null());
+  }
+
+  /// A minimal instance of every definition, and that instance with each
+  /// optional property set, must deserialize.
+  #[test]
+  fn schema_samples_deserialize() {
+    let schema = blueprint_schema();
+    let definitions = &schema["definitions"];
+    for (name, definition) in definitions.as_object().unwrap() {
+      let minimal = sample(definitions, definition);
+      check_sample(name, &minimal);
+      if let Some(properties) = definition["properties"].as_object() {
+        for (property, property_schema) in properties {
+          let mut instance = minimal.clone();
+          instance[property] = sample(definitions, property_schema);
+          check_sample(name, &instance);
+        }
+      }
+    }
+  }
+
+  /// Returns the enum of the definition, or of its first alternative.
+  fn enumeration(definition: &Value) -> Option<&Value> {
+    Some(&definition["enum"])
+      .filter(|names| names.is_array())
+      .or_else(|| Some(&definition["anyOf"][0]["enum"]))
+      .filter(|names| names.is_array())
+  }
+
+  /// Returns the field or variant names serde accepts for the named definition
+  /// by reading the error message for an unknown name. Definitions that accept
+  /// registered component keys can't reject unknown names so their names are
+  /// the builtin and registered ComponentKinds instead.
+  fn serde_names(name: &str, definition: &Value) -> Vec<String> {
+    match name {
+      "ComponentBlueprints" | "ComponentKind" => {
+        return serde_json::from_value(serde_json::to_value(kinds()).unwrap())
+          .unwrap();
+      }
+      _ => (),
+    }
+    let unknown = if definition["enum"].is_array() {
+      json!("\u{0}")
+    } else {
+      json!({"\u{0}": 0})
+    };
+    let error = deserialize(name, unknown).err().unwrap();
+    // E.g., "unknown field `\u{0}`, expected one of `x`, `y`" or "unknown
+    // field `\u{0}`, there are no fields".
+    let expected = error.splitn(2, ", ").nth(1).unwrap_or("");
+    expected.split('`').skip(1).step_by(2).map(str::to_string).collect()
+  }
+
+  fn sample(definitions: &Value, schema: &Value) -> Value {
+    if let Some(reference) = schema["$ref"].as_str() {
+      let name = reference.trim_start_matches("#/definitions/");
+      return sample(definitions, &definitions[name]);
+    }
+    if let Some(variants) = schema["enum"].as_array() {
+      return variants[0].clone();
+    }
+    if let Some(alternatives) = schema["anyOf"].as_array() {
+      return sample(definitions, &alternatives[0]);
+    }
+    match schema["type"].as_str().unwrap() {
+      "integer" => schema["minimum"].clone(),
+      "number" => json!(0.5),
+      "string" => json!(""),
+      "array" => json!([sample(definitions, &schema["items"])]),
+      // E.g., a registered component.
+      "object" if schema["additionalProperties"].is_null() => json!({}),
+      "object" if schema["properties"].is_null() => {
+        json!({"Default": sample(definitions, &schema["additionalProperties"])})
+      }
+      "object" => {
+        let mut object = json!({});
+        for property in schema["required"].as_array().unwrap_or(&vec![]) {
+          let property = property.as_str().unwrap();
+          object[property] =
+            sample(definitions, &schema["properties"][property]);
+        }
+        object
+      }
+      kind => panic!("Type {} unsupported.", kind),
+    }
+  }
+
+  fn check_sample(name: &str, instance: &Value) {
+    let result = deserialize(name, instance.clone());
+    assert!(
+      result.is_ok(),
+      "{} sample {} rejected: {:?}",
+      name,
+      instance,
+      result
+    );
+  }
+
+  /// Deserializes json as the serde type of the named definition.
+  fn deserialize(name: &str, json: Value) -> Result<(), String> {
+    match name {
+      "Blueprint" => from_value::<Blueprint>(json),
+      "ComponentBlueprints" => from_value::<ComponentBlueprints>(json),
+      "AlignToBlueprint" => from_value::<AlignToBlueprint>(json),
+      "AlignTarget" => from_value::<AlignTarget>(json),
+      "AnimatorBlueprint" => from_value::<AnimatorBlueprint>(json),
+      "CollisionBlueprint" => from_value::<CollisionBlueprint>(json),
+      "MarkerBlueprint" => from_value::<MarkerBlueprint>(json),
+      "R16Blueprint" => from_value::<R16Blueprint>(json),
+      "SizeU16Blueprint" => from_value::<SizeU16Blueprint>(json),
+      "SpriteBlueprint" => from_value::<SpriteBlueprint>(json),
+      "SpritePalette" => from_value::<SpritePalette>(json),
+      "VariantBlueprint" => from_value::<VariantBlueprint>(json),
+      "XY16Blueprint" => from_value::<XY16Blueprint>(json),
+      "XYNonZeroI16Blueprint" => from_value::<XYBlueprint<NonZeroI16>>(json),
+      "Alignment" => from_value::<Alignment>(json),
+      "AnimationID" => from_value::<AnimationID>(json),
+      "BlueprintID" => from_value::<BlueprintID>(json),
+      // Flags are (de)serialized as a list.
+      "CollidesWithFlag" => from_value::<CollidesWith>(json!([json])),
+      "ComponentKind" => from_value::<ComponentKind>(json),
+      "SpriteComposition" => from_value::<SpriteComposition>(json),
+      "SpriteLayer" => from_value::<SpriteLayer>(json),
+      _ => panic!("Definition {} has no serde type.", name),
+    }
+  }
+
+  fn from_value<T: DeserializeOwned>(json: Value) -> Result<(), String> {
+    serde_json::from_value::<T>(json)
+      .map(|_| ())
+      .map_err(|error| error.to_string())
+  }
+}
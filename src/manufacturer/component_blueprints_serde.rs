@@ -0,0 +1,196 @@
+//! ComponentBlueprints (de)serialization. JSON is a map of the builtin fields
+//! and any registered component keys. Absent components are omitted. When not
+//! human-readable, every builtin field is serialized in order followed by the
+//! registered layers as BinValues.
+
+use super::{Absent, ComponentBlueprints};
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+macro_rules! component_blueprints_serde {
+  ($($(#[$doc:meta])* $kind:ident $field:ident: $type:ty,)*) => {
+    const FIELDS: &[&str] = &[$(stringify!($field),)* "registered"];
+
+    impl Serialize for ComponentBlueprints {
+      fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+      ) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+          let mut state =
+            serializer.serialize_struct("ComponentBlueprints", FIELDS.len())?;
+          $(state.serialize_field(stringify!($field), &self.$field)?;)*
+          let registered: BTreeMap<&String, Vec<BinValue>> = self
+            .registered
+            .iter()
+            .map(|(key, layers)| {
+              (key, layers.iter().map(BinValue::from).collect())
+            })
+            .collect();
+          state.serialize_field("registered", &registered)?;
+          return state.end();
+        }
+        let mut map = serializer.serialize_map(None)?;
+        $(
+          if !self.$field.is_absent() {
+            map.serialize_entry(stringify!($field), &self.$field)?;
+          }
+        )*
+        for (key, layers) in &self.registered {
+          // Only the registry can merge layers. See
+          // ComponentRegistry::flatten().
+          match layers.as_slice() {
+            [layer] => map.serialize_entry(key, layer)?,
+            _ => {
+              return Err(ser::Error::custom(format!(
+                "Registered component {} has {} layers; expected 1.",
+                key,
+                layers.len()
+              )))
+            }
+          }
+        }
+        map.end()
+      }
+    }
+
+    impl<'de> Visitor<'de> for ComponentBlueprintsVisitor {
+      type Value = ComponentBlueprints;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct ComponentBlueprints")
+      }
+
+      fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+      ) -> Result<Self::Value, A::Error> {
+        let mut components = ComponentBlueprints::default();
+        while let Some(key) = map.next_key::<String>()? {
+          match key.as_str() {
+            $(stringify!($field) => components.$field = map.next_value()?,)*
+            _ => {
+              let layer: Value = map.next_value()?;
+              components.registered.insert(key, vec![layer]);
+            }
+          }
+        }
+        Ok(components)
+      }
+
+      fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+      ) -> Result<Self::Value, A::Error> {
+        let mut components = ComponentBlueprints::default();
+        let mut index = 0;
+        $(
+          components.$field = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(index, &self))?;
+          index += 1;
+        )*
+        let registered: BTreeMap<String, Vec<BinValue>> = seq
+          .next_element()?
+          .ok_or_else(|| de::Error::invalid_length(index, &self))?;
+        components.registered = registered
+          .into_iter()
+          .map(|(key, layers)| {
+            (key, layers.into_iter().map(Value::from).collect())
+          })
+          .collect();
+        Ok(components)
+      }
+    }
+  };
+}
+
+builtin_components!(component_blueprints_serde);
+
+impl<'de> Deserialize<'de> for ComponentBlueprints {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    if deserializer.is_human_readable() {
+      deserializer.deserialize_map(ComponentBlueprintsVisitor)
+    } else {
+      deserializer.deserialize_struct(
+        "ComponentBlueprints",
+        FIELDS,
+        ComponentBlueprintsVisitor,
+      )
+    }
+  }
+}
+
+struct ComponentBlueprintsVisitor;
+
+/// A registered component layer when not human-readable. Value is only
+/// deserialized self-describingly, which bincode doesn't support.
+#[derive(Deserialize, Serialize)]
+enum BinValue {
+  Null,
+  Bool(bool),
+  I64(i64),
+  U64(u64),
+  F64(f64),
+  String(String),
+  Array(Vec<BinValue>),
+  Object(Vec<(String, BinValue)>),
+}
+
+impl From<&Value> for BinValue {
+  fn from(value: &Value) -> Self {
+    match value {
+      Value::Null => Self::Null,
+      Value::Bool(value) => Self::Bool(*value),
+      Value::Number(number) => {
+        if let Some(number) = number.as_u64() {
+          Self::U64(number)
+        } else if let Some(number) = number.as_i64() {
+          Self::I64(number)
+        } else {
+          Self::F64(number.as_f64().unwrap_or_default())
+        }
+      }
+      Value::String(value) => Self::String(value.clone()),
+      Value::Array(values) => {
+        Self::Array(values.iter().map(Self::from).collect())
+      }
+      Value::Object(map) => Self::Object(
+        map
+          .iter()
+          .map(|(key, value)| (key.clone(), Self::from(value)))
+          .collect(),
+      ),
+    }
+  }
+}
+
+impl From<BinValue> for Value {
+  fn from(value: BinValue) -> Self {
+    match value {
+      BinValue::Null => Self::Null,
+      BinValue::Bool(value) => Self::Bool(value),
+      BinValue::I64(number) => Self::Number(number.into()),
+      BinValue::U64(number) => Self::Number(number.into()),
+      BinValue::F64(number) => {
+        Number::from_f64(number).map_or(Self::Null, Self::Number)
+      }
+      BinValue::String(value) => Self::String(value),
+      BinValue::Array(values) => {
+        Self::Array(values.into_iter().map(Self::from).collect())
+      }
+      BinValue::Object(entries) => Self::Object(
+        entries
+          .into_iter()
+          .map(|(key, value)| (key, Self::from(value)))
+          .collect::<Map<String, Value>>(),
+      ),
+    }
+  }
+}
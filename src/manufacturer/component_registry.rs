@@ -0,0 +1,263 @@
+//! Components manufactured from Blueprints without changes to the Manufacturer
+//! core. Each registered component provides its Blueprint type, keyed by name
+//! in ComponentBlueprints, and how that type patches and manufactures. The
+//! Manufacturer validates, merges, and manufactures registered components
+//! through the registry. Registered components are saved as their Blueprints.
+
+use super::{Blueprint, ComponentKind};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use specs::{Component, Entity, World, WorldExt};
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+/// The Blueprint of a registered component.
+pub trait RegisteredBlueprint:
+  Clone + DeserializeOwned + Serialize + 'static
+{
+  type Component: Component;
+
+  /// The ComponentBlueprints key. E.g., "cursor".
+  const KEY: &'static str;
+
+  /// Returns a copy of self with patch merged in. Patch takes precedence.
+  fn patch(&self, patch: &Self) -> Self;
+
+  fn manufacture(&self) -> Self::Component;
+
+  /// Returns the Blueprint that manufactures component.
+  fn from_component(component: &Self::Component) -> Self;
+}
+
+/// The registered component types by key.
+#[derive(Default)]
+pub struct ComponentRegistry {
+  registrations: HashMap<&'static str, Box<dyn Registration>>,
+}
+
+impl ComponentRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers T. Panics if the key is taken by a builtin or registered
+  /// component.
+  pub fn with<T: RegisteredBlueprint>(mut self) -> Self
+  where
+    <T::Component as Component>::Storage: Default,
+  {
+    assert!(
+      !ComponentKind::builtins().iter().any(|kind| kind.key() == T::KEY),
+      "Registered component {} is builtin.",
+      T::KEY
+    );
+    let registration: Box<dyn Registration> =
+      Box::new(TypedRegistration::<T>(PhantomData));
+    let duplicate = self.registrations.insert(T::KEY, registration);
+    assert!(duplicate.is_none(), "Component {} registered twice.", T::KEY);
+    self
+  }
+
+  pub fn contains(&self, key: &str) -> bool {
+    self.registrations.contains_key(key)
+  }
+
+  /// Returns the registered keys in order.
+  pub fn keys(&self) -> Vec<&'static str> {
+    let mut keys: Vec<_> = self.registrations.keys().copied().collect();
+    keys.sort();
+    keys
+  }
+
+  /// Registers every registered component with the World.
+  pub fn register(&self, ecs: &mut World) {
+    for registration in self.registrations.values() {
+      registration.register(ecs);
+    }
+  }
+
+  /// Checks that the key is registered and that the layers deserialize.
+  pub fn validate(&self, key: &str, layers: &[Value]) -> Result<(), String> {
+    self.get(key)?.validate(layers)
+  }
+
+  /// Merges the layers and inserts the component. The layers must be valid.
+  pub fn manufacture(
+    &self,
+    ecs: &mut World,
+    entity: Entity,
+    key: &str,
+    layers: &[Value],
+  ) -> Result<(), String> {
+    self.get(key)?.manufacture(ecs, entity, layers)
+  }
+
+  /// Merges the layers of every registered component of the Blueprint and its
+  /// children into one so that the Blueprint serializes. The layers must be
+  /// valid.
+  pub fn flatten(&self, blueprint: &mut Blueprint) -> Result<(), String> {
+    for (key, layers) in blueprint.components.registered.iter_mut() {
+      if layers.len() > 1 {
+        *layers = vec![self.get(key)?.flatten(layers)?];
+      }
+    }
+    for child in &mut blueprint.children {
+      self.flatten(child)?;
+    }
+    Ok(())
+  }
+
+  /// Returns the Blueprint JSON of the component the layers manufacture as
+  /// `save()` would return it. The layers must be valid.
+  pub fn saved(&self, key: &str, layers: &[Value]) -> Result<Value, String> {
+    self.get(key)?.saved(layers)
+  }
+
+  /// Returns the Entity's registered components as Blueprint JSON by key.
+  pub fn save(
+    &self,
+    ecs: &World,
+    entity: Entity,
+  ) -> Result<BTreeMap<String, Value>, String> {
+    let mut components = BTreeMap::new();
+    for (&key, registration) in &self.registrations {
+      if let Some(blueprint) = registration.save(ecs, entity)? {
+        components.insert(key.to_string(), blueprint);
+      }
+    }
+    Ok(components)
+  }
+
+  /// Inserts the registered components returned by `save()`.
+  pub fn load(
+    &self,
+    ecs: &mut World,
+    entity: Entity,
+    components: &BTreeMap<String, Value>,
+  ) -> Result<(), String> {
+    for (key, blueprint) in components {
+      self.manufacture(ecs, entity, key, &[blueprint.clone()])?;
+    }
+    Ok(())
+  }
+
+  fn get(&self, key: &str) -> Result<&dyn Registration, String> {
+    self
+      .registrations
+      .get(key)
+      .map(Box::as_ref)
+      .ok_or_else(|| format!("Component {} unregistered.", key))
+  }
+}
+
+/// RegisteredBlueprint with its type erased.
+trait Registration {
+  fn register(&self, ecs: &mut World);
+  fn validate(&self, layers: &[Value]) -> Result<(), String>;
+  fn flatten(&self, layers: &[Value]) -> Result<Value, String>;
+  fn manufacture(
+    &self,
+    ecs: &mut World,
+    entity: Entity,
+    layers: &[Value],
+  ) -> Result<(), String>;
+  fn saved(&self, layers: &[Value]) -> Result<Value, String>;
+  fn save(&self, ecs: &World, entity: Entity) -> Result<Option<Value>, String>;
+}
+
+struct TypedRegistration<T>(PhantomData<T>);
+
+impl<T: RegisteredBlueprint> Registration for TypedRegistration<T>
+where
+  <T::Component as Component>::Storage: Default,
+{
+  fn register(&self, ecs: &mut World) {
+    ecs.register::<T::Component>();
+  }
+
+  fn validate(&self, layers: &[Value]) -> Result<(), String> {
+    merge::<T>(layers).map(|_| ())
+  }
+
+  fn flatten(&self, layers: &[Value]) -> Result<Value, String> {
+    serde_json::to_value(merge::<T>(layers)?).map_err(|error| error.to_string())
+  }
+
+  fn manufacture(
+    &self,
+    ecs: &mut World,
+    entity: Entity,
+    layers: &[Value],
+  ) -> Result<(), String> {
+    let component = merge::<T>(layers)?.manufacture();
+    ecs
+      .write_storage::<T::Component>()
+      .insert(entity, component)
+      .map(|_| ())
+      .map_err(|error| error.to_string())
+  }
+
+  fn saved(&self, layers: &[Value]) -> Result<Value, String> {
+    let component = merge::<T>(layers)?.manufacture();
+    serde_json::to_value(T::from_component(&component))
+      .map_err(|error| error.to_string())
+  }
+
+  fn save(&self, ecs: &World, entity: Entity) -> Result<Option<Value>, String> {
+    ecs
+      .read_storage::<T::Component>()
+      .get(entity)
+      .map(|component| serde_json::to_value(T::from_component(component)))
+      .transpose()
+      .map_err(|error| error.to_string())
+  }
+}
+
+/// Deserializes each layer and patches them in order.
+fn merge<T: RegisteredBlueprint>(layers: &[Value]) -> Result<T, String> {
+  let mut blueprints = layers.iter().map(|layer| {
+    serde_json::from_value::<T>(layer.clone())
+      .map_err(|error| error.to_string())
+  });
+  let base = blueprints
+    .next()
+    .unwrap_or_else(|| Err(format!("Component {} has no layers.", T::KEY)))?;
+  blueprints.try_fold(base, |base, patch| Ok(base.patch(&patch?)))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::components::component_registry;
+  use crate::manufacturer::PatchBlueprint;
+  use serde_json::json;
+
+  #[test]
+  fn flatten_patched() {
+    let base: Blueprint = serde_json::from_value(json!({
+      "id": "Cursor",
+      "components": {"cursor": {"mode": "Point"}},
+      "children": [{"id": "Bee", "components": {"cursor": {"icon": "Dot"}}}]
+    }))
+    .unwrap();
+    let patch: Blueprint = serde_json::from_value(json!({
+      "id": "Cursor",
+      "components": {"cursor": {"icon": "Hand"}}
+    }))
+    .unwrap();
+    let mut patched = base.patch(&patch);
+    assert!(serde_json::to_value(&patched).is_err());
+
+    component_registry().flatten(&mut patched).unwrap();
+
+    assert_eq!(
+      serde_json::to_value(&patched).unwrap(),
+      json!({
+        "id": "Cursor",
+        "components": {"cursor": {"mode": "Point", "icon": "Hand"}},
+        "children": [{"id": "Bee", "components": {"cursor": {"icon": "Dot"}}}]
+      })
+    );
+  }
+}
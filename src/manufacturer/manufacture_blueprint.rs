@@ -1,14 +1,12 @@
 use super::{
-  AlignToBlueprint, MarkerBlueprint, SizeBlueprint, SpriteBlueprint,
-  XYBlueprint,
+  AlignToBlueprint, CollisionBlueprint, MarkerBlueprint, R16Blueprint,
+  SizeBlueprint, SpriteBlueprint, XYBlueprint,
 };
 use crate::atlas::{Animator, Atlas};
-use crate::components::AlignTo;
+use crate::components::{AlignTo, Collision};
 use crate::math::{R16, XY, XY16};
 use crate::sprites::{Sprite, SpriteComposition, SpriteLayer};
-use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::hash::Hash;
 use std::num::NonZeroI16;
 
 /// Unfortunately, due to the deep patching of components, type mapping with
@@ -22,39 +20,18 @@ pub trait ManufactureAtlasBlueprint<T> {
   fn manufacture(&self, atlas: &Atlas) -> T;
 }
 
+/// The target is unset. The Manufacturer resolves it once the tree is built.
 impl ManufactureBlueprint<Option<AlignTo>> for Option<AlignToBlueprint> {
   fn manufacture(&self) -> Option<AlignTo> {
     if let Some(blueprint) = self {
       let margin = blueprint.margin.manufacture().unwrap_or(XY::new(0, 0));
-      Some(AlignTo::new(blueprint.alignment, margin, blueprint.to.clone()))
+      Some(AlignTo::new(blueprint.alignment, margin, None))
     } else {
       None
     }
   }
 }
 
-impl<'a, T: Hash + Eq + Clone>
-  ManufactureAtlasBlueprint<Option<HashMap<T, Vec<Sprite>>>>
-  for HashMap<T, Vec<SpriteBlueprint>>
-{
-  fn manufacture(&self, atlas: &Atlas) -> Option<HashMap<T, Vec<Sprite>>> {
-    let mut sprite_map = HashMap::new();
-    for (state, blueprints) in self.iter() {
-      // this is inflating all the states which seems bad <-- this needs to be runtime... how can runtime and manufacturer work together? i need a lut for this.
-      let sprites: Vec<_> = blueprints
-        .iter()
-        .map(|component| component.manufacture(atlas))
-        .collect();
-      sprite_map.insert(state.clone(), sprites);
-    }
-    if sprite_map.is_empty() {
-      None
-    } else {
-      Some(sprite_map)
-    }
-  }
-}
-
 impl ManufactureBlueprint<Option<()>> for Option<MarkerBlueprint> {
   fn manufacture(&self) -> Option<()> {
     if self.is_some() {
@@ -99,16 +76,9 @@ impl<'a> ManufactureAtlasBlueprint<Sprite> for SpriteBlueprint {
       |size| XY::new(size.w.unwrap_or(0), size.h.unwrap_or(0)),
     ))
     .expect("XY<u16> to XY16 conversion failed."); //use atlas, review ts
-    let bounds = self.bounds.clone().map_or(
+    let bounds = self.bounds.as_ref().map_or(
       R16::new_size(position.x, position.y, size.x, size.y),
-      |bounds| {
-        R16::new_size(
-          bounds.x.unwrap_or(0),
-          bounds.y.unwrap_or(0),
-          bounds.w.unwrap_or(0),
-          bounds.h.unwrap_or(0),
-        )
-      },
+      ManufactureBlueprint::manufacture,
     );
 
     let layer = self.layer.unwrap_or(SpriteLayer::Default);
@@ -144,10 +114,40 @@ impl<'a> ManufactureAtlasBlueprint<Sprite> for SpriteBlueprint {
       wrap_velocity,
       layer,
       animator,
+      self.palette.clone(),
     )
   }
 }
 
+impl ManufactureBlueprint<R16> for R16Blueprint {
+  fn manufacture(&self) -> R16 {
+    R16::new_size(
+      self.x.unwrap_or(0),
+      self.y.unwrap_or(0),
+      self.w.unwrap_or(0),
+      self.h.unwrap_or(0),
+    )
+  }
+}
+
+/// Slice bodies are taken from the first Cel of each Animation. The Animations
+/// must be in the atlas.
+impl ManufactureAtlasBlueprint<Option<Collision>>
+  for Option<CollisionBlueprint>
+{
+  fn manufacture(&self, atlas: &Atlas) -> Option<Collision> {
+    let blueprint = self.as_ref()?;
+    let mut bodies: Vec<R16> =
+      blueprint.bodies.iter().map(ManufactureBlueprint::manufacture).collect();
+    for id in &blueprint.slices {
+      if let Some(cel) = atlas.animations[id].cels.first() {
+        bodies.extend(cel.slices.iter().cloned());
+      }
+    }
+    Some(Collision { bodies, cel_bodies: vec![] })
+  }
+}
+
 impl ManufactureBlueprint<Option<String>> for Option<String> {
   fn manufacture(&self) -> Option<String> {
     if let Some(blueprint) = self {
@@ -1,35 +1,219 @@
 use super::BlueprintID;
 use super::{
-  Blueprint, ManufactureAtlasBlueprint, ManufactureBlueprint, PatchBlueprint,
+  child_name, AlignTarget, Blueprint, ComponentBlueprints, ComponentKind,
+  ComponentRegistry, ManufactureAtlasBlueprint, ManufactureBlueprint,
+  SpriteBlueprint, VariantBlueprint,
 };
-use crate::atlas::Atlas;
+use crate::atlas::{AnimationID, Atlas};
 use crate::components::{
-  Cam, Children, FollowMouse, MaxSize, Parent, Position, Renderable, Text,
-  Velocity,
+  AlignTo, Cam, Children, FollowMouse, Manufactured, MaxSize, Parent, Position,
+  Renderable, Tags, Text, Velocity,
 };
+use crate::math::Random;
+use crate::sprites::{SpriteBlueprints, SpriteMap};
 use specs::world::Builder;
 use specs::world::Entity;
 use specs::world::WorldExt;
-use specs::World;
+use specs::{Entities, Join, ReadStorage, World, WriteStorage};
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter;
 use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct Manufacturer {
   blueprints: HashMap<BlueprintID, Blueprint>,
+  /// The sprites of each definition Blueprint variant, or the sprites alone
+  /// without variants, shared by every Renderable manufactured from it.
+  sprites: HashMap<BlueprintID, Vec<SpriteBlueprints>>,
   atlas: Rc<Atlas>,
+  registry: ComponentRegistry,
+}
+
+/// A Blueprint graph defect. Each chain is the IDs from a definition Blueprint
+/// through its (possibly inherited) children to the offending one.
+#[derive(Debug, PartialEq)]
+pub enum ManufacturerError {
+  /// The manufactured ID has no definition Blueprint.
+  Undefined(BlueprintID),
+  /// A child references one of its ancestors. The chain ends with the repeat.
+  Cycle(Vec<BlueprintID>),
+  /// A child references an ID without a definition Blueprint.
+  DanglingChild(Vec<BlueprintID>),
+  /// A sprite references an AnimationID missing from the Atlas.
+  UnknownAnimation(Vec<BlueprintID>, AnimationID),
+  /// A sprite palette row is outside the target palette.
+  PaletteRow(Vec<BlueprintID>, AnimationID, u16),
+  /// A component or removal directive references an unregistered key.
+  UnknownComponent(Vec<BlueprintID>, String),
+  /// A registered component doesn't deserialize. The error is serde's.
+  InvalidComponent(Vec<BlueprintID>, String, String),
+  /// An AlignTo target doesn't resolve to an Entity. Parent and sibling
+  /// targets of children are checked when validated, the rest only when
+  /// manufactured.
+  UnresolvedTarget(Vec<BlueprintID>, AlignTarget),
+}
+
+impl fmt::Display for ManufacturerError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Undefined(id) => write!(formatter, "Blueprint undefined: {}.", id),
+      Self::Cycle(chain) => {
+        write!(formatter, "Blueprint cycle: {}.", format_chain(chain))
+      }
+      Self::DanglingChild(chain) => {
+        write!(formatter, "Blueprint child undefined: {}.", format_chain(chain))
+      }
+      Self::UnknownAnimation(chain, id) => write!(
+        formatter,
+        "Blueprint animation unknown: {} references {}.",
+        format_chain(chain),
+        id
+      ),
+      Self::PaletteRow(chain, id, row) => write!(
+        formatter,
+        "Blueprint palette row out of range: {} references row {} of {}.",
+        format_chain(chain),
+        row,
+        id
+      ),
+      Self::UnknownComponent(chain, key) => write!(
+        formatter,
+        "Blueprint component unknown: {} references {}.",
+        format_chain(chain),
+        key
+      ),
+      Self::InvalidComponent(chain, key, error) => write!(
+        formatter,
+        "Blueprint component invalid: {} {}: {}",
+        format_chain(chain),
+        key,
+        error
+      ),
+      Self::UnresolvedTarget(chain, target) => write!(
+        formatter,
+        "Blueprint alignment unresolved: {} targets {:?}.",
+        format_chain(chain),
+        target
+      ),
+    }
+  }
+}
+
+fn format_chain(chain: &[BlueprintID]) -> String {
+  let ids: Vec<_> = chain.iter().map(BlueprintID::to_string).collect();
+  ids.join(" -> ")
 }
 
 impl Manufacturer {
+  /// Fails if the Blueprint graph is invalid. See `validate()`.
   pub fn new(
     blueprints: HashMap<BlueprintID, Blueprint>,
     atlas: Rc<Atlas>,
-  ) -> Self {
-    Self { blueprints, atlas }
+    registry: ComponentRegistry,
+  ) -> Result<Self, ManufacturerError> {
+    Self::validate(&blueprints, &atlas, &registry)?;
+    let sprites = blueprints
+      .iter()
+      .map(|(id, blueprint)| {
+        let components = &blueprint.components;
+        let variants = (0..components.variants.len().max(1))
+          .map(|variant| Arc::new(variant_sprites(components, variant)))
+          .collect();
+        (*id, variants)
+      })
+      .collect();
+    Ok(Self { blueprints, sprites, atlas, registry })
   }
 
-  /// Returns the root Entity.
-  pub fn manufacture(&self, ecs: &mut World, id: BlueprintID) -> Entity {
-    self.manufacture_blueprint(ecs, &self.blueprints[&id])
+  /// Checks every definition Blueprint and each child as it would be patched
+  /// and manufactured for cycles, undefined child IDs, sprite AnimationIDs
+  /// missing from the Atlas, unregistered or invalid registered components,
+  /// and parent or sibling AlignTo targets of children that don't resolve.
+  /// Definitions are checked in ID order so the error reported is stable.
+  pub fn validate(
+    blueprints: &HashMap<BlueprintID, Blueprint>,
+    atlas: &Atlas,
+    registry: &ComponentRegistry,
+  ) -> Result<(), ManufacturerError> {
+    let mut ids: Vec<_> = blueprints.keys().collect();
+    ids.sort();
+    for id in ids {
+      let validator = Validator { blueprints, atlas, registry };
+      validator.validate(&mut vec![*id], &blueprints[id])?;
+    }
+    Ok(())
+  }
+
+  /// Checks a patch of a definition Blueprint, such as one placed in a level,
+  /// as `validate()` checks children.
+  pub fn validate_patch(
+    blueprints: &HashMap<BlueprintID, Blueprint>,
+    atlas: &Atlas,
+    registry: &ComponentRegistry,
+    patch: &Blueprint,
+  ) -> Result<(), ManufacturerError> {
+    let validator = Validator { blueprints, atlas, registry };
+    validator.validate_child(&mut vec![], patch, &[])
+  }
+
+  /// Registers every registered component with the World.
+  pub fn register(&self, ecs: &mut World) {
+    self.registry.register(ecs);
+  }
+
+  pub fn get_blueprint(&self, id: BlueprintID) -> Option<&Blueprint> {
+    self.blueprints.get(&id)
+  }
+
+  pub fn get_registry(&self) -> &ComponentRegistry {
+    &self.registry
+  }
+
+  /// Returns the root Entity. Variants are picked with the World's Random
+  /// resource which must be present if any Blueprint in the tree has
+  /// variants. Fails if the ID is undefined or if an AlignTo target doesn't
+  /// resolve in which case nothing is manufactured.
+  pub fn manufacture(
+    &self,
+    ecs: &mut World,
+    id: BlueprintID,
+  ) -> Result<Entity, ManufacturerError> {
+    let blueprint =
+      self.blueprints.get(&id).ok_or(ManufacturerError::Undefined(id))?;
+    let variant = pick_variant(ecs, &blueprint.components.variants);
+    let mut links = Links::default();
+    let entity = self.manufacture_blueprint(
+      ecs,
+      blueprint,
+      &self.sprites[&id][variant],
+      &mut links,
+    );
+    links.resolve(ecs)?;
+    mark_manufactured(ecs, entity, id);
+    Ok(entity)
+  }
+
+  /// Manufactures a patch of a definition Blueprint. Unlike definitions, patches
+  /// aren't validated at construction so they're validated here. Returns the
+  /// root Entity. Fails as `manufacture()` does too.
+  pub fn manufacture_patch(
+    &self,
+    ecs: &mut World,
+    patch: &Blueprint,
+  ) -> Result<Entity, ManufacturerError> {
+    Self::validate_patch(&self.blueprints, &self.atlas, &self.registry, patch)?;
+    let patched =
+      self.blueprints[&patch.id].patch_definition(patch, &self.blueprints);
+    let variant = pick_variant(ecs, &patched.components.variants);
+    let sprites = self.patch_sprites(patch, &patched, variant);
+    let mut links = Links::default();
+    let entity =
+      self.manufacture_blueprint(ecs, &patched, &sprites, &mut links);
+    links.resolve(ecs)?;
+    mark_manufactured(ecs, entity, patch.id);
+    Ok(entity)
   }
 
   /// This is not parsing. This is processing an existing Blueprint (and all
@@ -37,13 +221,18 @@ impl Manufacturer {
   /// Entitys. Entitys are just identifiers that are associated with Components.
   /// All of these Components and identifiers are injected directly into the
   /// World.
+  /// `sprites` must equal the Blueprint's sprites. AlignTo targets are added
+  /// to `links` for the caller to resolve once the whole tree is built.
   /// Returns the root Entity.
   fn manufacture_blueprint(
     &self,
     ecs: &mut World,
     blueprint: &Blueprint,
+    sprites: &SpriteBlueprints,
+    links: &mut Links,
   ) -> Entity {
     let components = &blueprint.components;
+    links.chain.push(blueprint.id);
 
     let mut entity = ecs.create_entity();
     if let Some(component) = &components.parent {
@@ -61,6 +250,12 @@ impl Manufacturer {
     if let Some(component) = components.cam.manufacture() {
       entity = entity.with(Cam { size: component });
     }
+    if let Some(component) = components.collision.manufacture(&self.atlas) {
+      entity = entity.with(component);
+    }
+    if let Some(component) = components.collides_with {
+      entity = entity.with(component);
+    }
     if let Some(component) = components.max_wh.manufacture() {
       // [todo] use NonZeroI16? Flesh out NonZeroI16 ops?
       entity = entity.with(MaxSize { size: component });
@@ -74,17 +269,42 @@ impl Manufacturer {
     if let Some(component) = components.text.manufacture() {
       entity = entity.with(Text { text: component });
     }
-    if let Some(component) = components.sprites.manufacture(&self.atlas) {
+    if !components.tags.is_empty() {
+      entity = entity.with(Tags { tags: components.tags.clone() });
+    }
+    if !sprites.is_empty() {
       // todo: move by position
-      entity = entity.with(Renderable { sprites: component });
+      let sprites = SpriteMap::new(sprites.clone(), &self.atlas);
+      entity = entity.with(Renderable { sprites });
     }
     let entity = entity.build();
+    links.entities.push(entity);
+    if let Some(target) =
+      components.align_to.as_ref().and_then(|align_to| align_to.to.clone())
+    {
+      links.targets.push((links.chain.clone(), entity, target));
+    }
+
+    // Registered components are valid since the graph was validated.
+    for (key, layers) in &components.registered {
+      self
+        .registry
+        .manufacture(ecs, entity, key, layers)
+        .expect("Registered component not inserted.");
+    }
 
+    // Children are always defined and acyclic since the graph was validated.
     let mut children = vec![];
     for child in &blueprint.children {
-      let mut patched = self.blueprints[&child.id].patch(child);
+      let mut patched =
+        self.blueprints[&child.id].patch_definition(child, &self.blueprints);
       patched.components.parent = Some(Parent { parent: entity.clone() });
-      let child = self.manufacture_blueprint(ecs, &patched);
+      let variant = pick_variant(ecs, &patched.components.variants);
+      let sprites = self.patch_sprites(child, &patched, variant);
+      let child = self.manufacture_blueprint(ecs, &patched, &sprites, links);
+      if let Some(name) = patched.name {
+        links.names.insert(child, name);
+      }
       children.push(child);
     }
 
@@ -96,17 +316,292 @@ impl Manufacturer {
         .expect("Children component not inserted.");
     }
 
+    links.chain.pop();
     entity
   }
+
+  /// Returns the sprites of the variant of `patched`, the definition of
+  /// `patch` patched. The definition's sprites are only shared when the patch
+  /// doesn't alter them or its variants.
+  fn patch_sprites(
+    &self,
+    patch: &Blueprint,
+    patched: &Blueprint,
+    variant: usize,
+  ) -> SpriteBlueprints {
+    if patch.components.sprites.is_empty()
+      && patch.components.variants.is_empty()
+      && patch.replace_sprites.is_empty()
+      && !patch.remove.contains(&ComponentKind::Sprites)
+      && !patch.remove.contains(&ComponentKind::Variants)
+    {
+      self.sprites[&patch.id][variant].clone()
+    } else {
+      Arc::new(variant_sprites(&patched.components, variant))
+    }
+  }
+}
+
+/// Returns the sprites with the states of the variant, if any, replacing
+/// their own.
+fn variant_sprites(
+  components: &ComponentBlueprints,
+  variant: usize,
+) -> HashMap<String, Vec<SpriteBlueprint>> {
+  let mut sprites = components.sprites.clone();
+  if let Some(variant) = components.variants.get(variant) {
+    sprites.extend(variant.sprites.clone());
+  }
+  sprites
+}
+
+/// Returns the index of a variant picked by weight with the World's Random, or
+/// zero when there are none.
+fn pick_variant(ecs: &World, variants: &[VariantBlueprint]) -> usize {
+  fn weight(variant: &VariantBlueprint) -> u32 {
+    variant.weight.map_or(1, |weight| weight.get().into())
+  }
+  if variants.is_empty() {
+    return 0;
+  }
+  let total: u32 = variants.iter().map(weight).sum();
+  let mut roll =
+    (ecs.write_resource::<Random>().float() * f64::from(total)) as u32;
+  for (i, variant) in variants.iter().enumerate() {
+    if roll < weight(variant) {
+      return i;
+    }
+    roll -= weight(variant);
+  }
+  variants.len() - 1
+}
+
+/// Children are manufactured from their root's Blueprint so only roots are
+/// marked.
+fn mark_manufactured(ecs: &mut World, entity: Entity, id: BlueprintID) {
+  ecs
+    .write_storage::<Manufactured>()
+    .insert(entity, Manufactured { id })
+    .expect("Manufactured component not inserted.");
+}
+
+/// The AlignTo targets of a Blueprint tree being manufactured.
+#[derive(Default)]
+struct Links {
+  /// The IDs from the root to the Blueprint being manufactured.
+  chain: Vec<BlueprintID>,
+  /// Every Entity manufactured in the tree.
+  entities: Vec<Entity>,
+  /// The names of named children.
+  names: HashMap<Entity, String>,
+  /// The chain, Entity, and target of every AlignTo with a target.
+  targets: Vec<(Vec<BlueprintID>, Entity, AlignTarget)>,
+}
+
+impl Links {
+  /// Links every AlignTo to its target. The tree is deleted if any target
+  /// doesn't resolve.
+  fn resolve(self, ecs: &mut World) -> Result<(), ManufacturerError> {
+    if self.targets.is_empty() {
+      return Ok(());
+    }
+    let result = self.link(ecs);
+    if result.is_err() {
+      ecs.delete_entities(&self.entities).expect("Entitys not deleted.");
+    }
+    result
+  }
+
+  fn link(&self, ecs: &World) -> Result<(), ManufacturerError> {
+    let (entities, parents, children, tags, mut align_tos) = ecs
+      .system_data::<(
+        Entities,
+        ReadStorage<Parent>,
+        ReadStorage<Children>,
+        ReadStorage<Tags>,
+        WriteStorage<AlignTo>,
+      )>();
+    for (chain, entity, target) in &self.targets {
+      let parent = parents.get(*entity).map(|parent| parent.parent);
+      let to = match target {
+        AlignTarget::Parent => parent,
+        AlignTarget::Camera => {
+          align_tos
+            .get_mut(*entity)
+            .expect("AlignTo component missing.")
+            .set_to_cam();
+          continue;
+        }
+        AlignTarget::Sibling(name) => {
+          parent.and_then(|parent| children.get(parent)).and_then(|children| {
+            children
+              .children
+              .iter()
+              .find(|child| self.names.get(child) == Some(name))
+              .cloned()
+          })
+        }
+        AlignTarget::Tag(tag) => (&entities, &tags)
+          .join()
+          .find(|(_, tags)| tags.tags.contains(tag))
+          .map(|(entity, _)| entity),
+      }
+      .ok_or_else(|| {
+        ManufacturerError::UnresolvedTarget(chain.clone(), target.clone())
+      })?;
+      align_tos
+        .get_mut(*entity)
+        .expect("AlignTo component missing.")
+        .set_to(Some(to));
+    }
+    Ok(())
+  }
+}
+
+struct Validator<'a> {
+  blueprints: &'a HashMap<BlueprintID, Blueprint>,
+  atlas: &'a Atlas,
+  registry: &'a ComponentRegistry,
+}
+
+impl<'a> Validator<'a> {
+  /// `chain` ends with the ID of `blueprint`, which is either a definition or
+  /// already patched.
+  fn validate(
+    &self,
+    chain: &mut Vec<BlueprintID>,
+    blueprint: &Blueprint,
+  ) -> Result<(), ManufacturerError> {
+    let components = &blueprint.components;
+    let variants = components.variants.iter().map(|variant| &variant.sprites);
+    for sprites in
+      iter::once(&components.sprites).chain(variants).flat_map(HashMap::values)
+    {
+      for sprite in sprites {
+        let mut ids = vec![sprite.id];
+        ids.extend(sprite.constituent_id);
+        if let Some(palette) = &sprite.palette {
+          ids.extend(&[palette.source, palette.target]);
+        }
+        if let Some(id) =
+          ids.into_iter().find(|id| !self.atlas.animations.contains_key(id))
+        {
+          return Err(ManufacturerError::UnknownAnimation(chain.clone(), id));
+        }
+        if let Some(palette) = &sprite.palette {
+          if i16::try_from(palette.row)
+            .map_or(true, |row| row >= palette.target_rows(self.atlas))
+          {
+            return Err(ManufacturerError::PaletteRow(
+              chain.clone(),
+              palette.target,
+              palette.row,
+            ));
+          }
+        }
+      }
+    }
+    if let Some(collision) = &components.collision {
+      if let Some(id) = collision
+        .slices
+        .iter()
+        .find(|id| !self.atlas.animations.contains_key(id))
+      {
+        return Err(ManufacturerError::UnknownAnimation(chain.clone(), *id));
+      }
+    }
+
+    for (key, layers) in &blueprint.components.registered {
+      if !self.registry.contains(key) {
+        return Err(ManufacturerError::UnknownComponent(
+          chain.clone(),
+          key.clone(),
+        ));
+      }
+      self.registry.validate(key, layers).map_err(|error| {
+        ManufacturerError::InvalidComponent(chain.clone(), key.clone(), error)
+      })?;
+    }
+
+    for child in &blueprint.children {
+      self.validate_child(chain, child, &blueprint.children)?;
+    }
+    Ok(())
+  }
+
+  /// `chain` ends with the ID of the parent of `patch`, if any, and `siblings`
+  /// are the parent's children, including `patch`.
+  fn validate_child(
+    &self,
+    chain: &mut Vec<BlueprintID>,
+    patch: &Blueprint,
+    siblings: &[Blueprint],
+  ) -> Result<(), ManufacturerError> {
+    chain.push(patch.id);
+    if chain[..chain.len() - 1].contains(&patch.id) {
+      return Err(ManufacturerError::Cycle(chain.clone()));
+    }
+    let definition = self
+      .blueprints
+      .get(&patch.id)
+      .ok_or_else(|| ManufacturerError::DanglingChild(chain.clone()))?;
+    for kind in &patch.remove {
+      if let ComponentKind::Registered(key) = kind {
+        if !self.registry.contains(key) {
+          return Err(ManufacturerError::UnknownComponent(
+            chain.clone(),
+            key.clone(),
+          ));
+        }
+      }
+    }
+    let patched = definition.patch_definition(patch, &self.blueprints);
+    if let Some(target) = patched
+      .components
+      .align_to
+      .as_ref()
+      .and_then(|align_to| align_to.to.as_ref())
+    {
+      let resolves = match target {
+        AlignTarget::Parent => chain.len() > 1,
+        AlignTarget::Sibling(name) => {
+          siblings.iter().any(|sibling| self.name(sibling) == Some(name))
+        }
+        AlignTarget::Camera | AlignTarget::Tag(_) => true,
+      };
+      if !resolves {
+        return Err(ManufacturerError::UnresolvedTarget(
+          chain.clone(),
+          target.clone(),
+        ));
+      }
+    }
+    self.validate(chain, &patched)?;
+    chain.pop();
+    Ok(())
+  }
+
+  /// Returns the name of the child as patched.
+  fn name<'b>(&'b self, child: &'b Blueprint) -> Option<&'b String> {
+    child_name(child, &self.blueprints)
+  }
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
-  use crate::components::{FollowMouse, Position, Velocity};
-  use crate::math::{XY, XY16};
+  use crate::atlas::{self, Animation, Playback};
+  use crate::components::{
+    component_registry, CollidesWith, Collision, Cursor, CursorIcon,
+    CursorMode, FollowMouse, Position, Velocity,
+  };
+  use crate::manufacturer::parse_blueprints;
+  use crate::math::{R16, XY, XY16};
+  use crate::sprites::Sprite;
   use specs::join::Join;
-  use specs::ReadStorage;
+  use specs::prelude::DenseVecStorage;
+  use specs::{Component, ReadStorage};
+  use std::time::{Duration, Instant};
 
   #[test]
   fn manufacture_empty() {
@@ -119,15 +614,42 @@ mod test {
       size: XY::new(0, 0),
       animations: HashMap::new(),
     };
-    let manufacturer = Manufacturer::new(blueprints, Rc::new(atlas));
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
     let mut ecs = World::new();
+    ecs.register::<Manufactured>();
 
-    manufacturer.manufacture(&mut ecs, BlueprintID::Bee);
+    manufacturer.manufacture(&mut ecs, BlueprintID::Bee).unwrap();
 
     let entities: Vec<Entity> = (&ecs.entities()).join().collect();
     assert_eq!(entities.len(), 1);
   }
 
+  #[test]
+  fn manufacture_undefined() {
+    let atlas = Atlas {
+      version: String::new(),
+      filename: String::new(),
+      format: String::new(),
+      size: XY::new(0, 0),
+      animations: HashMap::new(),
+    };
+    let manufacturer = Manufacturer::new(
+      HashMap::new(),
+      Rc::new(atlas),
+      ComponentRegistry::new(),
+    )
+    .unwrap();
+    let mut ecs = World::new();
+
+    assert_eq!(
+      manufacturer.manufacture(&mut ecs, BlueprintID::Bee).err(),
+      Some(ManufacturerError::Undefined(BlueprintID::Bee))
+    );
+    assert_eq!((&ecs.entities()).join().count(), 0);
+  }
+
   #[test]
   fn manufacture_components() {
     let mut blueprints = HashMap::new();
@@ -149,12 +671,15 @@ mod test {
       size: XY::new(0, 0),
       animations: HashMap::new(),
     };
-    let manufacturer = Manufacturer::new(blueprints, Rc::new(atlas));
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
     let mut ecs = World::new();
+    ecs.register::<Manufactured>();
     ecs.register::<Position>();
     ecs.register::<Velocity>();
 
-    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::Bee);
+    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::Bee).unwrap();
 
     let position = ecs.read_storage::<Position>();
     let position = position.get(entity).unwrap();
@@ -184,11 +709,14 @@ mod test {
       size: XY::new(0, 0),
       animations: HashMap::new(),
     };
-    let manufacturer = Manufacturer::new(blueprints, Rc::new(atlas));
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
     let mut ecs = World::new();
+    ecs.register::<Manufactured>();
     ecs.register::<FollowMouse>();
 
-    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::Bee);
+    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::Bee).unwrap();
 
     let follow_mouse = ecs.read_storage::<FollowMouse>();
     assert_eq!(follow_mouse.get(entity).is_some(), true);
@@ -229,14 +757,18 @@ mod test {
       size: XY::new(0, 0),
       animations: HashMap::new(),
     };
-    let manufacturer = Manufacturer::new(blueprints, Rc::new(atlas));
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
     let mut ecs = World::new();
+    ecs.register::<Manufactured>();
     ecs.register::<Parent>();
     ecs.register::<Children>();
     ecs.register::<Position>();
     ecs.register::<Velocity>();
 
-    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog);
+    let entity =
+      manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog).unwrap();
 
     let entities: Vec<Entity> = (&ecs.entities()).join().collect();
     assert_eq!(entities.len(), 4);
@@ -304,8 +836,11 @@ mod test {
       size: XY::new(0, 0),
       animations: HashMap::new(),
     };
-    let manufacturer = Manufacturer::new(blueprints, Rc::new(atlas));
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
     let mut ecs = World::new();
+    ecs.register::<Manufactured>();
     ecs.register::<Parent>();
     ecs.register::<Children>();
     ecs.register::<Position>();
@@ -313,8 +848,9 @@ mod test {
     ecs.register::<FollowMouse>();
 
     let save_dialog_entity =
-      manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog);
-    let map_entity = manufacturer.manufacture(&mut ecs, BlueprintID::Map);
+      manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog).unwrap();
+    let map_entity =
+      manufacturer.manufacture(&mut ecs, BlueprintID::Map).unwrap();
 
     let entities: Vec<Entity> = (&ecs.entities()).join().collect();
     assert_eq!(entities.len(), 4);
@@ -348,4 +884,612 @@ mod test {
       },
     );
   }
+
+  #[test]
+  fn manufacture_registered_components() {
+    let mut blueprints = HashMap::new();
+    blueprints.insert(
+      BlueprintID::SaveDialog,
+      from_json!({
+        "id": "SaveDialog",
+        "children": [
+          {"id": "Button", "components": {"cursor": {"icon": "Reticle"}}},
+          {"id": "Button", "remove": ["cursor"]},
+          {"id": "Button", "remove": ["cursor"], "components": {"cursor": {}}}
+        ]
+      })
+      .unwrap(),
+    );
+    blueprints.insert(
+      BlueprintID::Button,
+      from_json!({
+        "id": "Button",
+        "components": {"cursor": {"mode": "Point", "icon": "Hand"}}
+      })
+      .unwrap(),
+    );
+    let atlas = Atlas {
+      version: String::new(),
+      filename: String::new(),
+      format: String::new(),
+      size: XY::new(0, 0),
+      animations: HashMap::new(),
+    };
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), component_registry())
+        .unwrap();
+    let mut ecs = World::new();
+    ecs.register::<Manufactured>();
+    ecs.register::<Parent>();
+    ecs.register::<Children>();
+    manufacturer.register(&mut ecs);
+
+    let entity =
+      manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog).unwrap();
+
+    let children = ecs.read_storage::<Children>();
+    let cursors = ecs.read_storage::<Cursor>();
+    let cursors: Vec<_> = children
+      .get(entity)
+      .unwrap()
+      .children
+      .iter()
+      .map(|child| cursors.get(*child).map(|cursor| (cursor.mode, cursor.icon)))
+      .collect();
+    assert_eq!(
+      cursors,
+      vec![
+        Some((CursorMode::Point, CursorIcon::Reticle)),
+        None,
+        Some((CursorMode::Hidden, CursorIcon::Dot))
+      ]
+    );
+  }
+
+  #[test]
+  fn manufacture_align_targets() {
+    let mut blueprints = HashMap::new();
+    blueprints.insert(
+      BlueprintID::SaveDialog,
+      from_json!({
+        "id": "SaveDialog",
+        "components": {"tags": ["dialog"]},
+        "children": [
+          {"id": "Button", "name": "ok"},
+          {
+            "id": "Button",
+            "components": {"align_to": {"alignment": "East", "to": "Parent"}}
+          },
+          {
+            "id": "Button",
+            "components": {
+              "align_to": {"alignment": "East", "to": {"Sibling": "ok"}}
+            }
+          },
+          {
+            "id": "Button",
+            "components": {"align_to": {"alignment": "East", "to": "Camera"}}
+          },
+          {
+            "id": "Button",
+            "components": {
+              "align_to": {"alignment": "East", "to": {"Tag": "dialog"}}
+            }
+          }
+        ]
+      })
+      .unwrap(),
+    );
+    blueprints.insert(
+      BlueprintID::Button,
+      from_json!({
+        "id": "Button",
+        "components": {"align_to": {"alignment": "Center"}}
+      })
+      .unwrap(),
+    );
+    let atlas = Atlas {
+      version: String::new(),
+      filename: String::new(),
+      format: String::new(),
+      size: XY::new(0, 0),
+      animations: HashMap::new(),
+    };
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
+    let mut ecs = World::new();
+    ecs.register::<Manufactured>();
+    ecs.register::<Parent>();
+    ecs.register::<Children>();
+    ecs.register::<AlignTo>();
+    ecs.register::<Cam>();
+    ecs.register::<Tags>();
+
+    let entity =
+      manufacturer.manufacture(&mut ecs, BlueprintID::SaveDialog).unwrap();
+
+    let children = ecs.read_storage::<Children>();
+    let children = &children.get(entity).unwrap().children;
+    let align_tos = ecs.read_storage::<AlignTo>();
+    let targets: Vec<_> = children
+      .iter()
+      .map(|child| {
+        let align_to = align_tos.get(*child).unwrap();
+        (align_to.get_to(), align_to.is_to_cam())
+      })
+      .collect();
+    assert_eq!(
+      targets,
+      vec![
+        (None, false),
+        (Some(entity), false),
+        (Some(children[0]), false),
+        (None, true),
+        (Some(entity), false)
+      ]
+    );
+  }
+
+  #[test]
+  fn manufacture_unresolved_target() {
+    let mut blueprints = HashMap::new();
+    blueprints.insert(
+      BlueprintID::Button,
+      from_json!({
+        "id": "Button",
+        "components": {
+          "align_to": {"alignment": "Center", "to": {"Tag": "dialog"}}
+        }
+      })
+      .unwrap(),
+    );
+    let atlas = Atlas {
+      version: String::new(),
+      filename: String::new(),
+      format: String::new(),
+      size: XY::new(0, 0),
+      animations: HashMap::new(),
+    };
+    let manufacturer =
+      Manufacturer::new(blueprints, Rc::new(atlas), ComponentRegistry::new())
+        .unwrap();
+    let mut ecs = World::new();
+    ecs.register::<Manufactured>();
+    ecs.register::<Parent>();
+    ecs.register::<Children>();
+    ecs.register::<AlignTo>();
+    ecs.register::<Cam>();
+    ecs.register::<Tags>();
+
+    let result = manufacturer.manufacture(&mut ecs, BlueprintID::Button);
+
+    assert_eq!(
+      result.err(),
+      Some(ManufacturerError::UnresolvedTarget(
+        vec![BlueprintID::Button],
+        AlignTarget::Tag("dialog".to_string())
+      ))
+    );
+    assert_eq!((&ecs.entities()).join().count(), 0);
+  }
+
+  #[test]
+  fn manufacture_variants() {
+    let atlas = Rc::new(
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap(),
+    );
+    let mut blueprints = HashMap::new();
+    blueprints.insert(
+      BlueprintID::Bee,
+      from_json!({
+        "id": "Bee",
+        "components": {
+          "sprites": {"Default": [{"id": "Bee"}]},
+          "variants": [
+            {},
+            {"weight": 2, "sprites": {"Default": [{"id": "BeeDead"}]}},
+            {"sprites": {"Default": [{"id": "BeeShadow"}]}}
+          ]
+        }
+      })
+      .unwrap(),
+    );
+    let manufacturer =
+      Manufacturer::new(blueprints, atlas, ComponentRegistry::new()).unwrap();
+    let mut ecs = World::new();
+    ecs.register::<Manufactured>();
+    ecs.register::<Renderable>();
+    ecs.insert(Random::new(1));
+
+    let ids: Vec<_> = (0..6)
+      .map(|_| {
+        let entity =
+          manufacturer.manufacture(&mut ecs, BlueprintID::Bee).unwrap();
+        let renderables = ecs.read_storage::<Renderable>();
+        let sprites = renderables.get(entity).unwrap().sprites.get_sprites();
+        sprites.iter().map(Sprite::get_id).collect::<Vec<_>>()
+      })
+      .collect();
+
+    assert_eq!(
+      ids,
+      vec![
+        vec![AnimationID::Bee],
+        vec![AnimationID::Bee],
+        vec![AnimationID::BeeShadow],
+        vec![AnimationID::BeeDead],
+        vec![AnimationID::BeeDead],
+        vec![AnimationID::Bee]
+      ]
+    );
+  }
+
+  #[test]
+  fn manufacture_collision() {
+    let atlas = Rc::new(
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap(),
+    );
+    let mut blueprints = HashMap::new();
+    blueprints.insert(
+      BlueprintID::Tree,
+      from_json!({
+        "id": "Tree",
+        "components": {
+          "collision": {"bodies": [{"x": 1, "w": 2}], "slices": ["TreeSmall"]},
+          "collides_with": ["Obstacle", "TypeScenery"]
+        }
+      })
+      .unwrap(),
+    );
+    let manufacturer =
+      Manufacturer::new(blueprints, atlas, ComponentRegistry::new()).unwrap();
+    let mut ecs = World::new();
+    ecs.register::<Manufactured>();
+    ecs.register::<Collision>();
+    ecs.register::<CollidesWith>();
+
+    let entity = manufacturer.manufacture(&mut ecs, BlueprintID::Tree).unwrap();
+
+    assert_eq!(
+      ecs.read_storage::<Collision>().get(entity).unwrap().bodies,
+      vec![R16::new_size(1, 0, 2, 0), R16::new_size(3, 8, 4, 3)]
+    );
+    assert_eq!(
+      ecs.read_storage::<CollidesWith>().get(entity),
+      Some(&(CollidesWith::OBSTACLE | CollidesWith::TYPE_SCENERY))
+    );
+  }
+
+  #[test]
+  fn validate_graph() {
+    [
+      (
+        vec![from_json!({"id": "Group", "children": [{"id": "Group"}]})],
+        Some(ManufacturerError::Cycle(vec![
+          BlueprintID::Group,
+          BlueprintID::Group,
+        ])),
+      ),
+      (
+        vec![
+          from_json!({"id": "Group", "children": [{"id": "Tree"}]}),
+          from_json!({"id": "Tree", "children": [{"id": "Group"}]}),
+        ],
+        Some(ManufacturerError::Cycle(vec![
+          BlueprintID::Group,
+          BlueprintID::Tree,
+          BlueprintID::Group,
+        ])),
+      ),
+      (
+        vec![
+          from_json!({"id": "Group"}),
+          from_json!({
+            "id": "Tree",
+            "children": [{"id": "Group", "children": [{"id": "Tree"}]}]
+          }),
+        ],
+        Some(ManufacturerError::Cycle(vec![
+          BlueprintID::Tree,
+          BlueprintID::Group,
+          BlueprintID::Tree,
+        ])),
+      ),
+      (
+        vec![from_json!({"id": "Group", "children": [{"id": "Tree"}]})],
+        Some(ManufacturerError::DanglingChild(vec![
+          BlueprintID::Group,
+          BlueprintID::Tree,
+        ])),
+      ),
+      (
+        vec![
+          from_json!({"id": "Group", "children": [{"id": "Tree"}]}),
+          from_json!({"id": "Tree"}),
+        ],
+        None,
+      ),
+      (
+        vec![
+          from_json!({
+            "id": "Group",
+            "children": [{
+              "id": "Tree",
+              "components": {
+                "sprites": {"Default": [{"id": "Bee", "constituent_id": "BeeShadow"}]}
+              }
+            }]
+          }),
+          from_json!({"id": "Tree"}),
+        ],
+        Some(ManufacturerError::UnknownAnimation(
+          vec![BlueprintID::Group, BlueprintID::Tree],
+          AnimationID::BeeShadow,
+        )),
+      ),
+      (
+        vec![from_json!({
+          "id": "Group",
+          "components": {
+            "variants": [{"sprites": {"Default": [{"id": "BeeDead"}]}}]
+          }
+        })],
+        Some(ManufacturerError::UnknownAnimation(
+          vec![BlueprintID::Group],
+          AnimationID::BeeDead,
+        )),
+      ),
+      (
+        vec![from_json!({
+          "id": "Group",
+          "components": {
+            "sprites": {
+              "Default": [
+                {"id": "Bee", "palette": {"source": "Bee", "target": "Bee"}}
+              ]
+            }
+          }
+        })],
+        Some(ManufacturerError::PaletteRow(
+          vec![BlueprintID::Group],
+          AnimationID::Bee,
+          0,
+        )),
+      ),
+      (
+        vec![from_json!({
+          "id": "Group",
+          "components": {"collision": {"slices": ["TreeSmall"]}}
+        })],
+        Some(ManufacturerError::UnknownAnimation(
+          vec![BlueprintID::Group],
+          AnimationID::TreeSmall,
+        )),
+      ),
+      (
+        vec![from_json!({"id": "Group", "components": {"nope": {}}})],
+        Some(ManufacturerError::UnknownComponent(
+          vec![BlueprintID::Group],
+          "nope".to_string(),
+        )),
+      ),
+      (
+        vec![from_json!({
+          "id": "Group",
+          "components": {"cursor": {"mode": "Nope"}}
+        })],
+        Some(ManufacturerError::InvalidComponent(
+          vec![BlueprintID::Group],
+          "cursor".to_string(),
+          "unknown variant `Nope`, expected one of `Hidden`, `Point`, `Pick`"
+            .to_string(),
+        )),
+      ),
+      (
+        vec![
+          from_json!({
+            "id": "Group",
+            "children": [{"id": "Tree", "remove": ["nope"]}]
+          }),
+          from_json!({"id": "Tree"}),
+        ],
+        Some(ManufacturerError::UnknownComponent(
+          vec![BlueprintID::Group, BlueprintID::Tree],
+          "nope".to_string(),
+        )),
+      ),
+      (
+        vec![
+          from_json!({
+            "id": "Group",
+            "components": {"cursor": {"icon": "Hand"}},
+            "children": [{"id": "Tree", "components": {"cursor": {}}}]
+          }),
+          from_json!({"id": "Tree", "components": {"cursor": {"mode": "Pick"}}}),
+        ],
+        None,
+      ),
+      (
+        vec![
+          from_json!({
+            "id": "Group",
+            "children": [
+              {"id": "Tree", "name": "oak"},
+              {
+                "id": "Tree",
+                "components": {
+                  "align_to": {"alignment": "North", "to": {"Sibling": "elm"}}
+                }
+              }
+            ]
+          }),
+          from_json!({"id": "Tree"}),
+        ],
+        Some(ManufacturerError::UnresolvedTarget(
+          vec![BlueprintID::Group, BlueprintID::Tree],
+          AlignTarget::Sibling("elm".to_string()),
+        )),
+      ),
+      (
+        vec![
+          from_json!({
+            "id": "Group",
+            "children": [
+              {"id": "Tree", "name": "oak"},
+              {
+                "id": "Tree",
+                "components": {
+                  "align_to": {"alignment": "North", "to": {"Sibling": "oak"}}
+                }
+              },
+              {
+                "id": "Tree",
+                "components": {"align_to": {"alignment": "North", "to": "Parent"}}
+              }
+            ]
+          }),
+          from_json!({"id": "Tree"}),
+        ],
+        None,
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (definitions, expected))| {
+      let blueprints: HashMap<BlueprintID, Blueprint> = definitions
+        .iter()
+        .map(|blueprint: &serde_json::Result<Blueprint>| {
+          let blueprint = blueprint.as_ref().unwrap();
+          (blueprint.id, blueprint.clone())
+        })
+        .collect();
+      let mut animations = HashMap::new();
+      animations.insert(
+        AnimationID::Bee,
+        Animation {
+          size: XY::new(1, 1),
+          cels: vec![],
+          duration: 0.,
+          direction: Playback::Forward,
+          repeat: None,
+        },
+      );
+      let atlas = Atlas {
+        version: String::new(),
+        filename: String::new(),
+        format: String::new(),
+        size: XY::new(0, 0),
+        animations,
+      };
+      assert_eq!(
+        Manufacturer::validate(&blueprints, &atlas, &component_registry())
+          .err(),
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  /// Sprites for every state, as Renderables held before states were inflated
+  /// lazily.
+  #[derive(Component)]
+  struct EagerRenderable {
+    _sprites: HashMap<String, Vec<Sprite>>,
+  }
+
+  /// Manufactures the root as `manufacture()` once did, inflating every sprite
+  /// state up front, for comparison. Children are manufactured as usual.
+  fn manufacture_eager(
+    manufacturer: &Manufacturer,
+    ecs: &mut World,
+    id: BlueprintID,
+  ) -> Entity {
+    let blueprint = &manufacturer.blueprints[&id];
+    let sprites = &blueprint.components.sprites;
+    let mut links = Links::default();
+    let entity = manufacturer.manufacture_blueprint(
+      ecs,
+      blueprint,
+      &SpriteBlueprints::default(),
+      &mut links,
+    );
+    if !sprites.is_empty() {
+      let sprites = sprites
+        .iter()
+        .map(|(state, blueprints)| {
+          let sprites = blueprints
+            .iter()
+            .map(|blueprint| blueprint.manufacture(&manufacturer.atlas))
+            .collect();
+          (state.clone(), sprites)
+        })
+        .collect();
+      ecs
+        .write_storage::<EagerRenderable>()
+        .insert(entity, EagerRenderable { _sprites: sprites })
+        .expect("EagerRenderable component not inserted.");
+    }
+    mark_manufactured(ecs, entity, id);
+    entity
+  }
+
+  /// Run with `npm run bench:rust`. Manufactures a 1,000 Entity level lazily
+  /// and eagerly, each into a fresh World, and compares.
+  #[test]
+  #[ignore]
+  fn bench_manufacture_level() {
+    fn world() -> World {
+      let mut ecs = World::new();
+      ecs.register::<Manufactured>();
+      ecs.register::<Position>();
+      ecs.register::<Velocity>();
+      ecs.register::<Renderable>();
+      ecs.register::<EagerRenderable>();
+      ecs.register::<Collision>();
+      ecs.register::<CollidesWith>();
+      ecs
+    }
+    let atlas = Rc::new(
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap(),
+    );
+    let files: Vec<_> = [
+      (BlueprintID::Bee, include_str!("blueprints/bee.json")),
+      (BlueprintID::RainCloud, include_str!("blueprints/rain_cloud.json")),
+    ]
+    .iter()
+    .map(|(id, json)| (id.filename(), json.to_string()))
+    .collect();
+    let blueprints = parse_blueprints(&files).unwrap();
+    let manufacturer =
+      Manufacturer::new(blueprints, atlas, ComponentRegistry::new()).unwrap();
+    let level: Vec<_> = [BlueprintID::Bee, BlueprintID::RainCloud]
+      .iter()
+      .cycle()
+      .take(1000)
+      .collect();
+
+    let mut lazy = Duration::default();
+    let mut eager = Duration::default();
+    for _ in 0..10 {
+      let mut ecs = world();
+      let start = Instant::now();
+      for id in &level {
+        manufacturer.manufacture(&mut ecs, **id).unwrap();
+      }
+      lazy += start.elapsed();
+
+      let mut ecs = world();
+      let start = Instant::now();
+      for id in &level {
+        manufacture_eager(&manufacturer, &mut ecs, **id);
+      }
+      eager += start.elapsed();
+    }
+
+    println!("Lazy manufacture of 1,000 Entitys: {:?}.", lazy / 10);
+    println!("Eager manufacture of 1,000 Entitys: {:?}.", eager / 10);
+    assert!(lazy < eager);
+  }
 }
@@ -1,11 +1,19 @@
+#[macro_use]
 mod blueprint;
 mod blueprint_id;
+mod blueprint_parser;
+mod blueprint_schema;
+mod component_blueprints_serde;
+mod component_registry;
 mod manufacture_blueprint;
 mod manufacturer;
 mod patch_blueprint;
 
 pub use blueprint::*;
 pub use blueprint_id::*;
-use manufacture_blueprint::*;
+pub use blueprint_parser::*;
+pub use blueprint_schema::*;
+pub use component_registry::*;
+pub(crate) use manufacture_blueprint::*;
 pub use manufacturer::*;
 use patch_blueprint::*;
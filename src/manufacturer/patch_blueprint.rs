@@ -1,9 +1,11 @@
 use super::{
-  AlignToBlueprint, Blueprint, ComponentBlueprints, MarkerBlueprint,
-  SizeU16Blueprint, XYBlueprint,
+  AlignToBlueprint, Blueprint, BlueprintID, CollisionBlueprint,
+  ComponentBlueprints, ComponentKind, MarkerBlueprint, SizeU16Blueprint,
+  XYBlueprint,
 };
-use crate::components::{Children, Parent};
-use std::collections::HashMap;
+use crate::components::{Children, CollidesWith, Parent};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 
 pub trait PatchBlueprint<T: Clone> {
@@ -11,35 +13,91 @@ pub trait PatchBlueprint<T: Clone> {
 }
 
 impl PatchBlueprint<Blueprint> for Blueprint {
-  /// Create a copy of self, merge any components present in patch, and append
-  /// any children in patch. Blueprint children themselves are not patched as
-  /// its the Manufacturer's responsibility.
+  /// Create a copy of self, apply the removal directives in patch, merge any
+  /// components present in patch, and append any children in patch. Blueprint
+  /// children themselves are not patched as its the Manufacturer's
+  /// responsibility. The directives are consumed. Children are only named by
+  /// their own names. See `patch_definition()`.
   fn patch(&self, patch: &Blueprint) -> Blueprint {
+    self.patch_definition(patch, &HashMap::new())
+  }
+}
+
+impl Blueprint {
+  /// Like `patch()` but children are named as `child_name()` names them so
+  /// that `remove_children` removes the same children Siblings target.
+  pub(crate) fn patch_definition(
+    &self,
+    patch: &Blueprint,
+    definitions: &HashMap<BlueprintID, Blueprint>,
+  ) -> Blueprint {
+    let mut components = self.components.clone();
+    for kind in &patch.remove {
+      components.remove(kind);
+    }
+    for state in &patch.replace_sprites {
+      components.sprites.remove(state);
+    }
+    let children: Vec<_> = self
+      .children
+      .iter()
+      .filter(|child| {
+        child_name(child, definitions)
+          .map_or(true, |name| !patch.remove_children.contains(name))
+      })
+      .cloned()
+      .collect();
     Self {
       id: patch.id,
-      components: self.components.patch(&patch.components),
-      children: self.children.patch(&patch.children),
+      components: components.patch(&patch.components),
+      children: children.patch(&patch.children),
+      name: self.name.patch(&patch.name),
+      remove: vec![],
+      remove_children: vec![],
+      replace_sprites: vec![],
     }
   }
 }
 
-impl PatchBlueprint<ComponentBlueprints> for ComponentBlueprints {
-  fn patch(&self, patch: &Self) -> Self {
-    Self {
-      align_to: self.align_to.patch(&patch.align_to),
-      cam: self.cam.patch(&patch.cam),
-      follow_mouse: self.follow_mouse.patch(&patch.follow_mouse),
-      position: self.position.patch(&patch.position),
-      velocity: self.velocity.patch(&patch.velocity),
-      text: self.text.patch(&patch.text),
-      max_wh: self.max_wh.patch(&patch.max_wh),
-      parent: self.parent.patch(&patch.parent),
-      children: self.children.patch(&patch.children),
-      sprites: self.sprites.patch(&patch.sprites),
+/// Returns the name of the child as patched: its own or else its definition's.
+pub(crate) fn child_name<'a>(
+  child: &'a Blueprint,
+  definitions: &'a HashMap<BlueprintID, Blueprint>,
+) -> Option<&'a String> {
+  child.name.as_ref().or_else(|| {
+    definitions.get(&child.id).and_then(|definition| definition.name.as_ref())
+  })
+}
+
+macro_rules! patch_component_blueprints {
+  ($($(#[$doc:meta])* $kind:ident $field:ident: $type:ty,)*) => {
+    impl ComponentBlueprints {
+      /// Unsets the component so that it's absent, as if never defined.
+      fn remove(&mut self, kind: &ComponentKind) {
+        match kind {
+          $(ComponentKind::$kind => self.$field = Default::default(),)*
+          ComponentKind::Registered(key) => {
+            self.registered.remove(key);
+          }
+        }
+      }
     }
-  }
+
+    impl PatchBlueprint<ComponentBlueprints> for ComponentBlueprints {
+      fn patch(&self, patch: &Self) -> Self {
+        Self {
+          $($field: self.$field.patch(&patch.$field),)*
+          registered: self.registered.patch(&patch.registered),
+          parent: self.parent.patch(&patch.parent),
+          children: self.children.patch(&patch.children),
+        }
+      }
+    }
+  };
 }
 
+builtin_components!(patch_component_blueprints);
+
 impl PatchBlueprint<Option<AlignToBlueprint>> for Option<AlignToBlueprint> {
   fn patch(&self, patch: &Self) -> Self {
     match (self, patch) {
@@ -48,12 +106,32 @@ impl PatchBlueprint<Option<AlignToBlueprint>> for Option<AlignToBlueprint> {
       (Some(base), Some(patch)) => Some(AlignToBlueprint {
         alignment: patch.alignment,
         margin: base.margin.patch(&patch.margin),
-        to: base.to.or(patch.to),
+        to: patch.to.clone().or_else(|| base.to.clone()),
       }),
     }
   }
 }
 
+/// Bodies and slices merge by aggregation.
+impl PatchBlueprint<Option<CollisionBlueprint>> for Option<CollisionBlueprint> {
+  fn patch(&self, patch: &Self) -> Self {
+    match (self, patch) {
+      (_, None) => self.clone(),
+      (None, _) => patch.clone(),
+      (Some(base), Some(patch)) => Some(CollisionBlueprint {
+        bodies: base.bodies.patch(&patch.bodies),
+        slices: base.slices.patch(&patch.slices),
+      }),
+    }
+  }
+}
+
+impl PatchBlueprint<Option<CollidesWith>> for Option<CollidesWith> {
+  fn patch(&self, patch: &Self) -> Self {
+    patch.or(*self)
+  }
+}
+
 impl PatchBlueprint<Option<Children>> for Option<Children> {
   fn patch(&self, patch: &Self) -> Self {
     match (self, patch) {
@@ -66,12 +144,29 @@ impl PatchBlueprint<Option<Children>> for Option<Children> {
   }
 }
 
+/// Lists merge by aggregation per key.
 impl<K: Clone + Eq + Hash, V: Clone> PatchBlueprint<HashMap<K, Vec<V>>>
   for HashMap<K, Vec<V>>
 {
   fn patch(&self, patch: &Self) -> Self {
     let mut patched = self.clone();
-    patched.extend(patch.clone());
+    for (key, values) in patch {
+      patched.entry(key.clone()).or_default().extend(values.iter().cloned());
+    }
+    patched
+  }
+}
+
+/// Registered components can only be merged by the ComponentRegistry so patch
+/// layers are appended for it to merge at manufacture time.
+impl PatchBlueprint<BTreeMap<String, Vec<Value>>>
+  for BTreeMap<String, Vec<Value>>
+{
+  fn patch(&self, patch: &Self) -> Self {
+    let mut patched = self.clone();
+    for (key, layers) in patch {
+      patched.entry(key.clone()).or_default().extend(layers.iter().cloned());
+    }
     patched
   }
 }
@@ -137,3 +232,221 @@ impl<T: Clone> PatchBlueprint<Option<XYBlueprint<T>>>
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn patch_directives() {
+    [
+      (
+        json!({
+          "id": "Bee",
+          "components": {"position": {"x": 1}, "velocity": {"x": 2}}
+        }),
+        json!({"id": "Bee", "remove": ["position"]}),
+        json!({"id": "Bee", "components": {"velocity": {"x": 2}}}),
+      ),
+      (
+        json!({
+          "id": "Bee",
+          "components": {"position": {"x": 1}}
+        }),
+        json!({
+          "id": "Bee",
+          "components": {"position": {"y": 2}},
+          "remove": ["position"]
+        }),
+        json!({"id": "Bee", "components": {"position": {"y": 2}}}),
+      ),
+      (
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {"Default": [{"id": "Bee"}], "Dead": [{"id": "BeeDead"}]}
+          }
+        }),
+        json!({
+          "id": "Bee",
+          "components": {"sprites": {"Walk": [{"id": "BeeShadow"}]}}
+        }),
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {
+              "Default": [{"id": "Bee"}],
+              "Dead": [{"id": "BeeDead"}],
+              "Walk": [{"id": "BeeShadow"}]
+            }
+          }
+        }),
+      ),
+      (
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {"Default": [{"id": "Bee"}], "Dead": [{"id": "BeeDead"}]}
+          }
+        }),
+        json!({
+          "id": "Bee",
+          "components": {"sprites": {"Walk": [{"id": "BeeShadow"}]}},
+          "remove": ["sprites"]
+        }),
+        json!({
+          "id": "Bee",
+          "components": {"sprites": {"Walk": [{"id": "BeeShadow"}]}}
+        }),
+      ),
+      (
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {
+              "Default": [{"id": "Bee"}],
+              "Dead": [{"id": "BeeDead"}],
+              "Walk": [{"id": "Bee"}]
+            }
+          }
+        }),
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {
+              "Default": [{"id": "BeeShadow"}],
+              "Walk": [{"id": "BeeShadow"}]
+            }
+          },
+          "replace_sprites": ["Default", "Dead"]
+        }),
+        json!({
+          "id": "Bee",
+          "components": {
+            "sprites": {
+              "Default": [{"id": "BeeShadow"}],
+              "Walk": [{"id": "Bee"}, {"id": "BeeShadow"}]
+            }
+          }
+        }),
+      ),
+      (
+        json!({
+          "id": "SaveDialog",
+          "children": [
+            {"id": "Button", "name": "ok"},
+            {"id": "Button", "name": "cancel"},
+            {"id": "Button"}
+          ]
+        }),
+        json!({
+          "id": "SaveDialog",
+          "children": [{"id": "Cursor", "name": "ok"}],
+          "remove_children": ["ok"]
+        }),
+        json!({
+          "id": "SaveDialog",
+          "children": [
+            {"id": "Button", "name": "cancel"},
+            {"id": "Button"},
+            {"id": "Cursor", "name": "ok"}
+          ]
+        }),
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (base, patch, expected))| {
+      let base: Blueprint = serde_json::from_value(base.clone()).unwrap();
+      let patch: Blueprint = serde_json::from_value(patch.clone()).unwrap();
+      let expected: Blueprint =
+        serde_json::from_value(expected.clone()).unwrap();
+      assert_eq!(
+        serde_json::to_value(base.patch(&patch)).unwrap(),
+        serde_json::to_value(expected).unwrap(),
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn remove_children_by_definition_name() {
+    let mut definitions = HashMap::new();
+    definitions.insert(
+      BlueprintID::Button,
+      from_json!({"id": "Button", "name": "ok"}).unwrap(),
+    );
+    let base: Blueprint = from_json!({
+      "id": "SaveDialog",
+      "children": [{"id": "Button"}, {"id": "Button", "name": "cancel"}]
+    })
+    .unwrap();
+    let patch: Blueprint =
+      from_json!({"id": "SaveDialog", "remove_children": ["ok"]}).unwrap();
+
+    let patched = base.patch_definition(&patch, &definitions);
+
+    assert_eq!(
+      serde_json::to_value(patched.children).unwrap(),
+      json!([{"id": "Button", "name": "cancel"}])
+    );
+    assert_eq!(base.patch(&patch).children.len(), 2);
+  }
+
+  #[test]
+  fn patch_registered_layers() {
+    let base: Blueprint = from_json!({
+      "id": "Bee",
+      "components": {"cursor": {"icon": "Hand"}, "item": {}}
+    })
+    .unwrap();
+    [
+      (
+        json!({"id": "Bee"}),
+        json!({"cursor": [{"icon": "Hand"}], "item": [{}]}),
+      ),
+      (
+        json!({"id": "Bee", "components": {"cursor": {"mode": "Pick"}}}),
+        json!({"cursor": [{"icon": "Hand"}, {"mode": "Pick"}], "item": [{}]}),
+      ),
+      (json!({"id": "Bee", "remove": ["cursor"]}), json!({"item": [{}]})),
+      (
+        json!({
+          "id": "Bee",
+          "components": {"cursor": {"mode": "Pick"}},
+          "remove": ["cursor"]
+        }),
+        json!({"cursor": [{"mode": "Pick"}], "item": [{}]}),
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (patch, expected))| {
+      let patch: Blueprint = serde_json::from_value(patch.clone()).unwrap();
+      assert_eq!(
+        serde_json::to_value(base.patch(&patch).components.registered).unwrap(),
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn directives_roundtrip() {
+    let json = json!({
+      "id": "SaveDialog",
+      "name": "dialog",
+      "components": {"cursor": {"icon": "Hand"}},
+      "remove": ["align_to", "sprites", "cursor"],
+      "remove_children": ["ok"],
+      "replace_sprites": ["Default"],
+      "children": [{"id": "Button", "remove": ["text"]}]
+    });
+
+    let decoded: Blueprint = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(decoded).unwrap(), json);
+  }
+}
@@ -1,5 +1,8 @@
 // http://www.firstpr.com.au/dsp/rand31/
 // https://jsperf.com/park-miller-vs-math-random
+/// A Park-Miller pseudorandom number generator. The same seed always produces
+/// the same sequence.
+#[derive(Clone, Debug)]
 pub struct Random {
   seed: i32,
 }
@@ -7,11 +10,9 @@ pub struct Random {
 impl Random {
   /// seed An integer.
   pub fn new(seed: i32) -> Self {
-    let mut seed = seed % 0x7fff_ffff; // [-0x7fff_fffe, 0x7fff_fffe]
-    if seed <= 0 {
-      seed += 0x7fff_ffff // [1, 0x7fff_ffff]
-    }
-    Self { seed }
+    // [1, 0x7fff_fffe]. Zero would only ever produce zero.
+    let seed = seed.rem_euclid(0x7fff_fffe);
+    Self { seed: if seed == 0 { 0x7fff_fffe } else { seed } }
   }
 
   /// Returns [0, 1)
@@ -21,8 +22,31 @@ impl Random {
 
   /// Returns an integer [0, 2^31 - 3].
   pub fn int(&mut self) -> i32 {
-    // [1, 2^31 - 2] or [0x1, 0x7fff_fffe]
-    self.seed = (self.seed * 16_807) % 0x7fff_ffff;
+    // [1, 2^31 - 2] or [0x1, 0x7fff_fffe]. The product overflows 32 bits.
+    self.seed = ((i64::from(self.seed) * 16_807) % 0x7fff_ffff) as i32;
     self.seed - 1
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn int() {
+    [
+      (1, [16_806, 282_475_248, 1_622_650_072]),
+      (0x7fff_fffe, [2_147_466_839, 1_865_008_397, 524_833_573]),
+      (0, [2_147_466_839, 1_865_008_397, 524_833_573]),
+      (-1, [2_147_450_032, 1_582_533_148, 1_049_667_147]),
+      (i32::min_value(), [2_147_433_225, 1_300_057_899, 1_574_500_721]),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (seed, expected))| {
+      let mut random = Random::new(*seed);
+      let actual = [random.int(), random.int(), random.int()];
+      assert_eq!(actual, *expected, "Case {} failed: {}.", i, seed);
+    });
+  }
+}
@@ -4,7 +4,7 @@ use num::traits::{
   real::Real,
   Zero,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
   convert::{From, TryFrom, TryInto},
   fmt,
@@ -18,7 +18,7 @@ use std::{
 /// makes no distinction between back- and front-facing rectangles except for
 /// `Rect.flipped()`. A back-facing rectangle can be recomputed to a
 /// front-facing rectangle by calling order().
-#[derive(Clone, Eq, PartialEq, Serialize)]
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Rect<T> {
   pub from: XY<T>,
   pub to: XY<T>,
@@ -8,7 +8,7 @@ use num::{
     Signed,
   },
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
   convert::{From, TryFrom, TryInto},
   fmt,
@@ -19,7 +19,7 @@ use std::{
   ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
 };
 
-#[derive(Clone, Eq, PartialEq, Serialize)]
+#[derive(Clone, Deserialize, Eq, PartialEq, Serialize)]
 pub struct XY<T> {
   pub x: T,
   pub y: T,
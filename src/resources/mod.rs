@@ -1,4 +1,7 @@
-use crate::math::Millis;
+use crate::atlas::{AnimationID, AnimatorEvent, BodyKind};
+use crate::components::CollidesWith;
+use crate::math::{Millis, XY};
+use specs::Entity;
 use std::time::Duration;
 
 pub struct Timing {
@@ -7,3 +10,56 @@ pub struct Timing {
   /// Time since last frame, possibly 0.
   pub delta: Millis,
 }
+
+/// How CamSystem moves the Viewport's cam to follow an Entity. Without a
+/// target, the cam only holds within the level.
+pub struct CamFollow {
+  /// The Entity whose WorldPosition is followed, usually the player.
+  pub target: Option<Entity>,
+  /// The size of the region centered in the cam that the target moves within
+  /// freely. The cam only follows once the target leaves it.
+  pub dead_zone: XY<u16>,
+  /// The fraction of the distance to the target eased per second in [0, 1].
+  /// 1 follows rigidly and 0 holds still.
+  pub easing: f64,
+  /// If true, the cam moves in whole pixels only and at least one pixel per
+  /// update until it arrives. Otherwise, subpixel progress is carried between
+  /// updates.
+  pub snap: bool,
+}
+
+impl Default for CamFollow {
+  fn default() -> Self {
+    Self { target: None, dead_zone: XY::new(32, 32), easing: 0.99, snap: false }
+  }
+}
+
+/// The Animator events of the last update for every animated sprite. Systems
+/// read these to chain states, e.g., bird-rise then bird-fly, or to remove an
+/// entity after its death animation.
+#[derive(Default)]
+pub struct AnimationEvents {
+  pub events: Vec<(Entity, AnimationID, AnimatorEvent)>,
+}
+
+/// The Collision contacts of the last update ordered by Entity, then by body
+/// kinds. Bodies overlap or are within the Collider's near margin.
+#[derive(Default)]
+pub struct Contacts {
+  pub contacts: Vec<Contact>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+  /// The lesser Entity first.
+  pub entities: (Entity, Entity),
+  /// The CollidesWith flags of each Entity including the Near flags computed
+  /// from every Entity near it.
+  pub flags: (CollidesWith, CollidesWith),
+  /// The kind of each Entity's bodies in contact or None for Collision bodies,
+  /// which have no kind. Entitys have a Contact per pair of kinds in contact
+  /// so that, e.g., a Hitbox touching a Hurtbox is told apart from Feet.
+  pub kinds: (Option<BodyKind>, Option<BodyKind>),
+  /// True if the bodies overlap, false if they're only near.
+  pub overlapping: bool,
+}
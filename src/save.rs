@@ -0,0 +1,267 @@
+//! Saves and loads the World for save games, editor snapshots, and test
+//! fixtures. Only Persistent marked Entitys are saved along with every Entity
+//! they link to (parents, children, and alignment targets) which are marked
+//! as they're found. Links are remapped through the markers so a loaded World
+//! matches the saved one even though its Entitys differ. Registered
+//! components are saved as their Blueprints by the ComponentRegistry.
+
+use crate::atlas::Atlas;
+use crate::components::{
+  AlignTo, Cam, Children, CollidesWith, Collision, FollowMouse, Manufactured,
+  MaxSize, Parent, Position, Renderable, Tags, Text, Velocity,
+};
+use crate::manufacturer::ComponentRegistry;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specs::error::NoError;
+use specs::saveload::{
+  DeserializeComponents, Marker, MarkerAllocator, SerializeComponents,
+  SimpleMarker, SimpleMarkerAllocator,
+};
+use specs::{
+  Entities, Entity, Join, ReadStorage, World, WorldExt, Write, WriteStorage,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The marker of Entitys to save.
+pub struct Persistent;
+pub type PersistentMarker = SimpleMarker<Persistent>;
+pub type PersistentMarkerAllocator = SimpleMarkerAllocator<Persistent>;
+
+#[derive(Debug)]
+pub struct SaveError(pub String);
+
+#[derive(Deserialize, Serialize)]
+struct SaveFile {
+  /// The builtin Components of every Persistent Entity.
+  entities: Value,
+  /// The registered Components by Persistent marker ID.
+  registered: Vec<(u64, BTreeMap<String, Value>)>,
+}
+
+impl fmt::Display for SaveError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(formatter, "{}", self.0)
+  }
+}
+
+impl From<NoError> for SaveError {
+  fn from(error: NoError) -> Self {
+    match error {}
+  }
+}
+
+/// Generates `register()` and the storages of every saved Component from one
+/// list.
+macro_rules! saved_components {
+  ($($component:ident),*) => {
+    /// Registers the marker and every saved Component.
+    pub fn register(ecs: &mut World) {
+      ecs.register::<PersistentMarker>();
+      ecs.insert(PersistentMarkerAllocator::new());
+      $(ecs.register::<$component>();)*
+    }
+
+    type SavedReadStorages<'a> = ($(ReadStorage<'a, $component>,)*);
+    type SavedWriteStorages<'a> = ($(WriteStorage<'a, $component>,)*);
+  };
+}
+
+// Add saved Components here.
+saved_components!(
+  AlignTo,
+  Cam,
+  Children,
+  CollidesWith,
+  Collision,
+  FollowMouse,
+  Manufactured,
+  MaxSize,
+  Parent,
+  Position,
+  Renderable,
+  Tags,
+  Text,
+  Velocity
+);
+
+/// Marks the Entity Persistent. Marking an already marked Entity is harmless.
+pub fn mark(ecs: &World, entity: Entity) {
+  let mut allocator = ecs.write_resource::<PersistentMarkerAllocator>();
+  allocator.mark(entity, &mut ecs.write_storage::<PersistentMarker>());
+}
+
+/// Returns the Persistent Entitys and their Components as JSON. Linked Entitys
+/// are marked Persistent.
+pub fn save(
+  ecs: &mut World,
+  registry: &ComponentRegistry,
+) -> Result<String, SaveError> {
+  let entities = ecs
+    .exec(
+      |(entities, mut allocator, mut markers, components): (
+        Entities,
+        Write<PersistentMarkerAllocator>,
+        WriteStorage<PersistentMarker>,
+        SavedReadStorages,
+      )| {
+        SerializeComponents::<SaveError, PersistentMarker>::serialize_recursive(
+          &components,
+          &entities,
+          &mut markers,
+          &mut allocator,
+          serde_json::value::Serializer,
+        )
+      },
+    )
+    .map_err(|error| SaveError(error.to_string()))?;
+
+  let mut registered = Vec::new();
+  let markers = ecs.read_storage::<PersistentMarker>();
+  for (entity, marker) in (&ecs.entities(), &markers).join() {
+    let components = registry.save(ecs, entity).map_err(SaveError)?;
+    if !components.is_empty() {
+      registered.push((marker.id(), components));
+    }
+  }
+  serde_json::to_string(&SaveFile { entities, registered })
+    .map_err(|error| SaveError(error.to_string()))
+}
+
+/// Loads JSON returned by `save()` into the World. Entitys with markers
+/// already in the World are overwritten. Sprites are reinflated from the
+/// Atlas and restart their animations.
+pub fn load(
+  ecs: &mut World,
+  json: &str,
+  atlas: &Atlas,
+  registry: &ComponentRegistry,
+) -> Result<(), SaveError> {
+  let SaveFile { entities: saved, registered } =
+    serde_json::from_str(json).map_err(|error| SaveError(error.to_string()))?;
+  ecs
+    .exec(
+      |(entities, mut allocator, mut markers, mut components): (
+        Entities,
+        Write<PersistentMarkerAllocator>,
+        WriteStorage<PersistentMarker>,
+        SavedWriteStorages,
+      )| {
+        DeserializeComponents::<SaveError, _>::deserialize(
+          &mut components,
+          &entities,
+          &mut markers,
+          &mut allocator,
+          saved,
+        )
+      },
+    )
+    .map_err(|error| SaveError(error.to_string()))?;
+
+  for (id, components) in &registered {
+    let entity = ecs
+      .read_resource::<PersistentMarkerAllocator>()
+      .retrieve_entity_internal(*id)
+      .ok_or_else(|| SaveError(format!("Marker {} unsaved.", id)))?;
+    registry.load(ecs, entity, components).map_err(SaveError)?;
+  }
+
+  let markers = ecs.read_storage::<PersistentMarker>();
+  let mut renderables = ecs.write_storage::<Renderable>();
+  for (renderable, _) in (&mut renderables, &markers).join() {
+    renderable.sprites.inflate(atlas);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas::{self, AnimationID};
+  use crate::components::{self, Alignment, Cursor, CursorIcon, CursorMode};
+  use crate::manufacturer::BlueprintID;
+  use crate::math::{R16, XY};
+  use crate::sprites::{Sprite, SpriteMap};
+  use specs::Builder;
+  use std::sync::Arc;
+
+  #[test]
+  fn roundtrip() {
+    let atlas =
+      atlas::parse(&include_json!("atlas/atlas.json").unwrap()).unwrap();
+    let registry = components::component_registry();
+    let mut ecs = World::new();
+    register(&mut ecs);
+    registry.register(&mut ecs);
+    let sprites =
+      from_json!({"Default": [{"id": "Bee"}], "Dead": [{"id": "BeeDead"}]})
+        .unwrap();
+    let mut sprites = SpriteMap::new(Arc::new(sprites), &atlas);
+    sprites.set_state("Dead".to_string(), &atlas);
+    let root = ecs
+      .create_entity()
+      .with(Position { position: XY::new(1, 2) })
+      .with(Text { text: "root".to_string() })
+      .with(Renderable { sprites })
+      .with(Manufactured { id: BlueprintID::Bee })
+      .with(Tags { tags: vec!["root".to_string()] })
+      .with(Collision {
+        bodies: vec![R16::new_size(1, 1, 3, 3)],
+        cel_bodies: vec![],
+      })
+      .with(CollidesWith::TYPE_CHARACTER | CollidesWith::HARMFUL)
+      .build();
+    let child = ecs
+      .create_entity()
+      .with(Parent { parent: root })
+      .with(AlignTo::new(Alignment::Center, XY::new(3, 4), Some(root)))
+      .with(Velocity { velocity: XY::new(5, 6) })
+      .with(FollowMouse {})
+      .with(Cursor { mode: CursorMode::Pick, icon: CursorIcon::Hand })
+      .build();
+    ecs
+      .write_storage::<Children>()
+      .insert(root, Children { children: vec![child] })
+      .unwrap();
+    ecs.create_entity().with(Position { position: XY::new(7, 8) }).build();
+    mark(&ecs, root);
+
+    let json = save(&mut ecs, &registry).unwrap();
+
+    let mut loaded = World::new();
+    register(&mut loaded);
+    registry.register(&mut loaded);
+    load(&mut loaded, &json, &atlas, &registry).unwrap();
+    let resaved = save(&mut loaded, &registry).unwrap();
+    assert_eq!(
+      serde_json::from_str::<serde_json::Value>(&resaved).unwrap(),
+      serde_json::from_str::<serde_json::Value>(&json).unwrap()
+    );
+    assert_eq!((&loaded.entities()).join().count(), 2);
+
+    let children = loaded.read_storage::<Children>();
+    let parents = loaded.read_storage::<Parent>();
+    let renderables = loaded.read_storage::<Renderable>();
+    let (root, root_children, renderable) =
+      (&loaded.entities(), &children, &renderables).join().next().unwrap();
+    assert_eq!(root_children.children.len(), 1);
+    let child = root_children.children[0];
+    assert_eq!(parents.get(child).unwrap().parent, root);
+    assert!(loaded.read_storage::<FollowMouse>().contains(child));
+    let cursors = loaded.read_storage::<Cursor>();
+    let cursor = cursors.get(child).unwrap();
+    assert_eq!(
+      (cursor.mode, cursor.icon),
+      (CursorMode::Pick, CursorIcon::Hand)
+    );
+    assert_eq!(
+      loaded.read_storage::<CollidesWith>().get(root),
+      Some(&(CollidesWith::TYPE_CHARACTER | CollidesWith::HARMFUL))
+    );
+    assert_eq!(renderable.sprites.get_state(), "Dead");
+    let ids: Vec<_> =
+      renderable.sprites.get_sprites().iter().map(Sprite::get_id).collect();
+    assert_eq!(ids, vec![AnimationID::BeeDead]);
+  }
+}
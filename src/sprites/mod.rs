@@ -3,9 +3,11 @@ mod sprite_composition;
 mod sprite_layer;
 mod sprite_layout;
 mod sprite_map;
+mod sprite_palette;
 
 pub use sprite::*;
 pub use sprite_composition::*;
 pub use sprite_layer::*;
 pub use sprite_layout::*;
 pub use sprite_map::*;
+pub use sprite_palette::*;
@@ -1,10 +1,12 @@
-use super::{SpriteComposition, SpriteLayer};
-use crate::atlas::{AnimationID, Animator, Atlas};
+use super::{SpriteComposition, SpriteLayer, SpritePalette};
+use crate::atlas::{AnimationID, Animator, AnimatorEvent, Atlas, Body};
 use crate::math::{Millis, R16, XY, XY16};
-use std::{convert::TryInto, num::NonZeroI16};
+use std::convert::{TryFrom, TryInto};
+use std::num::NonZeroI16;
 
 /// A mapping from an Atlas Animation to a level region. This includes all
 /// distinct state needed to represent an instance to the shader.
+#[derive(Clone)]
 pub struct Sprite {
   /// The source Animation subtextures identifer to draw from.
   id: AnimationID,
@@ -44,6 +46,8 @@ pub struct Sprite {
   layer: i16,
   /// Source Animation state.
   animator: Animator,
+  /// The color remap applied to the rendered composition, if any.
+  palette: Option<SpritePalette>,
 }
 
 impl Sprite {
@@ -57,9 +61,11 @@ impl Sprite {
     wrap_velocity: XY16,
     layer: SpriteLayer,
     animator: Animator,
+    palette: Option<SpritePalette>,
   ) -> Self {
     Self {
       animator,
+      palette,
       id,
       constituent,
       composition,
@@ -71,8 +77,56 @@ impl Sprite {
     }
   }
 
-  pub fn animate(&mut self, atlas: &Atlas, exposure: Millis) {
-    self.animator.animate(&atlas.animations[&self.id], exposure);
+  pub fn animate(
+    &mut self,
+    atlas: &Atlas,
+    exposure: Millis,
+  ) -> Vec<AnimatorEvent> {
+    self.animator.animate(&atlas.animations[&self.id], exposure)
+  }
+
+  /// Returns the current source Cel's collision bodies offset by the sprite's
+  /// bounds.
+  /// Bodies are mirrored when the scale is negative.
+  pub fn get_bodies(&self, atlas: &Atlas) -> Vec<Body> {
+    let animation = &atlas.animations[&self.id];
+    let cel = match self.animator.cel(animation) {
+      Some(cel) => cel,
+      None => return vec![],
+    };
+    let size: XY16 = animation
+      .size
+      .clone()
+      .try_into()
+      .expect("Animation size u16 to i16 conversion failed.");
+    let scale: XY16 = self.scale.clone().into();
+    cel
+      .bodies
+      .iter()
+      .map(|Body { kind, bounds }| {
+        let mut bounds = bounds.clone();
+        if scale.x < 0 {
+          bounds = R16::new(
+            size.x - bounds.to.x,
+            bounds.from.y,
+            size.x - bounds.from.x,
+            bounds.to.y,
+          );
+        }
+        if scale.y < 0 {
+          bounds = R16::new(
+            bounds.from.x,
+            size.y - bounds.to.y,
+            bounds.to.x,
+            size.y - bounds.from.y,
+          );
+        }
+        Body {
+          kind: *kind,
+          bounds: bounds * scale.abs() + self.bounds.from.clone(),
+        }
+      })
+      .collect()
   }
 
   pub fn reset_animation(&mut self) {
@@ -158,6 +212,14 @@ impl Sprite {
     self.layer += layer;
   }
 
+  pub fn get_palette(&self) -> Option<&SpritePalette> {
+    self.palette.as_ref()
+  }
+
+  pub fn palette_to(&mut self, to: Option<SpritePalette>) {
+    self.palette = to;
+  }
+
   pub fn serialize(
     &self,
     config: &bincode::Config,
@@ -165,38 +227,73 @@ impl Sprite {
   ) -> bincode::Result<Vec<u8>> {
     let mut bytes = Vec::new();
 
-    bytes.append(
-      &mut config.serialize(
-        &self
-          .animator
-          .cel(&atlas.animations[&self.id])
-          .ok_or(bincode::ErrorKind::Custom("No source Cel.".to_string()))?
-          .bounds,
-      )?,
-    );
-    bytes.append(
-      &mut config.serialize(
-        &self
-          .animator
-          .cel(&atlas.animations[&self.constituent])
-          .ok_or(bincode::ErrorKind::Custom("No constituent Cel.".to_string()))?
-          .bounds,
-      )?,
-    );
-    bytes.append(&mut config.serialize(&self.composition)?);
+    let source = self
+      .animator
+      .cel(&atlas.animations[&self.id])
+      .ok_or(bincode::ErrorKind::Custom("No source Cel.".to_string()))?;
+    let constituent =
+      self
+        .animator
+        .cel(&atlas.animations[&self.constituent])
+        .ok_or(bincode::ErrorKind::Custom("No constituent Cel.".to_string()))?;
+    bytes.append(&mut config.serialize(&source.bounds)?);
+    bytes.append(&mut config.serialize(&constituent.bounds)?);
+    bytes.append(&mut config.serialize(&(self.composition as u16))?);
     bytes.append(&mut config.serialize(&self.bounds)?);
     bytes.append(&mut config.serialize(&self.scale)?);
     bytes.append(&mut config.serialize(&self.wrap)?);
     bytes.append(&mut config.serialize(&self.wrap_velocity)?);
+    bytes.append(&mut config.serialize(&source.trim)?);
+    bytes.append(&mut config.serialize(&constituent.trim)?);
+    let (palette_source, palette_target) = self.serialize_palette(atlas)?;
+    bytes.append(&mut config.serialize(&palette_source)?);
+    bytes.append(&mut config.serialize(&palette_target)?);
 
     Ok(bytes)
   }
+
+  /// Returns the source palette row region and the target palette row start
+  /// within the atlas. The row is truncated to the narrower palette. Without a
+  /// palette, the source row is empty and no colors are remapped.
+  fn serialize_palette(&self, atlas: &Atlas) -> bincode::Result<(R16, XY16)> {
+    let palette = match &self.palette {
+      Some(palette) => palette,
+      None => return Ok((R16::new(0, 0, 0, 0), XY::new(0, 0))),
+    };
+    let source =
+      atlas.animations[&palette.source].cels.first().ok_or_else(|| {
+        bincode::ErrorKind::Custom("No source palette Cel.".to_string())
+      })?;
+    let target =
+      atlas.animations[&palette.target].cels.first().ok_or_else(|| {
+        bincode::ErrorKind::Custom("No target palette Cel.".to_string())
+      })?;
+    let row = i16::try_from(palette.row)
+      .ok()
+      .filter(|row| *row < palette.target_rows(atlas))
+      .ok_or_else(|| {
+        bincode::ErrorKind::Custom("Palette row out of range.".to_string())
+      })?;
+    let source_from = source.bounds.from.clone() + source.trim.from.clone();
+    let target_from = target.bounds.from.clone() + target.trim.from.clone();
+    let len = (source.trim.to.x - source.trim.from.x)
+      .min(target.trim.to.x - target.trim.from.x);
+    Ok((
+      R16::new(
+        source_from.x,
+        source_from.y,
+        source_from.x + len,
+        source_from.y + 1,
+      ),
+      XY::new(target_from.x, target_from.y + row),
+    ))
+  }
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
-  use crate::atlas::{Animation, Cel, Playback};
+  use crate::atlas::{self, Animation, BodyKind, Cel, Playback};
   use std::collections::hash_map::HashMap;
 
   #[test]
@@ -211,6 +308,7 @@ mod test {
       XY::new(0, 0),
       SpriteLayer::Default,
       Animator::new(0, 0.),
+      None,
     );
 
     assert_eq!(sprite.get_scale(), &(1, 1).try_into().unwrap());
@@ -236,6 +334,7 @@ mod test {
       XY::new(7, 8),
       SpriteLayer::Default,
       Animator::new(0, 0.),
+      None,
     );
 
     assert_eq!(sprite.get_wrap(), &XY::new(5, 6));
@@ -259,6 +358,7 @@ mod test {
       XY::new(0, 0),
       SpriteLayer::Default,
       Animator::new(0, 0.),
+      None,
     );
 
     assert_eq!(sprite.get_layer(), SpriteLayer::Default as i16);
@@ -273,14 +373,200 @@ mod test {
     assert_eq!(sprite.get_layer(), (SpriteLayer::Default as i16));
   }
 
+  #[test]
+  fn bodies() {
+    let mut animations = HashMap::new();
+    let cels = vec![Cel {
+      bounds: R16::new(0, 0, 8, 4),
+      trim: R16::new_size(0, 0, 8, 4),
+      duration: 1.,
+      slices: vec![],
+      bodies: vec![Body {
+        kind: BodyKind::Hitbox,
+        bounds: R16::new_size(5, 1, 2, 3),
+      }],
+    }];
+    animations.insert(
+      AnimationID::Bee,
+      Animation {
+        size: XY::new(8, 4),
+        cels,
+        duration: 1.,
+        direction: Playback::Forward,
+        repeat: None,
+      },
+    );
+    let atlas = Atlas {
+      version: "1.2.3.4".to_string(),
+      filename: "atlas.png".to_string(),
+      format: "I8".to_string(),
+      size: XY::new(256, 256),
+      animations,
+    };
+    [
+      ((1, 1), R16::new_size(15, 21, 2, 3)),
+      ((2, 1), R16::new_size(20, 21, 4, 3)),
+      ((-1, 1), R16::new_size(11, 21, 2, 3)),
+      ((1, -2), R16::new_size(15, 20, 2, 6)),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (scale, expected))| {
+      let sprite = Sprite::new(
+        AnimationID::Bee,
+        AnimationID::Bee,
+        SpriteComposition::Source,
+        R16::new_size(10, 20, 8, 4),
+        (*scale).try_into().unwrap(),
+        XY::new(0, 0),
+        XY::new(0, 0),
+        SpriteLayer::Default,
+        Animator::new(0, 0.),
+        None,
+      );
+      assert_eq!(
+        sprite.get_bodies(&atlas),
+        vec![Body { kind: BodyKind::Hitbox, bounds: expected.clone() }],
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn serialize_palette() {
+    let mut animations = HashMap::new();
+    for (id, bounds) in [
+      (AnimationID::PaletteRed, R16::new_size(10, 20, 3, 2)),
+      (AnimationID::PaletteBlack, R16::new_size(30, 40, 2, 1)),
+    ]
+    .iter()
+    {
+      let size = bounds.to.clone() - bounds.from.clone();
+      let cels = vec![Cel {
+        bounds: bounds.clone(),
+        trim: R16::new(0, 0, size.x, size.y),
+        duration: 1.,
+        slices: vec![],
+        bodies: vec![],
+      }];
+      animations.insert(
+        *id,
+        Animation {
+          size: XY::new(size.x as u16, size.y as u16),
+          cels,
+          duration: 1.,
+          direction: Playback::Forward,
+          repeat: None,
+        },
+      );
+    }
+    let atlas = Atlas {
+      version: "1.2.3.4".to_string(),
+      filename: "atlas.png".to_string(),
+      format: "I8".to_string(),
+      size: XY::new(256, 256),
+      animations,
+    };
+    [
+      (None, Some((R16::new(0, 0, 0, 0), XY::new(0, 0)))),
+      (
+        Some((AnimationID::PaletteBlack, AnimationID::PaletteRed, 1)),
+        Some((R16::new(30, 40, 32, 41), XY::new(10, 21))),
+      ),
+      (
+        Some((AnimationID::PaletteRed, AnimationID::PaletteBlack, 0)),
+        Some((R16::new(10, 20, 12, 21), XY::new(30, 40))),
+      ),
+      (Some((AnimationID::PaletteRed, AnimationID::PaletteBlack, 1)), None),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (palette, expected))| {
+      let sprite = Sprite::new(
+        AnimationID::PaletteRed,
+        AnimationID::PaletteRed,
+        SpriteComposition::Source,
+        R16::new(0, 0, 3, 2),
+        (1, 1).try_into().unwrap(),
+        XY::new(0, 0),
+        XY::new(0, 0),
+        SpriteLayer::Default,
+        Animator::new(0, 0.),
+        palette.map(|(source, target, row)| SpritePalette {
+          source,
+          target,
+          row,
+        }),
+      );
+      assert_eq!(
+        sprite.serialize_palette(&atlas).ok(),
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  /// The exported palettes are single pixel swatches so each remaps one color
+  /// and only has row 0.
+  #[test]
+  fn serialize_palette_swatches() {
+    let atlas =
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap();
+    let palettes: Vec<_> = atlas
+      .animations
+      .iter()
+      .filter(|(id, _)| id.tag().starts_with("palette-"))
+      .collect();
+    assert!(!palettes.is_empty());
+    for (id, animation) in palettes {
+      assert_eq!(
+        animation.cels[0].trim,
+        R16::new_size(0, 0, 1, 1),
+        "{}",
+        id.tag()
+      );
+    }
+
+    [(0, Some((R16::new(164, 30, 165, 31), XY::new(160, 30)))), (1, None)]
+      .iter()
+      .for_each(|(row, expected)| {
+        let sprite = Sprite::new(
+          AnimationID::Bee,
+          AnimationID::Bee,
+          SpriteComposition::Source,
+          R16::new(0, 0, 1, 1),
+          (1, 1).try_into().unwrap(),
+          XY::new(0, 0),
+          XY::new(0, 0),
+          SpriteLayer::Default,
+          Animator::new(0, 0.),
+          Some(SpritePalette {
+            source: AnimationID::PaletteRed,
+            target: AnimationID::PaletteBlack,
+            row: *row,
+          }),
+        );
+        assert_eq!(
+          sprite.serialize_palette(&atlas).ok(),
+          *expected,
+          "Row {} failed.",
+          row
+        );
+      });
+  }
+
   #[rustfmt::skip]
   #[test]
   fn serialize() {
     let mut animations = HashMap::new();
     let cels = vec![Cel {
       bounds: R16::new(7, 8, 12, 14),
+      trim: R16::new_size(0, 0, 5, 6),
       duration: 100.,
       slices: vec![],
+      bodies: vec![],
     }];
     animations.insert(
       AnimationID::Bee,
@@ -289,6 +575,7 @@ mod test {
         cels,
         duration: 100.,
         direction: Playback::Forward,
+        repeat: None,
       },
     );
     let atlas = Atlas {
@@ -308,6 +595,7 @@ mod test {
       XY::new(17, 18),
       SpriteLayer::UICursor,
       Animator::new(0, 0.),
+      None,
     );
     let bytes =
       sprite.serialize(bincode::config().big_endian(), &atlas).unwrap();
@@ -320,7 +608,11 @@ mod test {
         0, 9, 0, 10, 0, 55, 0, 72,
         0, 11, 0, 13,
         0, 15, 0, 16,
-        0, 17, 0, 18
+        0, 17, 0, 18,
+        0, 0, 0, 0, 0, 5, 0, 6,
+        0, 0, 0, 0, 0, 5, 0, 6,
+        0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0
       ]
     );
   }
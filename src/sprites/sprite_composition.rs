@@ -1,9 +1,10 @@
-use serde::Deserialize;
-use serde_repr::Serialize_repr;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
 
 // See https://developer.android.com/reference/android/graphics/PorterDuff.Mode.
+// Rendered as the u16 discriminant. See Sprite::serialize().
 #[repr(u16)]
-#[derive(Serialize_repr, Clone, Copy, Debug, PartialEq, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, PartialEq, Serialize)]
 pub enum SpriteComposition {
   /// The constituent is unused. The source is rendered unaltered.
   Source,
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Formatter;
+use strum_macros::EnumIter;
 
 /// The sprite draw order from bottom (lesser) to top (greater). Within a Layer,
 /// sprites are drawn in y-coordinate + height ascending order (lesser to
@@ -12,7 +13,7 @@ use std::fmt::Formatter;
 /// controlled manner allowing for effects like shadows to consistently be drawn
 /// below the object meant to cast it.
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, EnumIter, PartialEq, Serialize)]
 pub enum SpriteLayer {
   /// Terrain.
   Plane,
@@ -1,34 +1,42 @@
 use super::Sprite;
-use crate::manufacturer::SpriteBlueprint;
+use crate::atlas::Atlas;
+use crate::manufacturer::{ManufactureAtlasBlueprint, SpriteBlueprint};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub type State = String;
 
-/// All SpriteMaps implement this state.
-// pub static DEFAULT_STATE: State = "Default".to_string();
-// Do I want a hidden state?
+/// All SpriteMaps start in this state.
+pub static DEFAULT_STATE: &str = "Default";
 
-pub struct SpriteMap<'a> {
-  pub state: State,
-  // todo: we'll have to see how this goes reconstructing the entire state every
-  // time the state changes.
-  pub sprites: Vec<Sprite>, // this becomes a sprite_layout.
+/// The SpriteBlueprints of every state shared by all Entities manufactured from
+/// the same Blueprint.
+pub type SpriteBlueprints = Arc<HashMap<State, Vec<SpriteBlueprint>>>;
 
-  // actually don't want a a vec since it's assembled on the fly.
-  // pub sprites: HashMap<State, Vec<Sprite>>,
-  pub sprite_blueprints: &'a HashMap<String, Vec<SpriteBlueprint>>,
+/// Sprites for the active state only. Inflating every state of every Entity at
+/// manufacture time is expensive and mostly wasted so the Sprites are inflated
+/// from the shared blueprints on each state change instead.
+///
+/// Only the state and blueprints are serialized. Deserialized SpriteMaps must
+/// be inflated before use.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SpriteMap {
+  state: State,
+  #[serde(skip)]
+  sprites: Vec<Sprite>,
+  #[serde(
+    serialize_with = "serialize_blueprints",
+    deserialize_with = "deserialize_blueprints"
+  )]
+  sprite_blueprints: SpriteBlueprints,
 }
 
-impl<'a> SpriteMap<'a> {
-  /// Sets the Default state.
-  pub fn new(
-    state: State,
-    sprite_blueprints: &'a HashMap<String, Vec<SpriteBlueprint>>,
-  ) -> Self {
-    // let mut sprites = HashMap::new();
-    // sprites.insert(DEFAULT_STATE, vec![]);
-    // invalideate
-    let sprites = vec![];
+impl SpriteMap {
+  /// Sets the DEFAULT_STATE.
+  pub fn new(sprite_blueprints: SpriteBlueprints, atlas: &Atlas) -> Self {
+    let state = DEFAULT_STATE.to_string();
+    let sprites = inflate(&sprite_blueprints, &state, atlas);
     Self { state, sprites, sprite_blueprints }
   }
 
@@ -41,17 +49,84 @@ impl<'a> SpriteMap<'a> {
     self.sprite_blueprints.keys().collect()
   }
 
-  /// Transitions the active state as needed.
-  pub fn set_state(&mut self, state: State) {
+  /// Transitions the active state as needed. A state without blueprints has no
+  /// Sprites.
+  pub fn set_state(&mut self, state: State, atlas: &Atlas) {
     if self.state == state {
       return;
     }
-    assert_eq!(self.sprite_blueprints.contains_key(&state), true);
+    self.sprites = inflate(&self.sprite_blueprints, &state, atlas);
     self.state = state;
   }
 
+  /// Reinflates the Sprites of the active state which restarts their
+  /// animations.
+  pub fn inflate(&mut self, atlas: &Atlas) {
+    self.sprites = inflate(&self.sprite_blueprints, &self.state, atlas);
+  }
+
   /// Returns sprites for the active state.
   pub fn get_sprites(&self) -> &Vec<Sprite> {
     &self.sprites
   }
+
+  pub fn get_sprites_mut(&mut self) -> &mut Vec<Sprite> {
+    &mut self.sprites
+  }
+}
+
+// The blueprints are no longer shared once deserialized.
+fn serialize_blueprints<S: Serializer>(
+  sprite_blueprints: &SpriteBlueprints,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  sprite_blueprints.as_ref().serialize(serializer)
+}
+
+fn deserialize_blueprints<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<SpriteBlueprints, D::Error> {
+  HashMap::deserialize(deserializer).map(Arc::new)
+}
+
+fn inflate(
+  sprite_blueprints: &HashMap<State, Vec<SpriteBlueprint>>,
+  state: &str,
+  atlas: &Atlas,
+) -> Vec<Sprite> {
+  sprite_blueprints.get(state).map_or(vec![], |blueprints| {
+    blueprints.iter().map(|blueprint| blueprint.manufacture(atlas)).collect()
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas::{self, AnimationID};
+  use crate::manufacturer::Blueprint;
+
+  #[test]
+  fn set_state_inflates_active_state() {
+    let atlas =
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap();
+    let blueprint: Blueprint =
+      include_json!("../manufacturer/blueprints/bee.json").unwrap();
+    let mut sprites =
+      SpriteMap::new(Arc::new(blueprint.components.sprites), &atlas);
+    [
+      ("Default", vec![AnimationID::Bee, AnimationID::BeeShadow]),
+      ("Dead", vec![AnimationID::BeeDead, AnimationID::BeeBlood]),
+      ("Dead", vec![AnimationID::BeeDead, AnimationID::BeeBlood]),
+      ("Missing", vec![]),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (state, expected))| {
+      sprites.set_state(state.to_string(), &atlas);
+      let ids: Vec<_> =
+        sprites.get_sprites().iter().map(Sprite::get_id).collect();
+      assert_eq!(sprites.get_state(), state, "Case {} failed.", i);
+      assert_eq!(&ids, expected, "Case {} failed.", i);
+    });
+  }
 }
@@ -0,0 +1,33 @@
+use crate::atlas::{AnimationID, Atlas};
+use serde::{Deserialize, Serialize};
+
+/// A color remap between palette Animations (e.g., AnimationID::PaletteRed).
+/// A palette is a row of pixels in its first cel, read left to right. Each
+/// rendered color found in the source palette's first row is replaced by the
+/// color in the same column of the target palette's row. Colors absent from
+/// the source palette are unaltered.
+///
+/// The exported palette animations are single pixel swatches so a palette
+/// currently remaps one color and the only row is 0. Wider or taller palette
+/// cels remap more colors or add rows without code changes.
+#[serde(deny_unknown_fields)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpritePalette {
+  pub source: AnimationID,
+  pub target: AnimationID,
+  /// The target palette row in local pixels. Defaults to 0.
+  #[serde(default)]
+  pub row: u16,
+}
+
+impl SpritePalette {
+  /// Returns the number of rows in the target palette's first cel. Zero if the
+  /// target is missing or has no cels.
+  pub fn target_rows(&self, atlas: &Atlas) -> i16 {
+    atlas
+      .animations
+      .get(&self.target)
+      .and_then(|animation| animation.cels.first())
+      .map_or(0, |cel| cel.trim.to.y - cel.trim.from.y)
+  }
+}
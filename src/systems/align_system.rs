@@ -1,38 +1,280 @@
-use crate::components::{AlignTo, Bounds, MaxSize, Text};
-use crate::graphics::Renderer;
+use crate::components::{
+  AlignTo, Bounds, Cam, Children, Position, Renderable, WorldPosition,
+};
 use crate::graphics::Viewport;
-use crate::math::XY16;
-use crate::math::{Rect, R16};
-use crate::resources::Timing;
-use crate::sprites::{Sprite, SpriteComposition};
-use num::traits::cast::ToPrimitive;
-use specs::prelude::{ResourceId, SystemData};
+use crate::math::{R16, XY16};
 use specs::Join;
-use specs::{Read, ReadExpect, ReadStorage, System, World};
-use std::cell::RefCell;
-use std::ops::DerefMut;
-use std::rc::Rc;
-use web_sys::console;
+use specs::{Entities, Entity, ReadExpect, ReadStorage, System, WriteStorage};
 
+/// Moves every aligned Entity so that its bounds are plotted against those of
+/// its target, which may be the Viewport's cam. Must run after the camera, movement, and transforms so that
+/// targets are where they'll be rendered. Targets are aligned before the
+/// Entitys aligned to them. Only Entitys with a Position are moved. Their
+/// descendants' WorldPositions are moved with them.
 pub struct AlignSystem;
 
 impl<'a> System<'a> for AlignSystem {
-  type SystemData = (ReadStorage<'a, AlignTo>);
+  type SystemData = (
+    Entities<'a>,
+    ReadExpect<'a, Viewport>,
+    ReadStorage<'a, AlignTo>,
+    ReadStorage<'a, Bounds>,
+    ReadStorage<'a, Cam>,
+    ReadStorage<'a, Children>,
+    ReadStorage<'a, Renderable>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, WorldPosition>,
+  );
 
   fn run(&mut self, data: Self::SystemData) {
-    let (align_to) = data;
-
-    // for (bounds, text, max_wh) in (&align_to, (&max_wh).maybe()).join() {
-    //   console::log_1(
-    //     &format!(
-    //       "Hello {:?} {} {} {:?}",
-    //       &bounds,
-    //       timing.step,
-    //       text.0,
-    //       max_wh.unwrap_or(&MaxWH(WH16::from(255, 255))).0
-    //     )
-    //     .into(),
-    //   );
-    // }
+    let (
+      entities,
+      viewport,
+      align_tos,
+      bounds,
+      cams,
+      children,
+      renderables,
+      mut positions,
+      mut world_positions,
+    ) = data;
+
+    for entity in order(&entities, &align_tos) {
+      let align_to = align_tos.get(entity).expect("AlignTo component missing.");
+      if !positions.contains(entity) {
+        continue;
+      }
+      let target = if align_to.is_to_cam() {
+        Some(viewport.cam.clone())
+      } else {
+        align_to.get_to().and_then(|to| {
+          level_bounds(
+            to,
+            &viewport,
+            &bounds,
+            &cams,
+            &renderables,
+            &world_positions,
+          )
+        })
+      };
+      let target = match target {
+        Some(target) => target,
+        None => continue,
+      };
+      let aligned = level_bounds(
+        entity,
+        &viewport,
+        &bounds,
+        &cams,
+        &renderables,
+        &world_positions,
+      )
+      .unwrap_or_else(|| {
+        let origin = world_positions
+          .get(entity)
+          .map_or(XY16::new(0, 0), |world| world.position.clone());
+        R16::new_size(origin.x, origin.y, 0, 0)
+      });
+
+      let by = align_to.plot(&aligned, &target) - aligned.from;
+      if by == XY16::new(0, 0) {
+        continue;
+      }
+      if let Some(position) = positions.get_mut(entity) {
+        position.position += by.clone();
+      }
+      shift(entity, &by, &children, &mut world_positions);
+    }
+  }
+}
+
+/// Returns the aligned Entitys ordered by the length of their alignment
+/// chain, then by Entity. Cyclic chains are ordered last.
+fn order(entities: &Entities, align_tos: &ReadStorage<AlignTo>) -> Vec<Entity> {
+  let aligned: Vec<_> =
+    (entities, align_tos).join().map(|(entity, _)| entity).collect();
+  let mut ranked: Vec<_> = aligned
+    .iter()
+    .map(|entity| {
+      let mut rank = 0;
+      let mut target = *entity;
+      while let Some(to) = align_tos.get(target).and_then(AlignTo::get_to) {
+        rank += 1;
+        target = to;
+        if rank > aligned.len() {
+          break;
+        }
+      }
+      (rank, *entity)
+    })
+    .collect();
+  ranked.sort();
+  ranked.into_iter().map(|(_, entity)| entity).collect()
+}
+
+/// Returns the Viewport cam for a Cam. Otherwise, returns the Bounds or the
+/// union of the sprites, in local pixels, offset by the WorldPosition. None if
+/// the Entity has no bounds.
+fn level_bounds(
+  entity: Entity,
+  viewport: &Viewport,
+  bounds: &ReadStorage<Bounds>,
+  cams: &ReadStorage<Cam>,
+  renderables: &ReadStorage<Renderable>,
+  world_positions: &WriteStorage<WorldPosition>,
+) -> Option<R16> {
+  if cams.contains(entity) {
+    return Some(viewport.cam.clone());
+  }
+  let local = match bounds.get(entity) {
+    Some(bounds) => Some(bounds.bounds.clone()),
+    None => renderables.get(entity).and_then(|renderable| {
+      let sprites: Vec<_> = renderable
+        .sprites
+        .get_sprites()
+        .iter()
+        .map(|sprite| sprite.get_bounds().clone())
+        .collect();
+      R16::union_all(&sprites)
+    }),
+  }?;
+  Some(match world_positions.get(entity) {
+    Some(world) => local + world.position.clone(),
+    None => local,
+  })
+}
+
+/// Moves the WorldPositions of the Entity and its descendants.
+fn shift(
+  entity: Entity,
+  by: &XY16,
+  children: &ReadStorage<Children>,
+  world_positions: &mut WriteStorage<WorldPosition>,
+) {
+  let mut stack = vec![entity];
+  while let Some(entity) = stack.pop() {
+    if let Some(world) = world_positions.get_mut(entity) {
+      world.position += by.clone();
+    }
+    if let Some(children) = children.get(entity) {
+      stack.extend(&children.children);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas::{self, AnimationID};
+  use crate::components::{Alignment, Parent};
+  use crate::math::XY;
+  use crate::sprites::SpriteMap;
+  use specs::{Builder, RunNow, World, WorldExt};
+  use std::num::NonZeroU16;
+  use std::sync::Arc;
+
+  fn world() -> World {
+    let mut ecs = World::new();
+    System::setup(&mut AlignSystem, &mut ecs);
+    ecs.register::<Parent>();
+    ecs.insert(Viewport {
+      canvas_size: XY::new(170, 190),
+      scale: NonZeroU16::new(1).unwrap(),
+      cam: R16::new_size(10, 20, 17, 19),
+    });
+    ecs
+  }
+
+  fn positioned(ecs: &mut World, position: XY16) -> specs::EntityBuilder {
+    ecs
+      .create_entity()
+      .with(Position { position: position.clone() })
+      .with(WorldPosition { position })
+  }
+
+  fn world_position(ecs: &World, entity: Entity) -> XY16 {
+    ecs.read_storage::<WorldPosition>().get(entity).unwrap().position.clone()
+  }
+
+  #[test]
+  fn align_to_cam() {
+    let mut ecs = world();
+    let cam = ecs.create_entity().with(Cam { size: XY::new(17, 19) }).build();
+    let hud = positioned(&mut ecs, XY16::new(1, 1))
+      .with(Bounds { bounds: R16::new_size(0, 0, 7, 9) })
+      .with(AlignTo::new(Alignment::North, XY16::new(3, 5), Some(cam)))
+      .build();
+    let icon = positioned(&mut ecs, XY16::new(2, 2))
+      .with(Parent { parent: hud })
+      .build();
+    ecs
+      .write_storage::<Children>()
+      .insert(hud, Children { children: vec![icon] })
+      .unwrap();
+
+    AlignSystem.run_now(&ecs);
+
+    assert_eq!(
+      ecs.read_storage::<Position>().get(hud).unwrap().position,
+      XY16::new(12, 25)
+    );
+    assert_eq!(world_position(&ecs, hud), XY16::new(12, 25));
+    assert_eq!(world_position(&ecs, icon), XY16::new(13, 26));
+    assert_eq!(
+      ecs.read_storage::<Position>().get(icon).unwrap().position,
+      XY16::new(2, 2)
+    );
+  }
+
+  #[test]
+  fn align_to_sprites() {
+    let atlas =
+      atlas::parse(&include_json!("../atlas/atlas.json").unwrap()).unwrap();
+    let size = atlas.animations[&AnimationID::Bee].size.clone();
+    let (w, h) = (size.x as i16, size.y as i16);
+    let mut ecs = world();
+    let sprites =
+      from_json!({"Default": [{"id": "Bee"}, {"id": "Bee", "x": 10, "y": 1}]})
+        .unwrap();
+    let target = positioned(&mut ecs, XY16::new(100, 100))
+      .with(Renderable { sprites: SpriteMap::new(Arc::new(sprites), &atlas) })
+      .build();
+    let aligned = positioned(&mut ecs, XY16::new(0, 0))
+      .with(AlignTo::new(Alignment::SouthEast, XY16::new(0, 0), Some(target)))
+      .build();
+
+    AlignSystem.run_now(&ecs);
+
+    assert_eq!(
+      world_position(&ecs, aligned),
+      XY16::new(100 + 10 + w, 100 + 1 + h)
+    );
+  }
+
+  #[test]
+  fn align_chains_in_order() {
+    let mut ecs = world();
+    // Created first but aligned to the Entity aligned to the cam.
+    let label = positioned(&mut ecs, XY16::new(0, 0))
+      .with(Bounds { bounds: R16::new_size(0, 0, 1, 1) })
+      .with(AlignTo::new(Alignment::NorthWest, XY16::new(1, 1), None))
+      .build();
+    let cam = ecs.create_entity().with(Cam { size: XY::new(17, 19) }).build();
+    let panel = positioned(&mut ecs, XY16::new(0, 0))
+      .with(Bounds { bounds: R16::new_size(0, 0, 4, 4) })
+      .with(AlignTo::new(Alignment::NorthWest, XY16::new(2, 2), Some(cam)))
+      .build();
+    ecs.write_storage::<AlignTo>().get_mut(label).unwrap().set_to(Some(panel));
+    let cycle = positioned(&mut ecs, XY16::new(0, 0))
+      .with(AlignTo::new(Alignment::Center, XY16::new(0, 0), None))
+      .build();
+    ecs.write_storage::<AlignTo>().get_mut(cycle).unwrap().set_to(Some(cycle));
+
+    AlignSystem.run_now(&ecs);
+
+    assert_eq!(world_position(&ecs, panel), XY16::new(12, 22));
+    assert_eq!(world_position(&ecs, label), XY16::new(13, 23));
+    assert_eq!(world_position(&ecs, cycle), XY16::new(0, 0));
   }
 }
@@ -0,0 +1,44 @@
+use crate::atlas::Atlas;
+use crate::components::{Collision, Renderable};
+use crate::resources::{AnimationEvents, Timing};
+use specs::Join;
+use specs::{Entities, ReadExpect, System, Write, WriteStorage};
+use std::rc::Rc;
+
+/// Advances the Animators of rendered sprites and records their events. For
+/// entities that collide, the Collision Cel bodies are replaced with those of
+/// the current Cels.
+pub struct AnimatorSystem;
+
+impl<'a> System<'a> for AnimatorSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadExpect<'a, Rc<Atlas>>,
+    ReadExpect<'a, Timing>,
+    Write<'a, AnimationEvents>,
+    WriteStorage<'a, Renderable>,
+    WriteStorage<'a, Collision>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (entities, atlas, timing, mut events, mut renderables, mut collisions) =
+      data;
+
+    events.events.clear();
+    for (entity, renderable, collision) in
+      (&entities, &mut renderables, (&mut collisions).maybe()).join()
+    {
+      let sprites = renderable.sprites.get_sprites_mut();
+      for sprite in sprites.iter_mut() {
+        let id = sprite.get_id();
+        for event in sprite.animate(&atlas, timing.delta) {
+          events.events.push((entity, id, event));
+        }
+      }
+      if let Some(collision) = collision {
+        collision.cel_bodies =
+          sprites.iter().flat_map(|sprite| sprite.get_bodies(&atlas)).collect();
+      }
+    }
+  }
+}
@@ -0,0 +1,311 @@
+use crate::components::WorldPosition;
+use crate::graphics::Viewport;
+use crate::levels::LevelProperties;
+use crate::math::{XY, XY16};
+use crate::resources::{CamFollow, Timing};
+use specs::{Read, ReadExpect, ReadStorage, System, WriteExpect};
+use std::convert::TryFrom;
+
+/// Moves the Viewport's cam to keep the CamFollow target within the dead zone,
+/// easing the way there, and then clamps the cam to the level bounds. A cam
+/// larger than the level is centered on it instead. Must run after the target
+/// has moved and transformed and before anything aligned to the cam.
+#[derive(Default)]
+pub struct CamSystem {
+  /// The subpixel position of the cam. Resynchronized whenever the cam is
+  /// moved elsewhere, e.g., by a level load.
+  position: Option<XY<f64>>,
+}
+
+impl<'a> System<'a> for CamSystem {
+  type SystemData = (
+    ReadExpect<'a, Timing>,
+    Read<'a, CamFollow>,
+    Option<Read<'a, LevelProperties>>,
+    ReadStorage<'a, WorldPosition>,
+    WriteExpect<'a, Viewport>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (timing, follow, properties, world_positions, mut viewport) = data;
+
+    let cam = &viewport.cam;
+    let from = XY::<f64>::from(cam.from.clone());
+    let position = match &self.position {
+      Some(position) if round(position) == cam.from => position.clone(),
+      _ => from.clone(),
+    };
+    let size = XY::<f64>::from(cam.size());
+
+    let target = follow
+      .target
+      .and_then(|target| world_positions.get(target))
+      .map(|world| XY::<f64>::from(world.position.clone()));
+    let dead_zone = XY::<f64>::from(follow.dead_zone.clone());
+    let mut goal = match &target {
+      Some(target) => XY::new(
+        track(from.x, size.x, dead_zone.x, target.x),
+        track(from.y, size.y, dead_zone.y, target.y),
+      ),
+      None => from,
+    };
+    if let Some(properties) = &properties {
+      let min = XY::<f64>::from(properties.bounds.from.clone());
+      let max = XY::<f64>::from(properties.bounds.to.clone());
+      goal = XY::new(
+        bound(goal.x, size.x, min.x, max.x),
+        bound(goal.y, size.y, min.y, max.y),
+      );
+    }
+    let goal = XY::new(goal.x.round(), goal.y.round());
+
+    let ratio = 1. - (1. - follow.easing).powf(timing.delta / 1000.);
+    let mut eased = position.lerp(&goal, ratio);
+    if follow.snap {
+      let (eased_px, goal_px) = (round(&eased), round(&goal));
+      eased = XY::new(
+        f64::from(snap(cam.from.x, eased_px.x, goal_px.x)),
+        f64::from(snap(cam.from.y, eased_px.y, goal_px.y)),
+      );
+    }
+    // The last update closes any remaining subpixel distance to the goal.
+    if round(&eased) == round(&goal) {
+      eased = goal;
+    }
+
+    let to = round(&eased);
+    self.position = Some(eased);
+    if to != viewport.cam.from {
+      viewport.cam = viewport.cam.move_to(&to);
+    }
+  }
+}
+
+/// Returns the cam position along an axis that places the target within the
+/// centered dead zone with the least movement.
+fn track(from: f64, size: f64, dead_zone: f64, target: f64) -> f64 {
+  let zone_from = from + (size - dead_zone) / 2.;
+  let zone_to = zone_from + dead_zone;
+  if target < zone_from {
+    from - (zone_from - target)
+  } else if target > zone_to {
+    from + (target - zone_to)
+  } else {
+    from
+  }
+}
+
+/// Returns the cam position along an axis within the level's [min, max) or
+/// centered on it if the cam is larger.
+fn bound(from: f64, size: f64, min: f64, max: f64) -> f64 {
+  if size >= max - min {
+    return min - (size - (max - min)) / 2.;
+  }
+  from.max(min).min(max - size)
+}
+
+/// Returns the eased pixel along an axis, moving at least one pixel from the
+/// cam toward the goal.
+fn snap(from: i16, eased: i16, goal: i16) -> i16 {
+  if eased == from {
+    return from + (goal - from).signum();
+  }
+  eased
+}
+
+fn round(position: &XY<f64>) -> XY16 {
+  XY16::try_from(XY::new(position.x.round(), position.y.round()))
+    .expect("Cam f64 to i16 conversion failed.")
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::math::{Millis, R16};
+  use specs::{Builder, RunNow, World, WorldExt};
+  use std::num::NonZeroU16;
+  use std::time::Duration;
+
+  #[test]
+  fn follow() {
+    let bounds = R16::new(-100, -100, 100, 100);
+    let rigid = (1., false);
+    let eased = (0.75, false);
+    let snapped = (0.75, true);
+    [
+      // (name, cam, target, (easing, snap), delta, updates, bounds, expected)
+      ("In dead zone.", (0, 0), Some((10, 5)), rigid, 16., 1, &bounds, (0, 0)),
+      (
+        "Right of dead zone.",
+        (0, 0),
+        Some((20, 5)),
+        rigid,
+        16.,
+        1,
+        &bounds,
+        (8, 0),
+      ),
+      (
+        "Above dead zone.",
+        (0, 0),
+        Some((10, 0)),
+        rigid,
+        16.,
+        1,
+        &bounds,
+        (0, -4),
+      ),
+      ("No target.", (3, 3), None, rigid, 16., 1, &bounds, (3, 3)),
+      (
+        "Clamped.",
+        (0, 0),
+        Some((-200, -200)),
+        rigid,
+        16.,
+        1,
+        &bounds,
+        (-100, -100),
+      ),
+      (
+        "Clamped without target.",
+        (95, 0),
+        None,
+        rigid,
+        16.,
+        1,
+        &bounds,
+        (80, 0),
+      ),
+      (
+        "Centered on narrow level.",
+        (0, 0),
+        Some((10, 5)),
+        rigid,
+        16.,
+        1,
+        &R16::new(0, 0, 10, 40),
+        (-5, 0),
+      ),
+      (
+        "Eased halfway.",
+        (0, 0),
+        Some((20, 5)),
+        eased,
+        500.,
+        1,
+        &bounds,
+        (4, 0),
+      ),
+      (
+        "Eased arrives.",
+        (0, 0),
+        Some((20, 5)),
+        eased,
+        500.,
+        4,
+        &bounds,
+        (8, 0),
+      ),
+      (
+        "Subpixel carried.",
+        (0, 0),
+        Some((20, 5)),
+        eased,
+        16.,
+        2,
+        &bounds,
+        (0, 0),
+      ),
+      (
+        "Subpixel progress.",
+        (0, 0),
+        Some((20, 5)),
+        eased,
+        16.,
+        3,
+        &bounds,
+        (1, 0),
+      ),
+      ("Snapped.", (0, 0), Some((20, 5)), snapped, 16., 3, &bounds, (3, 0)),
+      ("Zero delta.", (0, 0), Some((20, 5)), eased, 0., 1, &bounds, (0, 0)),
+    ]
+    .iter()
+    .for_each(
+      |(
+        name,
+        cam,
+        target,
+        (easing, snap),
+        delta,
+        updates,
+        bounds,
+        expected,
+      )| {
+        let mut system = CamSystem::default();
+        let mut ecs = world(cam, bounds, *delta);
+        let target = target.map(|(x, y)| {
+          ecs
+            .create_entity()
+            .with(WorldPosition { position: XY16::new(x, y) })
+            .build()
+        });
+        ecs.insert(CamFollow {
+          target,
+          dead_zone: XY::new(4, 2),
+          easing: *easing,
+          snap: *snap,
+        });
+
+        for _ in 0..*updates {
+          system.run_now(&ecs);
+        }
+
+        assert_eq!(
+          ecs.read_resource::<Viewport>().cam,
+          R16::new_size(expected.0, expected.1, 20, 10),
+          "{}",
+          name
+        );
+      },
+    );
+  }
+
+  #[test]
+  fn follow_resynchronizes() {
+    let mut system = CamSystem::default();
+    let mut ecs = world(&(0, 0), &R16::new(-100, -100, 100, 100), 16.);
+    ecs.insert(CamFollow { easing: 0.75, ..CamFollow::default() });
+    system.run_now(&ecs);
+
+    ecs.write_resource::<Viewport>().cam = R16::new_size(7, 7, 20, 10);
+    system.run_now(&ecs);
+
+    assert_eq!(
+      ecs.read_resource::<Viewport>().cam,
+      R16::new_size(7, 7, 20, 10)
+    );
+  }
+
+  /// Returns a World with a 20x10 cam.
+  fn world(cam: &(i16, i16), bounds: &R16, delta: Millis) -> World {
+    let mut ecs = World::new();
+    ecs.register::<WorldPosition>();
+    ecs.insert(Timing { play_time: Duration::default(), delta });
+    ecs.insert(Viewport {
+      canvas_size: XY::new(20, 10),
+      scale: NonZeroU16::new(1).unwrap(),
+      cam: R16::new_size(cam.0, cam.1, 20, 10),
+    });
+    ecs.insert(LevelProperties {
+      bounds: bounds.clone(),
+      min_viewport: XY::new(
+        NonZeroU16::new(20).unwrap(),
+        NonZeroU16::new(10).unwrap(),
+      ),
+      cam: XY16::new(cam.0, cam.1),
+      player: XY16::new(0, 0),
+      seed: 0,
+    });
+    ecs
+  }
+}
@@ -1 +1,647 @@
-pub struct Collider {}
+use crate::atlas::BodyKind;
+use crate::components::{
+  CollidesWith, Collision, Position, Velocity, WorldPosition,
+};
+use crate::math::{Millis, R16, XY16};
+use crate::resources::{Contact, Contacts, Timing};
+use specs::{
+  Entities, Entity, Join, ReadExpect, ReadStorage, System, Write, WriteStorage,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The broadphase grid cell size in pixels.
+const CELL: i16 = 32;
+
+/// Bodies within this many pixels of each other are near.
+pub const NEAR_MARGIN: i16 = 4;
+
+/// The fraction of movement kept while overlapping an Impediment.
+const IMPEDED: f64 = 0.5;
+
+/// Moves Entitys by their Velocity in pixels per second and records the
+/// Collision contacts. Movement is stepped a pixel at a time so that Obstacles
+/// block it exactly and is slowed while overlapping an Impediment. Only the
+/// movement bodies, Feet and Collision bodies, block or impede. Entitys are
+/// processed in Entity order so every update is deterministic.
+#[derive(Default)]
+pub struct Collider {
+  /// The subpixel movement carried to the next update by Entity.
+  remainders: HashMap<Entity, (f64, f64)>,
+}
+
+impl<'a> System<'a> for Collider {
+  type SystemData = (
+    Entities<'a>,
+    ReadExpect<'a, Timing>,
+    Write<'a, Contacts>,
+    ReadStorage<'a, Collision>,
+    ReadStorage<'a, CollidesWith>,
+    ReadStorage<'a, Velocity>,
+    ReadStorage<'a, WorldPosition>,
+    WriteStorage<'a, Position>,
+  );
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (
+      entities,
+      timing,
+      mut contacts,
+      collisions,
+      collides_withs,
+      velocities,
+      world_positions,
+      mut positions,
+    ) = data;
+
+    let mut owners: Vec<_> = (
+      &entities,
+      &collisions,
+      (&collides_withs).maybe(),
+      (&world_positions).maybe(),
+    )
+      .join()
+      .map(|(entity, collision, flags, origin)| {
+        Owner::new(entity, collision, flags, origin)
+      })
+      .collect();
+    let mut grid = Grid::default();
+    for (i, owner) in owners.iter().enumerate() {
+      grid.insert(i, owner);
+    }
+
+    let remainders = &mut self.remainders;
+    remainders.retain(|entity, _| entities.is_alive(*entity));
+    let mut moves = Vec::new();
+    for (entity, velocity, _) in (&entities, &velocities, &positions).join() {
+      let remainder = remainders.entry(entity).or_insert((0., 0.));
+      let mover =
+        owners.binary_search_by_key(&entity, |owner| owner.entity).ok();
+      let by = travel(
+        &owners,
+        &grid,
+        mover,
+        &velocity.velocity,
+        timing.delta,
+        remainder,
+      );
+      if let Some(mover) = mover {
+        owners[mover].translate(&by);
+        grid.insert(mover, &owners[mover]);
+      }
+      if by != XY16::new(0, 0) {
+        moves.push((entity, by));
+      }
+    }
+    // Only moved Positions are written so that unmoved subtrees aren't
+    // transformed.
+    for (entity, by) in moves {
+      if let Some(position) = positions.get_mut(entity) {
+        position.position += by;
+      }
+    }
+
+    let mut pairs = Vec::new();
+    for (i, owner) in owners.iter().enumerate() {
+      let bounds = match owner.near_bounds() {
+        Some(bounds) => bounds,
+        None => continue,
+      };
+      for j in grid.candidates(&bounds).into_iter().filter(|j| *j > i) {
+        let kinds = owner.contacts(&owners[j]);
+        if !kinds.is_empty() {
+          pairs.push((i, j, kinds));
+        }
+      }
+    }
+    let mut near = vec![CollidesWith::INERT; owners.len()];
+    for (i, j, _) in &pairs {
+      near[*i] |= owners[*j].flags.near();
+      near[*j] |= owners[*i].flags.near();
+    }
+    contacts.contacts = pairs
+      .into_iter()
+      .flat_map(|(i, j, kinds)| {
+        let owners = &owners;
+        let near = &near;
+        kinds.into_iter().map(move |(kinds, overlapping)| Contact {
+          entities: (owners[i].entity, owners[j].entity),
+          flags: (owners[i].flags | near[i], owners[j].flags | near[j]),
+          kinds,
+          overlapping,
+        })
+      })
+      .collect();
+  }
+}
+
+/// The Collision of an Entity in level coordinates.
+struct Owner {
+  entity: Entity,
+  flags: CollidesWith,
+  /// Collision bodies have no kind.
+  bodies: Vec<(Option<BodyKind>, R16)>,
+}
+
+impl Owner {
+  /// Bodies are offset by the WorldPosition of the last transform.
+  fn new(
+    entity: Entity,
+    collision: &Collision,
+    flags: Option<&CollidesWith>,
+    origin: Option<&WorldPosition>,
+  ) -> Self {
+    let origin =
+      origin.map_or(XY16::new(0, 0), |origin| origin.position.clone());
+    let bodies = collision
+      .bodies
+      .iter()
+      .map(|body| (None, body))
+      .chain(
+        collision.cel_bodies.iter().map(|body| (Some(body.kind), &body.bounds)),
+      )
+      .map(|(kind, body)| (kind, body.clone() + origin.clone()))
+      .collect();
+    Self { entity, flags: flags.cloned().unwrap_or_default(), bodies }
+  }
+
+  /// Returns the bodies that move the owner: Feet and Collision bodies.
+  fn movement_bodies(&self) -> impl Iterator<Item = &R16> {
+    self.bodies.iter().filter_map(|(kind, body)| match kind {
+      None | Some(BodyKind::Feet) => Some(body),
+      Some(_) => None,
+    })
+  }
+
+  /// Returns the bounds of every body plus the near margin or None if there
+  /// are no bodies.
+  fn near_bounds(&self) -> Option<R16> {
+    let bodies: Vec<_> =
+      self.bodies.iter().map(|(_, body)| body.clone()).collect();
+    R16::union_all(&bodies).map(|bounds| expand(&bounds, NEAR_MARGIN))
+  }
+
+  /// Returns true if any movement bodies overlap.
+  fn overlaps(&self, rhs: &Self) -> bool {
+    self.movement_bodies().any(|lhs| {
+      let lhs = expand(lhs, 0);
+      rhs.movement_bodies().any(|rhs| lhs.intersects(rhs))
+    })
+  }
+
+  /// Returns whether the bodies overlap by the kinds of every pair of bodies
+  /// within the near margin.
+  fn contacts(
+    &self,
+    rhs: &Self,
+  ) -> BTreeMap<(Option<BodyKind>, Option<BodyKind>), bool> {
+    let mut kinds = BTreeMap::new();
+    for (lhs_kind, lhs) in &self.bodies {
+      let near = expand(lhs, NEAR_MARGIN);
+      let lhs = expand(lhs, 0);
+      for (rhs_kind, rhs) in &rhs.bodies {
+        if near.intersects(rhs) {
+          *kinds.entry((*lhs_kind, *rhs_kind)).or_insert(false) |=
+            lhs.intersects(rhs);
+        }
+      }
+    }
+    kinds
+  }
+
+  fn translate(&mut self, by: &XY16) {
+    for (_, body) in &mut self.bodies {
+      *body = body.clone() + by.clone();
+    }
+  }
+}
+
+/// A spatial hash of owner indices by the cells their near bounds touch.
+/// Candidates may not be near but every near owner is a candidate. Moved
+/// owners are inserted again so they're found at either position.
+#[derive(Default)]
+struct Grid {
+  cells: HashMap<(i16, i16), Vec<usize>>,
+}
+
+impl Grid {
+  fn insert(&mut self, index: usize, owner: &Owner) {
+    if let Some(bounds) = owner.near_bounds() {
+      for cell in cells(&bounds) {
+        self.cells.entry(cell).or_default().push(index);
+      }
+    }
+  }
+
+  /// Returns the owner indices in ascending order.
+  fn candidates(&self, bounds: &R16) -> BTreeSet<usize> {
+    cells(bounds)
+      .filter_map(|cell| self.cells.get(&cell))
+      .flatten()
+      .cloned()
+      .collect()
+  }
+}
+
+fn cells(bounds: &R16) -> impl Iterator<Item = (i16, i16)> {
+  let bounds = bounds.order();
+  let from = (bounds.from.x.div_euclid(CELL), bounds.from.y.div_euclid(CELL));
+  let to = (bounds.to.x.div_euclid(CELL), bounds.to.y.div_euclid(CELL));
+  (from.1..=to.1).flat_map(move |y| (from.0..=to.0).map(move |x| (x, y)))
+}
+
+fn expand(rect: &R16, margin: i16) -> R16 {
+  let rect = rect.order();
+  R16::new(
+    rect.from.x.saturating_sub(margin),
+    rect.from.y.saturating_sub(margin),
+    rect.to.x.saturating_add(margin),
+    rect.to.y.saturating_add(margin),
+  )
+}
+
+/// Returns the whole pixels the mover travels this update. Subpixel movement
+/// is carried in the remainder except along an axis that's blocked.
+fn travel(
+  owners: &[Owner],
+  grid: &Grid,
+  mover: Option<usize>,
+  velocity: &XY16,
+  delta: Millis,
+  remainder: &mut (f64, f64),
+) -> XY16 {
+  let impeded = mover.map_or(false, |mover| {
+    let owner = &owners[mover];
+    owner.near_bounds().map_or(false, |bounds| {
+      grid.candidates(&bounds).into_iter().any(|i| {
+        i != mover
+          && owners[i].flags.contains(CollidesWith::IMPEDIMENT)
+          && owner.overlaps(&owners[i])
+      })
+    })
+  });
+  let scale = if impeded { IMPEDED } else { 1. };
+  let distance = |speed: i16, remainder: f64| {
+    remainder + f64::from(speed) * delta / 1000. * scale
+  };
+
+  let mut by = XY16::new(0, 0);
+  remainder.0 = advance(
+    owners,
+    grid,
+    mover,
+    &mut by,
+    &XY16::new(1, 0),
+    distance(velocity.x, remainder.0),
+  );
+  remainder.1 = advance(
+    owners,
+    grid,
+    mover,
+    &mut by,
+    &XY16::new(0, 1),
+    distance(velocity.y, remainder.1),
+  );
+  by
+}
+
+/// Steps `by` a pixel at a time along the axis of unit for the whole distance.
+/// Returns the subpixel distance remaining or zero if blocked.
+fn advance(
+  owners: &[Owner],
+  grid: &Grid,
+  mover: Option<usize>,
+  by: &mut XY16,
+  unit: &XY16,
+  distance: f64,
+) -> f64 {
+  let whole = distance.trunc();
+  let unit = unit.clone() * whole.signum() as i16;
+  let steps = whole.abs().min(f64::from(i16::max_value())) as i16;
+  for _ in 0..steps {
+    let next = by.clone() + unit.clone();
+    if mover.map_or(false, |mover| obstructed(owners, grid, mover, by, &next)) {
+      return 0.;
+    }
+    *by = next;
+  }
+  distance - whole
+}
+
+/// Returns true if moving the mover's movement bodies by `to` instead of
+/// `from` would newly overlap an Obstacle's. Obstacles already overlapped don't obstruct so
+/// that a mover caught inside one can leave.
+fn obstructed(
+  owners: &[Owner],
+  grid: &Grid,
+  mover: usize,
+  from: &XY16,
+  to: &XY16,
+) -> bool {
+  let owner = &owners[mover];
+  let bounds = match owner.near_bounds() {
+    Some(bounds) => bounds + to.clone(),
+    None => return false,
+  };
+  grid.candidates(&bounds).into_iter().any(|i| {
+    i != mover
+      && owners[i].flags.contains(CollidesWith::OBSTACLE)
+      && owner.movement_bodies().any(|body| {
+        let was = body.clone() + from.clone();
+        let is = body.clone() + to.clone();
+        owners[i]
+          .movement_bodies()
+          .any(|obstacle| is.intersects(obstacle) && !was.intersects(obstacle))
+      })
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::atlas::Body;
+  use specs::{Builder, RunNow, World, WorldExt};
+  use std::time::Duration;
+
+  fn world(collider: &mut Collider, delta: Millis) -> World {
+    let mut ecs = World::new();
+    System::setup(collider, &mut ecs);
+    ecs.insert(Timing { play_time: Duration::default(), delta });
+    ecs
+  }
+
+  fn owner(
+    ecs: &mut World,
+    position: XY16,
+    body: R16,
+    flags: CollidesWith,
+  ) -> Entity {
+    // Roots so the WorldPosition is the Position.
+    ecs
+      .create_entity()
+      .with(Position { position: position.clone() })
+      .with(WorldPosition { position })
+      .with(Collision { bodies: vec![body], cel_bodies: vec![] })
+      .with(flags)
+      .build()
+  }
+
+  #[test]
+  fn contacts() {
+    let mut collider = Collider::default();
+    let mut ecs = world(&mut collider, 0.);
+    let tree = owner(
+      &mut ecs,
+      XY16::new(0, 0),
+      R16::new_size(0, 0, 4, 4),
+      CollidesWith::OBSTACLE,
+    );
+    let bee = owner(
+      &mut ecs,
+      XY16::new(2, 2),
+      R16::new_size(0, 0, 2, 2),
+      CollidesWith::HARMFUL,
+    );
+    let bunny = owner(
+      &mut ecs,
+      XY16::new(7, 0),
+      R16::new_size(0, 0, 2, 2),
+      CollidesWith::TYPE_CHARACTER,
+    );
+    // Near across a cell boundary.
+    let stone = owner(
+      &mut ecs,
+      XY16::new(-34, 0),
+      R16::new_size(0, 0, 2, 2),
+      CollidesWith::INERT,
+    );
+    let pit = owner(
+      &mut ecs,
+      XY16::new(-30, 0),
+      R16::new_size(0, 0, 2, 2),
+      CollidesWith::FATAL,
+    );
+    owner(
+      &mut ecs,
+      XY16::new(-100, -100),
+      R16::new_size(0, 0, 2, 2),
+      CollidesWith::OBSTACLE,
+    );
+
+    collider.run_now(&ecs);
+
+    let tree_flags = CollidesWith::OBSTACLE | CollidesWith::NEAR_HARMFUL;
+    let bee_flags = CollidesWith::HARMFUL | CollidesWith::NEAR_OBSTACLE;
+    let bunny_flags = CollidesWith::TYPE_CHARACTER
+      | CollidesWith::NEAR_OBSTACLE
+      | CollidesWith::NEAR_HARMFUL;
+    assert_eq!(
+      ecs.fetch::<Contacts>().contacts,
+      vec![
+        Contact {
+          entities: (tree, bee),
+          flags: (tree_flags, bee_flags),
+          kinds: (None, None),
+          overlapping: true
+        },
+        Contact {
+          entities: (tree, bunny),
+          flags: (tree_flags, bunny_flags),
+          kinds: (None, None),
+          overlapping: false
+        },
+        Contact {
+          entities: (bee, bunny),
+          flags: (bee_flags, bunny_flags),
+          kinds: (None, None),
+          overlapping: false
+        },
+        Contact {
+          entities: (stone, pit),
+          flags: (CollidesWith::NEAR_FATAL, CollidesWith::FATAL),
+          kinds: (None, None),
+          overlapping: false
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn movement() {
+    let obstacle = |x, y| (XY16::new(x, y), CollidesWith::OBSTACLE);
+    [
+      (XY16::new(100, 0), vec![], XY16::new(10, 0)),
+      (XY16::new(-100, 50), vec![], XY16::new(-10, 5)),
+      (XY16::new(100, 0), vec![obstacle(5, 0)], XY16::new(3, 0)),
+      (XY16::new(100, 100), vec![obstacle(5, 0)], XY16::new(3, 10)),
+      (XY16::new(100, 0), vec![obstacle(1, 0)], XY16::new(10, 0)),
+      (XY16::new(0, 100), vec![obstacle(0, 12)], XY16::new(0, 10)),
+      (
+        XY16::new(100, 0),
+        vec![(XY16::new(0, 0), CollidesWith::IMPEDIMENT)],
+        XY16::new(5, 0),
+      ),
+      (
+        XY16::new(100, 0),
+        vec![(XY16::new(5, 0), CollidesWith::IMPEDIMENT)],
+        XY16::new(10, 0),
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (velocity, others, expected))| {
+      let mut collider = Collider::default();
+      let mut ecs = world(&mut collider, 100.);
+      let mover = owner(
+        &mut ecs,
+        XY16::new(0, 0),
+        R16::new_size(0, 0, 2, 2),
+        CollidesWith::TYPE_CHARACTER,
+      );
+      ecs
+        .write_storage::<Velocity>()
+        .insert(mover, Velocity { velocity: velocity.clone() })
+        .unwrap();
+      for (position, flags) in others {
+        owner(&mut ecs, position.clone(), R16::new_size(0, 0, 2, 2), *flags);
+      }
+
+      collider.run_now(&ecs);
+
+      assert_eq!(
+        ecs.read_storage::<Position>().get(mover).unwrap().position,
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  fn cel_owner(
+    ecs: &mut World,
+    position: XY16,
+    bodies: &[(BodyKind, R16)],
+    flags: CollidesWith,
+  ) -> Entity {
+    let cel_bodies = bodies
+      .iter()
+      .map(|(kind, bounds)| Body { kind: *kind, bounds: bounds.clone() })
+      .collect();
+    ecs
+      .create_entity()
+      .with(Position { position: position.clone() })
+      .with(WorldPosition { position })
+      .with(Collision { bodies: vec![], cel_bodies })
+      .with(flags)
+      .build()
+  }
+
+  #[test]
+  fn contact_kinds() {
+    let mut collider = Collider::default();
+    let mut ecs = world(&mut collider, 0.);
+    let knight = cel_owner(
+      &mut ecs,
+      XY16::new(0, 0),
+      &[
+        (BodyKind::Feet, R16::new_size(0, 0, 2, 2)),
+        (BodyKind::Hitbox, R16::new_size(2, 0, 4, 2)),
+      ],
+      CollidesWith::TYPE_CHARACTER,
+    );
+    let bee = cel_owner(
+      &mut ecs,
+      XY16::new(5, 0),
+      &[(BodyKind::Hurtbox, R16::new_size(0, 0, 2, 2))],
+      CollidesWith::HARMFUL,
+    );
+
+    collider.run_now(&ecs);
+
+    let knight_flags =
+      CollidesWith::TYPE_CHARACTER | CollidesWith::NEAR_HARMFUL;
+    let contact = |kinds, overlapping| Contact {
+      entities: (knight, bee),
+      flags: (knight_flags, CollidesWith::HARMFUL),
+      kinds,
+      overlapping,
+    };
+    assert_eq!(
+      ecs.fetch::<Contacts>().contacts,
+      vec![
+        contact((Some(BodyKind::Hitbox), Some(BodyKind::Hurtbox)), true),
+        contact((Some(BodyKind::Feet), Some(BodyKind::Hurtbox)), false),
+      ]
+    );
+  }
+
+  #[test]
+  fn movement_bodies() {
+    let hitbox = (BodyKind::Hitbox, R16::new_size(2, 0, 2, 2));
+    let obstacle = (BodyKind::Feet, R16::new_size(2, 0, 2, 2));
+    [
+      // The mover's Hitbox would be blocked at 1.
+      (vec![obstacle], CollidesWith::OBSTACLE, XY16::new(3, 0)),
+      (vec![hitbox.clone()], CollidesWith::OBSTACLE, XY16::new(10, 0)),
+      // Overlaps the mover's Feet.
+      (
+        vec![(BodyKind::Hitbox, R16::new_size(-2, 0, 2, 2))],
+        CollidesWith::IMPEDIMENT,
+        XY16::new(10, 0),
+      ),
+      (
+        vec![(BodyKind::Feet, R16::new_size(-2, 0, 2, 2))],
+        CollidesWith::IMPEDIMENT,
+        XY16::new(5, 0),
+      ),
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, (bodies, flags, expected))| {
+      let mut collider = Collider::default();
+      let mut ecs = world(&mut collider, 100.);
+      let mover = cel_owner(
+        &mut ecs,
+        XY16::new(0, 0),
+        &[(BodyKind::Feet, R16::new_size(0, 0, 2, 2)), hitbox.clone()],
+        CollidesWith::TYPE_CHARACTER,
+      );
+      ecs
+        .write_storage::<Velocity>()
+        .insert(mover, Velocity { velocity: XY16::new(100, 0) })
+        .unwrap();
+      cel_owner(&mut ecs, XY16::new(3, 0), bodies, *flags);
+
+      collider.run_now(&ecs);
+
+      assert_eq!(
+        ecs.read_storage::<Position>().get(mover).unwrap().position,
+        *expected,
+        "Case {} failed.",
+        i
+      );
+    });
+  }
+
+  #[test]
+  fn subpixel_movement() {
+    let mut collider = Collider::default();
+    let mut ecs = world(&mut collider, 100.);
+    let entity = ecs
+      .create_entity()
+      .with(Position { position: XY16::new(0, 0) })
+      .with(Velocity { velocity: XY16::new(15, -15) })
+      .build();
+
+    let positions: Vec<_> = (0..3)
+      .map(|_| {
+        collider.run_now(&ecs);
+        ecs.read_storage::<Position>().get(entity).unwrap().position.clone()
+      })
+      .collect();
+
+    assert_eq!(
+      positions,
+      vec![XY16::new(1, -1), XY16::new(3, -3), XY16::new(4, -4)]
+    );
+  }
+}
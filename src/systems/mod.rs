@@ -1,9 +1,15 @@
 mod align_system;
+mod animator_system;
+mod cam_system;
 mod collider;
 mod input_processor_system;
 mod renderer_system;
+mod transform_system;
 
 pub use align_system::*;
+pub use animator_system::*;
+pub use cam_system::*;
 pub use collider::*;
 pub use input_processor_system::*;
 pub use renderer_system::*;
+pub use transform_system::*;
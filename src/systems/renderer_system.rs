@@ -1,5 +1,5 @@
 use crate::atlas::Atlas;
-use crate::components::{Bounds, MaxSize, Renderable, Text};
+use crate::components::{Bounds, MaxSize, Renderable, Text, WorldPosition};
 use crate::graphics::Renderer;
 use crate::graphics::Viewport;
 use crate::resources::Timing;
@@ -22,7 +22,8 @@ pub struct RenderData<'a> {
   // bounds: ReadStorage<'a, Bounds>,
   // text: ReadStorage<'a, Text>,
   // max_size: ReadStorage<'a, MaxWH>,
-  sprites: ReadStorage<'a, Renderable<String>>,
+  sprites: ReadStorage<'a, Renderable>,
+  world_positions: ReadStorage<'a, WorldPosition>,
 }
 
 impl<'a> System<'a> for RendererSystem {
@@ -38,16 +39,32 @@ impl<'a> System<'a> for RendererSystem {
       // text,
       // max_size,
       sprites,
+      world_positions,
     } = data;
 
     let mut bin_config = bincode::config();
     bin_config.native_endian();
-    let bytes: Vec<u8> = (&sprites).join().fold(vec![], |mut bytes, sprite| {
-      for sprite in &sprite.sprites["Default"] {
-        bytes.append(&mut sprite.serialize(&mut bin_config, &atlas).unwrap());
-      }
-      bytes
-    });
+    // Sprites are local to the WorldPosition, if any.
+    let bytes: Vec<u8> = (&sprites, (&world_positions).maybe()).join().fold(
+      vec![],
+      |mut bytes, (sprite, world_position)| {
+        for sprite in sprite.sprites.get_sprites() {
+          let mut sprite = sprite.clone();
+          if let Some(world_position) = world_position {
+            sprite.move_by(&world_position.position);
+          }
+          // Blueprints are validated so this is unexpected. Skip the sprite
+          // instead of halting every frame.
+          match sprite.serialize(&mut bin_config, &atlas) {
+            Ok(mut sprite_bytes) => bytes.append(&mut sprite_bytes),
+            Err(error) => {
+              console::log_1(&format!("Sprite unrenderable: {}.", error).into())
+            }
+          }
+        }
+        bytes
+      },
+    );
 
     let mut renderer = renderer.borrow_mut();
     renderer.render(
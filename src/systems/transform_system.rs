@@ -0,0 +1,268 @@
+use crate::components::{Children, Parent, Position, WorldPosition};
+use crate::math::XY16;
+use specs::prelude::SystemData;
+use specs::storage::ComponentEvent;
+use specs::{
+  BitSet, Entities, Entity, Join, ReadStorage, ReaderId, System, World,
+  WriteStorage,
+};
+
+/// Computes the WorldPosition of every positioned Entity by summing its
+/// Position with those of its ancestors. Only the subtrees of Positions or
+/// Parents inserted, modified, or removed since the last update are
+/// recomputed.
+/// Entitys without a Position have no WorldPosition and pass their parent's
+/// through to their children. Trees are walked iteratively so depth is
+/// unbounded.
+#[derive(Default)]
+pub struct TransformSystem {
+  positions_reader: Option<ReaderId<ComponentEvent>>,
+  parents_reader: Option<ReaderId<ComponentEvent>>,
+  changed: BitSet,
+}
+
+impl<'a> System<'a> for TransformSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Parent>,
+    ReadStorage<'a, Children>,
+    ReadStorage<'a, Position>,
+    WriteStorage<'a, WorldPosition>,
+  );
+
+  fn setup(&mut self, ecs: &mut World) {
+    Self::SystemData::setup(ecs);
+    self.positions_reader =
+      Some(WriteStorage::<Position>::fetch(ecs).register_reader());
+    self.parents_reader =
+      Some(WriteStorage::<Parent>::fetch(ecs).register_reader());
+  }
+
+  fn run(&mut self, data: Self::SystemData) {
+    let (entities, parents, children, positions, mut world_positions) = data;
+
+    self.changed.clear();
+    let positions_reader =
+      self.positions_reader.as_mut().expect("TransformSystem not set up.");
+    let parents_reader =
+      self.parents_reader.as_mut().expect("TransformSystem not set up.");
+    for event in positions
+      .channel()
+      .read(positions_reader)
+      .chain(parents.channel().read(parents_reader))
+    {
+      match event {
+        ComponentEvent::Inserted(id)
+        | ComponentEvent::Modified(id)
+        | ComponentEvent::Removed(id) => self.changed.add(*id),
+      };
+    }
+
+    // Roots of the changed subtrees in Entity order. Changed Entitys with a
+    // changed ancestor are transformed with the ancestor's subtree.
+    let mut roots = Vec::new();
+    for (entity, _) in (&entities, &self.changed).join() {
+      let mut origin = XY16::new(0, 0);
+      let mut ancestor = entity;
+      let mut covered = false;
+      while let Some(Parent { parent }) = parents.get(ancestor) {
+        if self.changed.contains(parent.id()) && entities.is_alive(*parent) {
+          covered = true;
+          break;
+        }
+        if let Some(position) = positions.get(*parent) {
+          origin += position.position.clone();
+        }
+        ancestor = *parent;
+      }
+      if !covered {
+        roots.push((entity, origin));
+      }
+    }
+
+    for (root, origin) in roots {
+      transform(root, origin, &children, &positions, &mut world_positions);
+    }
+  }
+}
+
+/// Computes the WorldPosition of the Entity and its descendants given the
+/// world position of its parent.
+fn transform(
+  root: Entity,
+  origin: XY16,
+  children: &ReadStorage<Children>,
+  positions: &ReadStorage<Position>,
+  world_positions: &mut WriteStorage<WorldPosition>,
+) {
+  let mut stack: Vec<(Entity, XY16)> = vec![(root, origin)];
+  while let Some((entity, origin)) = stack.pop() {
+    let world = match positions.get(entity) {
+      Some(position) => {
+        let world = origin + position.position.clone();
+        world_positions
+          .insert(entity, WorldPosition { position: world.clone() })
+          .expect("WorldPosition component not inserted.");
+        world
+      }
+      None => {
+        world_positions.remove(entity);
+        origin
+      }
+    };
+    if let Some(children) = children.get(entity) {
+      // Reversed so that children are popped in order.
+      for child in children.children.iter().rev() {
+        stack.push((*child, world.clone()));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use specs::{Builder, RunNow, WorldExt};
+
+  /// Returns the Entitys of a chain of the Positions. None is unpositioned.
+  fn chain(ecs: &mut World, positions: &[Option<XY16>]) -> Vec<Entity> {
+    let mut chain: Vec<Entity> = Vec::new();
+    for position in positions {
+      let mut builder = ecs.create_entity();
+      if let Some(position) = position {
+        builder = builder.with(Position { position: position.clone() });
+      }
+      if let Some(parent) = chain.last() {
+        builder = builder.with(Parent { parent: *parent });
+      }
+      let entity = builder.build();
+      if let Some(parent) = chain.last() {
+        ecs
+          .write_storage::<Children>()
+          .insert(*parent, Children { children: vec![entity] })
+          .unwrap();
+      }
+      chain.push(entity);
+    }
+    chain
+  }
+
+  fn world_positions(ecs: &World, entities: &[Entity]) -> Vec<Option<XY16>> {
+    let world_positions = ecs.read_storage::<WorldPosition>();
+    entities
+      .iter()
+      .map(|entity| {
+        world_positions.get(*entity).map(|world| world.position.clone())
+      })
+      .collect()
+  }
+
+  #[test]
+  fn transform_tree() {
+    let mut system = TransformSystem::default();
+    let mut ecs = World::new();
+    System::setup(&mut system, &mut ecs);
+    let entities = chain(
+      &mut ecs,
+      &[
+        Some(XY16::new(10, 20)),
+        Some(XY16::new(1, 2)),
+        None,
+        Some(XY16::new(3, 3)),
+      ],
+    );
+
+    system.run_now(&ecs);
+
+    assert_eq!(
+      world_positions(&ecs, &entities),
+      vec![
+        Some(XY16::new(10, 20)),
+        Some(XY16::new(11, 22)),
+        None,
+        Some(XY16::new(14, 25))
+      ]
+    );
+
+    ecs.write_storage::<Position>().get_mut(entities[0]).unwrap().position =
+      XY16::new(0, 0);
+    ecs.write_storage::<Position>().remove(entities[1]);
+    system.run_now(&ecs);
+
+    assert_eq!(
+      world_positions(&ecs, &entities),
+      vec![Some(XY16::new(0, 0)), None, None, Some(XY16::new(3, 3))]
+    );
+  }
+
+  #[test]
+  fn transform_changed_subtrees() {
+    let mut system = TransformSystem::default();
+    let mut ecs = World::new();
+    System::setup(&mut system, &mut ecs);
+    let lhs = chain(&mut ecs, &[Some(XY16::new(1, 1)), Some(XY16::new(1, 1))]);
+    let rhs = chain(&mut ecs, &[Some(XY16::new(2, 2)), Some(XY16::new(2, 2))]);
+    system.run_now(&ecs);
+    // Overwritten only if the subtree is recomputed.
+    for entity in lhs.iter().chain(&rhs) {
+      ecs
+        .write_storage::<WorldPosition>()
+        .insert(*entity, WorldPosition { position: XY16::new(-1, -1) })
+        .unwrap();
+    }
+
+    ecs.write_storage::<Position>().get_mut(rhs[1]).unwrap().position =
+      XY16::new(5, 5);
+    system.run_now(&ecs);
+
+    assert_eq!(
+      world_positions(&ecs, &lhs),
+      vec![Some(XY16::new(-1, -1)), Some(XY16::new(-1, -1))]
+    );
+    assert_eq!(
+      world_positions(&ecs, &rhs),
+      vec![Some(XY16::new(-1, -1)), Some(XY16::new(7, 7))]
+    );
+  }
+
+  #[test]
+  fn transform_reparented() {
+    let mut system = TransformSystem::default();
+    let mut ecs = World::new();
+    System::setup(&mut system, &mut ecs);
+    let lhs = chain(&mut ecs, &[Some(XY16::new(1, 1)), Some(XY16::new(1, 1))]);
+    let rhs = chain(&mut ecs, &[Some(XY16::new(10, 10))]);
+    system.run_now(&ecs);
+
+    ecs.write_storage::<Children>().remove(lhs[0]);
+    ecs
+      .write_storage::<Children>()
+      .insert(rhs[0], Children { children: vec![lhs[1]] })
+      .unwrap();
+    ecs.write_storage::<Parent>().get_mut(lhs[1]).unwrap().parent = rhs[0];
+    system.run_now(&ecs);
+
+    assert_eq!(world_positions(&ecs, &lhs[1..]), vec![Some(XY16::new(11, 11))]);
+
+    ecs.write_storage::<Children>().remove(rhs[0]);
+    ecs.write_storage::<Parent>().remove(lhs[1]);
+    system.run_now(&ecs);
+
+    assert_eq!(world_positions(&ecs, &lhs[1..]), vec![Some(XY16::new(1, 1))]);
+  }
+
+  #[test]
+  fn transform_deep_tree() {
+    let mut system = TransformSystem::default();
+    let mut ecs = World::new();
+    System::setup(&mut system, &mut ecs);
+    let entities = chain(&mut ecs, &vec![Some(XY16::new(1, -1)); 10_000]);
+
+    system.run_now(&ecs);
+
+    assert_eq!(
+      world_positions(&ecs, &entities[entities.len() - 1..]),
+      vec![Some(XY16::new(10_000, -10_000))]
+    );
+  }
+}
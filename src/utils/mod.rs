@@ -2,6 +2,8 @@
 pub mod assert;
 pub mod array_util;
 pub mod fn_util;
+pub mod png;
+pub mod zlib;
 
 #[cfg(test)]
 macro_rules! from_json {
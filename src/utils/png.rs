@@ -0,0 +1,85 @@
+//! A minimal [PNG] encoder for 8-bit RGBA images.
+//!
+//! [PNG]: https://www.w3.org/TR/PNG/
+
+use super::zlib;
+
+static SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Truecolor with alpha.
+const COLOR_TYPE_RGBA: u8 = 6;
+
+/// Returns the PNG file for row-major RGBA pixels.
+pub fn encode(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+  assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+  let mut header = Vec::new();
+  header.extend_from_slice(&width.to_be_bytes());
+  header.extend_from_slice(&height.to_be_bytes());
+  // Bit depth, color type, and default compression, filter, and interlace.
+  header.extend_from_slice(&[8, COLOR_TYPE_RGBA, 0, 0, 0]);
+
+  // Every scanline is prefixed with filter type none.
+  let mut scanlines = Vec::with_capacity(pixels.len() + height as usize);
+  if width > 0 {
+    for row in pixels.chunks(width as usize * 4) {
+      scanlines.push(0);
+      scanlines.extend_from_slice(row);
+    }
+  }
+
+  let mut png = SIGNATURE.to_vec();
+  write_chunk(&mut png, b"IHDR", &header);
+  write_chunk(&mut png, b"IDAT", &zlib::deflate(&scanlines));
+  write_chunk(&mut png, b"IEND", &[]);
+  png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+  png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  let start = png.len();
+  png.extend_from_slice(kind);
+  png.extend_from_slice(data);
+  let crc = crc32(&png[start..]);
+  png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+  !bytes.iter().fold(!0, |crc, &byte| {
+    (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+      if crc & 1 == 1 {
+        0xedb8_8320 ^ crc >> 1
+      } else {
+        crc >> 1
+      }
+    })
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn crc32_iend() {
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+  }
+
+  #[test]
+  fn encode_pixel() {
+    let png = encode(1, 1, &[1, 2, 3, 4]);
+    assert_eq!(png[..8], SIGNATURE);
+    // IHDR.
+    assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+    assert_eq!(png[16..29], [0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+    // IDAT.
+    let len = u32::from_be_bytes([png[33], png[34], png[35], png[36]]);
+    assert_eq!(png[37..41], *b"IDAT");
+    let data = &png[41..41 + len as usize];
+    assert_eq!(zlib::inflate(data).unwrap(), vec![0, 1, 2, 3, 4]);
+    // IEND.
+    assert_eq!(
+      png[png.len() - 12..],
+      [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+    );
+  }
+}
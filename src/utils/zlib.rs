@@ -0,0 +1,512 @@
+//! A minimal [zlib] decoder sufficient for the compressed cels of Aseprite
+//! files and an encoder sufficient for the packed atlas image. The
+//! implementation favors brevity over speed. Decoding follows the canonical
+//! Huffman decoding of zlib's puff reference and encoding uses only the fixed
+//! Huffman codes. See the [deflate] specification.
+//!
+//! [zlib]: https://tools.ietf.org/html/rfc1950
+//! [deflate]: https://tools.ietf.org/html/rfc1951
+
+/// The maximum bit length of any Huffman code.
+const MAX_BITS: usize = 15;
+
+static LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67,
+  83, 99, 115, 131, 163, 195, 227, 258,
+];
+static LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5,
+  5, 5, 0,
+];
+static DISTANCE_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+  769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+static DISTANCE_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11,
+  11, 12, 12, 13, 13,
+];
+/// The order code length code lengths are transmitted in dynamic blocks.
+static CODE_LENGTH_ORDER: [usize; 19] =
+  [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// The maximum distance of a match.
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// The most match candidates considered per byte.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+
+/// Returns the decompressed zlib stream. The Adler-32 checksum is verified.
+pub fn inflate(bytes: &[u8]) -> Result<Vec<u8>, String> {
+  if bytes.len() < 2 {
+    return Err("zlib header truncated.".to_string());
+  }
+  let (cmf, flg) = (bytes[0], bytes[1]);
+  if cmf & 0x0f != 8 {
+    return Err("zlib compression method is not deflate.".to_string());
+  }
+  if (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+    return Err("zlib header check failed.".to_string());
+  }
+  if flg & 0x20 != 0 {
+    return Err("zlib preset dictionaries are unsupported.".to_string());
+  }
+
+  let mut reader = BitReader::new(&bytes[2..]);
+  let data = inflate_raw(&mut reader)?;
+
+  let trailer = reader.align();
+  let checksum = bytes
+    .get(2 + trailer..2 + trailer + 4)
+    .ok_or("zlib Adler-32 checksum truncated.")?;
+  let checksum =
+    u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+  if checksum != adler32(&data) {
+    return Err("zlib Adler-32 checksum mismatch.".to_string());
+  }
+  Ok(data)
+}
+
+/// Returns the zlib stream of the bytes compressed as a single fixed Huffman
+/// block. Output is deterministic.
+pub fn deflate(bytes: &[u8]) -> Vec<u8> {
+  // Deflate with a 32 KiB window and the default compression level flag.
+  let mut writer = BitWriter { bytes: vec![0x78, 0x9c], buffer: 0, len: 0 };
+  writer.bits(1, 1); // Final block.
+  writer.bits(1, 2); // Fixed Huffman codes.
+
+  // The most recent position of each hash and the previous position of each
+  // byte with the same hash.
+  let mut head = vec![usize::max_value(); 1 << HASH_BITS];
+  let mut prev = vec![usize::max_value(); bytes.len()];
+  let mut i = 0;
+  while i < bytes.len() {
+    let (len, distance) = longest_match(bytes, i, &head, &prev);
+    let end = if len >= MIN_MATCH {
+      writer.length(len);
+      writer.distance(distance);
+      i + len
+    } else {
+      writer.literal(u16::from(bytes[i]));
+      i + 1
+    };
+    while i < end {
+      if i + MIN_MATCH <= bytes.len() {
+        let hash = hash(&bytes[i..]);
+        prev[i] = head[hash];
+        head[hash] = i;
+      }
+      i += 1;
+    }
+  }
+  writer.literal(256);
+
+  writer.align();
+  writer.bytes.extend_from_slice(&adler32(bytes).to_be_bytes());
+  writer.bytes
+}
+
+fn hash(bytes: &[u8]) -> usize {
+  let hash = usize::from(bytes[0]) << 10
+    ^ usize::from(bytes[1]) << 5
+    ^ usize::from(bytes[2]);
+  hash & ((1 << HASH_BITS) - 1)
+}
+
+/// Returns the length and distance of the longest prior match at i.
+fn longest_match(
+  bytes: &[u8],
+  i: usize,
+  head: &[usize],
+  prev: &[usize],
+) -> (usize, usize) {
+  if i + MIN_MATCH > bytes.len() {
+    return (0, 0);
+  }
+  let max = MAX_MATCH.min(bytes.len() - i);
+  let (mut best_len, mut best_distance) = (0, 0);
+  let mut candidate = head[hash(&bytes[i..])];
+  for _ in 0..MAX_CHAIN {
+    if candidate == usize::max_value() || i - candidate > WINDOW {
+      break;
+    }
+    let len =
+      (0..max).take_while(|&j| bytes[candidate + j] == bytes[i + j]).count();
+    if len > best_len {
+      best_len = len;
+      best_distance = i - candidate;
+      if len == max {
+        break;
+      }
+    }
+    candidate = prev[candidate];
+  }
+  (best_len, best_distance)
+}
+
+pub fn adler32(bytes: &[u8]) -> u32 {
+  let (mut a, mut b) = (1, 0);
+  for &byte in bytes {
+    a = (a + u32::from(byte)) % 65521;
+    b = (b + a) % 65521;
+  }
+  b << 16 | a
+}
+
+/// Returns the decompressed deflate stream.
+fn inflate_raw(reader: &mut BitReader) -> Result<Vec<u8>, String> {
+  let mut out = Vec::new();
+  loop {
+    let last = reader.bits(1)? == 1;
+    match reader.bits(2)? {
+      0 => inflate_stored(reader, &mut out)?,
+      1 => {
+        let (lengths, distances) = fixed_codes();
+        inflate_codes(reader, &mut out, &lengths, &distances)?
+      }
+      2 => {
+        let (lengths, distances) = dynamic_codes(reader)?;
+        inflate_codes(reader, &mut out, &lengths, &distances)?
+      }
+      _ => return Err("Deflate block type invalid.".to_string()),
+    }
+    if last {
+      return Ok(out);
+    }
+  }
+}
+
+fn inflate_stored(
+  reader: &mut BitReader,
+  out: &mut Vec<u8>,
+) -> Result<(), String> {
+  let start = reader.align();
+  let header = reader
+    .bytes
+    .get(start..start + 4)
+    .ok_or("Deflate stored block header truncated.")?;
+  let len = u16::from_le_bytes([header[0], header[1]]);
+  let nlen = u16::from_le_bytes([header[2], header[3]]);
+  if len != !nlen {
+    return Err("Deflate stored block length mismatch.".to_string());
+  }
+  let start = start + 4;
+  let end = start + usize::from(len);
+  out.extend_from_slice(
+    reader.bytes.get(start..end).ok_or("Deflate stored block truncated.")?,
+  );
+  reader.position = end;
+  Ok(())
+}
+
+fn inflate_codes(
+  reader: &mut BitReader,
+  out: &mut Vec<u8>,
+  lengths: &Huffman,
+  distances: &Huffman,
+) -> Result<(), String> {
+  loop {
+    let symbol = usize::from(lengths.decode(reader)?);
+    if symbol < 256 {
+      out.push(symbol as u8);
+      continue;
+    }
+    if symbol == 256 {
+      return Ok(());
+    }
+
+    let symbol = symbol - 257;
+    if symbol >= LENGTH_BASE.len() {
+      return Err("Deflate length symbol invalid.".to_string());
+    }
+    let len = usize::from(LENGTH_BASE[symbol])
+      + reader.bits(LENGTH_EXTRA[symbol])? as usize;
+
+    let symbol = usize::from(distances.decode(reader)?);
+    if symbol >= DISTANCE_BASE.len() {
+      return Err("Deflate distance symbol invalid.".to_string());
+    }
+    let distance = usize::from(DISTANCE_BASE[symbol])
+      + reader.bits(DISTANCE_EXTRA[symbol])? as usize;
+    if distance > out.len() {
+      return Err("Deflate distance exceeds output.".to_string());
+    }
+
+    // The copy may overlap the bytes being appended so copy serially.
+    let start = out.len() - distance;
+    for i in 0..len {
+      out.push(out[start + i]);
+    }
+  }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+  let mut lengths = [0; 288];
+  for (i, length) in lengths.iter_mut().enumerate() {
+    *length = match i {
+      0..=143 => 8,
+      144..=255 => 9,
+      256..=279 => 7,
+      _ => 8,
+    };
+  }
+  (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+  let len_count = reader.bits(5)? as usize + 257;
+  let distance_count = reader.bits(5)? as usize + 1;
+  let code_count = reader.bits(4)? as usize + 4;
+
+  let mut code_lengths = [0; 19];
+  for &i in CODE_LENGTH_ORDER.iter().take(code_count) {
+    code_lengths[i] = reader.bits(3)? as u8;
+  }
+  let code = Huffman::new(&code_lengths);
+
+  let mut lengths = Vec::with_capacity(len_count + distance_count);
+  while lengths.len() < len_count + distance_count {
+    let (length, repeat) = match code.decode(reader)? {
+      symbol @ 0..=15 => (symbol as u8, 1),
+      16 => (
+        *lengths.last().ok_or("Deflate repeat without previous length.")?,
+        3 + reader.bits(2)?,
+      ),
+      17 => (0, 3 + reader.bits(3)?),
+      _ => (0, 11 + reader.bits(7)?),
+    };
+    for _ in 0..repeat {
+      lengths.push(length);
+    }
+  }
+  if lengths.len() != len_count + distance_count {
+    return Err("Deflate code lengths overflow.".to_string());
+  }
+  if lengths[256] == 0 {
+    return Err("Deflate end of block code missing.".to_string());
+  }
+
+  Ok((Huffman::new(&lengths[..len_count]), Huffman::new(&lengths[len_count..])))
+}
+
+/// A canonical Huffman code described by the number of codes of each bit
+/// length and the symbols ordered by code.
+struct Huffman {
+  counts: [u16; MAX_BITS + 1],
+  symbols: Vec<u16>,
+}
+
+impl Huffman {
+  fn new(lengths: &[u8]) -> Self {
+    let mut counts = [0; MAX_BITS + 1];
+    for &length in lengths {
+      counts[usize::from(length)] += 1;
+    }
+    counts[0] = 0;
+
+    let mut offsets = [0; MAX_BITS + 1];
+    for len in 1..MAX_BITS {
+      offsets[len + 1] = offsets[len] + counts[len];
+    }
+    let mut symbols = vec![0; lengths.len()];
+    for (symbol, &length) in lengths.iter().enumerate() {
+      if length != 0 {
+        let offset = &mut offsets[usize::from(length)];
+        symbols[usize::from(*offset)] = symbol as u16;
+        *offset += 1;
+      }
+    }
+    Self { counts, symbols }
+  }
+
+  fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+    // code is the bits read so far, first is the first code of the current
+    // length, and index is the index of first in symbols.
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for len in 1..=MAX_BITS {
+      code |= reader.bits(1)? as i32;
+      let count = i32::from(self.counts[len]);
+      if code - first < count {
+        return Ok(self.symbols[(index + code - first) as usize]);
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    Err("Deflate Huffman code invalid.".to_string())
+  }
+}
+
+/// Reads bits least-significant first as deflate requires.
+struct BitReader<'a> {
+  bytes: &'a [u8],
+  /// The index of the next unread byte.
+  position: usize,
+  /// Bits read but not yet consumed.
+  buffer: u32,
+  /// The number of bits in buffer.
+  len: u8,
+}
+
+impl<'a> BitReader<'a> {
+  fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes, position: 0, buffer: 0, len: 0 }
+  }
+
+  fn bits(&mut self, count: u8) -> Result<u32, String> {
+    while self.len < count {
+      let byte =
+        *self.bytes.get(self.position).ok_or("Deflate stream truncated.")?;
+      self.position += 1;
+      self.buffer |= u32::from(byte) << self.len;
+      self.len += 8;
+    }
+    let bits = self.buffer & ((1 << count) - 1);
+    self.buffer >>= count;
+    self.len -= count;
+    Ok(bits)
+  }
+
+  /// Discards any partially read byte and returns the next byte index.
+  fn align(&mut self) -> usize {
+    self.buffer = 0;
+    self.len = 0;
+    self.position
+  }
+}
+
+/// Writes bits least-significant first as deflate requires.
+struct BitWriter {
+  bytes: Vec<u8>,
+  /// Bits written but not yet flushed.
+  buffer: u32,
+  /// The number of bits in buffer.
+  len: u8,
+}
+
+impl BitWriter {
+  fn bits(&mut self, bits: u32, count: u8) {
+    self.buffer |= bits << self.len;
+    self.len += count;
+    while self.len >= 8 {
+      self.bytes.push(self.buffer as u8);
+      self.buffer >>= 8;
+      self.len -= 8;
+    }
+  }
+
+  /// Writes a Huffman code which, unlike other fields, is packed
+  /// most-significant bit first.
+  fn code(&mut self, code: u32, count: u8) {
+    let reversed =
+      (0..count).fold(0, |reversed, i| reversed << 1 | (code >> i & 1));
+    self.bits(reversed, count)
+  }
+
+  /// Writes a literal or length symbol with the fixed Huffman code.
+  fn literal(&mut self, symbol: u16) {
+    let symbol = u32::from(symbol);
+    match symbol {
+      0..=143 => self.code(0x30 + symbol, 8),
+      144..=255 => self.code(0x190 + symbol - 144, 9),
+      256..=279 => self.code(symbol - 256, 7),
+      _ => self.code(0xc0 + symbol - 280, 8),
+    }
+  }
+
+  fn length(&mut self, len: usize) {
+    let i = LENGTH_BASE.iter().rposition(|&base| usize::from(base) <= len);
+    let i = i.unwrap();
+    self.literal(257 + i as u16);
+    self.bits((len - usize::from(LENGTH_BASE[i])) as u32, LENGTH_EXTRA[i]);
+  }
+
+  fn distance(&mut self, distance: usize) {
+    let i =
+      DISTANCE_BASE.iter().rposition(|&base| usize::from(base) <= distance);
+    let i = i.unwrap();
+    self.code(i as u32, 5);
+    self.bits(
+      (distance - usize::from(DISTANCE_BASE[i])) as u32,
+      DISTANCE_EXTRA[i],
+    );
+  }
+
+  /// Flushes any partially written byte.
+  fn align(&mut self) {
+    if self.len > 0 {
+      self.bytes.push(self.buffer as u8);
+    }
+    self.buffer = 0;
+    self.len = 0;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn inflate_stored() {
+    // zlib.compress(b"hello", 0)
+    let bytes = [
+      0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+      0x06, 0x2c, 0x02, 0x15,
+    ];
+    assert_eq!(inflate(&bytes).unwrap(), b"hello".to_vec());
+  }
+
+  #[test]
+  fn inflate_fixed() {
+    // zlib.compress(b"aaaaaaaaaabcabcabc", 9)
+    let bytes = [
+      0x78, 0xda, 0x4b, 0x4c, 0x84, 0x81, 0xa4, 0x64, 0x08, 0x02, 0x00, 0x41,
+      0x04, 0x06, 0xdc,
+    ];
+    assert_eq!(inflate(&bytes).unwrap(), b"aaaaaaaaaabcabcabc".to_vec());
+  }
+
+  #[test]
+  fn inflate_bad_checksum() {
+    let bytes = [
+      0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+      0x06, 0x2c, 0x02, 0x16,
+    ];
+    assert_eq!(inflate(&bytes).is_err(), true);
+  }
+
+  #[test]
+  fn deflate_round_trip() {
+    let atlas = include_bytes!("../atlas/atlas.png");
+    [
+      &b""[..],
+      &b"a"[..],
+      &b"aaaaaaaaaabcabcabc"[..],
+      &[0; 1000][..],
+      &atlas[..],
+      &include_bytes!("../../assets/atlas/backpacker.aseprite")[..],
+    ]
+    .iter()
+    .enumerate()
+    .for_each(|(i, bytes)| {
+      assert_eq!(
+        inflate(&deflate(bytes)).unwrap(),
+        bytes.to_vec(),
+        "Case {}.",
+        i
+      )
+    });
+  }
+
+  #[test]
+  fn deflate_compresses() {
+    assert!(deflate(&[0; 1000]).len() < 100);
+  }
+
+  #[test]
+  fn adler32_wikipedia() {
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+  }
+}
@@ -1,21 +1,19 @@
-use serde::Deserialize;
+use js_sys::Uint8Array;
 use wasm_bindgen::prelude::JsValue;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, Response, Window};
 
-pub async fn fetch_json<T: for<'a> Deserialize<'a>>(
+pub async fn fetch_bytes(
   window: &Window,
   path: &str,
-) -> Result<T, JsValue> {
+) -> Result<Vec<u8>, JsValue> {
   let request = Request::new_with_str(path)?;
-  request.headers().set("Accept", "application/json")?;
+  request.headers().set("Accept", "application/octet-stream")?;
   let response: Response =
     JsFuture::from(window.fetch_with_request(&request)).await?.dyn_into()?;
-  JsFuture::from(response.json()?)
-    .await?
-    .into_serde()
-    .map_err(|error| error.to_string().into())
+  let buffer = JsFuture::from(response.array_buffer()?).await?;
+  Ok(Uint8Array::new(&buffer).to_vec())
 }
 
 pub async fn fetch_text(
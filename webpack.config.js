@@ -35,7 +35,7 @@ module.exports = (_, argv) => {
           path.resolve(__dirname, 'pkg/**/*')
         ]
       }),
-      new CopyPlugin([{context: 'src', from: '**/*.{css,glsl,html,json,png}'}]),
+      new CopyPlugin([{context: 'src', from: '**/*.{bin,css,glsl,html,json,png}'}]),
       new webpack.DefinePlugin({
         'process.env': {
           dev: JSON.stringify(argv.mode !== 'production'),